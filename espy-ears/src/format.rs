@@ -0,0 +1,851 @@
+//! Formatting of syntax trees in espy's canonical style.
+//!
+//! Each statement is placed on its own line, and the contents of blocks are indented by two spaces.
+//! A block containing nothing but a single-line expression (such as `{ x }` or `if c then a end`)
+//! stays on one line, while `match` cases, functions, and statements always begin new lines.
+//!
+//! Comments are preserved.
+//! Comments on their own line stay above the statement (or case) that they preceded,
+//! and comments at the end of a line stay at the end of their statement.
+
+use crate::*;
+
+const INDENT: &str = "  ";
+
+/// Formats a program in the canonical style.
+///
+/// The program must parse without errors; otherwise, the first error is returned.
+pub fn format(source: &str) -> Result<String, Error<'_>> {
+    let block = Block::program(&mut Lexer::from(source).peekable());
+    let mut formatter = Formatter {
+        source,
+        tokens: block.tokens(),
+        indent: 0,
+        frames: Vec::new(),
+    };
+    let mut lines = formatter.block(&block, None)?;
+    // Comments after the last token are part of its trailing trivia,
+    // and comments in a program without any tokens aren't attached to anything.
+    match formatter.tokens.last() {
+        Some(last) => lines.extend(
+            comment_lines(last.trailing_trivia(source).slice(source))
+                .skip(1)
+                .map(|line| line.map(String::from)),
+        ),
+        None => lines.extend(comment_lines(source).map(|line| line.map(String::from))),
+    }
+    Ok(formatter.render(lines))
+}
+
+/// Returns the comments within trivia, line by line.
+///
+/// Lines without a comment are `None`; the first line is the remainder of the preceding token's.
+fn comment_lines(trivia: &str) -> impl Iterator<Item = Option<&str>> {
+    trivia.split('\n').map(|line| {
+        let line = line.trim();
+        line.starts_with('#').then_some(line)
+    })
+}
+
+/// The comments collected from the tokens of a single line,
+/// which are written around it once it is complete.
+#[derive(Default)]
+struct Frame<'source> {
+    /// Comments (and blank lines, as `None`) which precede the line.
+    above: Vec<Option<&'source str>>,
+    /// Comments which follow the line.
+    after: Vec<&'source str>,
+}
+
+struct Formatter<'source> {
+    source: &'source str,
+    /// Every token of the program in order, for finding the token before another.
+    tokens: Vec<Token<'source>>,
+    indent: usize,
+    frames: Vec<Frame<'source>>,
+}
+
+impl<'source> Formatter<'source> {
+    fn validate(&self, diagnostics: &Diagnostics<'source>) -> Result<(), Error<'source>> {
+        match diagnostics.errors.first() {
+            Some(error) => Err(*error),
+            None => Ok(()),
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame<'source> {
+        self.frames
+            .last_mut()
+            .expect("tokens must be formatted within a line")
+    }
+
+    fn indentation(&self) -> String {
+        INDENT.repeat(self.indent)
+    }
+
+    /// Joins lines at the current indentation, with at most one blank line between them.
+    fn render(&self, lines: Vec<Option<String>>) -> String {
+        let indentation = self.indentation();
+        let mut output = String::new();
+        let mut blank = false;
+        for line in lines {
+            match line {
+                None => blank = !output.is_empty(),
+                Some(line) => {
+                    if blank {
+                        output.push('\n');
+                        blank = false;
+                    }
+                    output.push_str(&indentation);
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+        }
+        output
+    }
+
+    /// Formats a line, placing the comments of its tokens around it.
+    fn line(
+        &mut self,
+        lines: &mut Vec<Option<String>>,
+        f: impl FnOnce(&mut Self) -> Result<String, Error<'source>>,
+    ) -> Result<(), Error<'source>> {
+        self.frames.push(Frame::default());
+        let text = f(self);
+        let frame = self.frames.pop().expect("frame was just pushed");
+        let mut text = text?;
+        lines.extend(frame.above.into_iter().map(|line| line.map(String::from)));
+        for comment in frame.after {
+            text.push(' ');
+            text.push_str(comment);
+        }
+        lines.push(Some(text));
+        Ok(())
+    }
+
+    /// The comments and blank lines on the lines before a token.
+    fn leading(&self, token: Token<'source>) -> Vec<Option<&'source str>> {
+        let index = self
+            .tokens
+            .partition_point(|x| x.span.start < token.span.start);
+        let previous = index.checked_sub(1).map(|index| self.tokens[index]);
+        let trivia = token
+            .leading_trivia(self.source, previous)
+            .slice(self.source);
+        let lines = trivia.split('\n').count();
+        comment_lines(trivia)
+            .enumerate()
+            // The first and last lines are shared with tokens, so they can't be blank.
+            .filter(|(i, line)| line.is_some() || (*i != 0 && *i != lines - 1))
+            .map(|(_, line)| line)
+            .collect()
+    }
+
+    /// The comment at the end of a token's line.
+    fn trailing(&self, token: Token<'source>) -> Option<&'source str> {
+        comment_lines(token.trailing_trivia(self.source).slice(self.source))
+            .next()
+            .flatten()
+    }
+
+    /// Returns the text of a token, collecting its comments into the current line.
+    fn token(&mut self, token: Token<'source>) -> &'source str {
+        let leading = self.leading(token);
+        let trailing = self.trailing(token);
+        let frame = self.frame();
+        frame.above.extend(leading);
+        frame.after.extend(trailing);
+        token.origin(self.source)
+    }
+
+    /// Returns the text of a token which begins a block,
+    /// followed by its trailing comment (which stays on the same line).
+    fn opener(&mut self, token: Token<'source>) -> String {
+        let leading = self.leading(token);
+        self.frame().above.extend(leading);
+        let mut text = token.origin(self.source).to_string();
+        if let Some(comment) = self.trailing(token) {
+            text.push(' ');
+            text.push_str(comment);
+        }
+        text
+    }
+
+    /// Returns the text of a token which ends a block.
+    ///
+    /// Its leading comments are expected to have been placed at the end of the block.
+    fn closer(&mut self, token: Option<Token<'source>>) -> &'source str {
+        let Some(token) = token else {
+            return "";
+        };
+        let trailing = self.trailing(token);
+        self.frame().after.extend(trailing);
+        token.origin(self.source)
+    }
+
+    /// Formats the contents of a block as lines,
+    /// including any comments before the token which closes it.
+    fn block(
+        &mut self,
+        block: &Block<'source>,
+        closer: Option<Token<'source>>,
+    ) -> Result<Vec<Option<String>>, Error<'source>> {
+        self.validate(&block.diagnostics)?;
+        let mut lines = Vec::new();
+        for statement in &block.statements {
+            self.line(&mut lines, |this| this.statement(statement))?;
+        }
+        match &block.result {
+            BlockResult::Expression(Some(expression)) => {
+                self.line(&mut lines, |this| this.expression(expression))?;
+            }
+            BlockResult::Expression(None) => {}
+            BlockResult::Function(function) => {
+                self.line(&mut lines, |this| this.function(function))?;
+                // The function's body ends where this block does.
+                lines.extend(self.block(&function.block, closer)?);
+                return Ok(lines);
+            }
+        }
+        if let Some(closer) = closer {
+            lines.extend(
+                self.leading(closer)
+                    .into_iter()
+                    .map(|line| line.map(String::from)),
+            );
+        }
+        Ok(lines)
+    }
+
+    /// Formats the contents of a block on their own lines, one level deeper than the current line.
+    fn body(
+        &mut self,
+        block: &Block<'source>,
+        closer: Option<Token<'source>>,
+    ) -> Result<String, Error<'source>> {
+        self.indent += 1;
+        let lines = self.block(block, closer)?;
+        let body = self.render(lines);
+        self.indent -= 1;
+        Ok(body)
+    }
+
+    /// Formats a block which contains only a single-line expression (or nothing at all),
+    /// returning the expression and the comments which follow it.
+    ///
+    /// Returns `None` if the block must span multiple lines.
+    #[allow(clippy::type_complexity)]
+    fn inline(
+        &mut self,
+        block: &Block<'source>,
+        opener: Token<'source>,
+        closer: Option<Token<'source>>,
+    ) -> Result<Option<(String, Vec<&'source str>)>, Error<'source>> {
+        self.validate(&block.diagnostics)?;
+        let BlockResult::Expression(expression) = &block.result else {
+            return Ok(None);
+        };
+        if !block.statements.is_empty()
+            || self.trailing(opener).is_some()
+            || closer.is_some_and(|closer| self.leading(closer).iter().any(Option::is_some))
+        {
+            return Ok(None);
+        }
+        let Some(expression) = expression else {
+            return Ok(Some((String::new(), Vec::new())));
+        };
+        self.frames.push(Frame::default());
+        let text = self.expression(expression);
+        let frame = self.frames.pop().expect("frame was just pushed");
+        let text = text?;
+        if !frame.above.is_empty() || text.contains('\n') {
+            return Ok(None);
+        }
+        Ok(Some((text, frame.after)))
+    }
+
+    /// Formats a block between two tokens, such as `{ ... }` or `then ... end`.
+    fn delimited(
+        &mut self,
+        opener: Token<'source>,
+        block: &Block<'source>,
+        closer: Option<Token<'source>>,
+    ) -> Result<String, Error<'source>> {
+        if let Some((text, after)) = self.inline(block, opener, closer)? {
+            let open = self.token(opener);
+            self.frame().after.extend(after);
+            let close = self.closer(closer);
+            return Ok(if text.is_empty() {
+                if opener.lexigram == Lexigram::OpenBrace {
+                    format!("{open}{close}")
+                } else {
+                    format!("{open} {close}")
+                }
+            } else {
+                format!("{open} {text} {close}")
+            });
+        }
+        let open = self.opener(opener);
+        let body = self.body(block, closer)?;
+        let close = self.closer(closer);
+        Ok(format!("{open}\n{body}{}{close}", self.indentation()))
+    }
+
+    fn statement(&mut self, statement: &Statement<'source>) -> Result<String, Error<'source>> {
+        let mut text = String::new();
+        match statement {
+            Statement::Evaluation(evaluation) => {
+                self.validate(&evaluation.diagnostics)?;
+                if let Some(binding) = &evaluation.binding {
+                    text.push_str(self.token(binding.let_token));
+                    if let Some(binding) = &binding.binding {
+                        text.push(' ');
+                        text.push_str(&self.binding(binding)?);
+                    }
+                    if let Some(equals_token) = binding.equals_token {
+                        text.push(' ');
+                        text.push_str(self.token(equals_token));
+                        text.push(' ');
+                    }
+                }
+                text.push_str(&self.optional_expression(&evaluation.expression)?);
+                if let Some(else_branch) = &evaluation.else_branch {
+                    text.push(' ');
+                    text.push_str(self.token(else_branch.else_token));
+                    if let Some(then_token) = else_branch.then_token {
+                        text.push(' ');
+                        text.push_str(&self.delimited(
+                            then_token,
+                            &else_branch.block,
+                            else_branch.end_token,
+                        )?);
+                    }
+                }
+                if let Some(semicolon_token) = evaluation.semicolon_token {
+                    self.token(semicolon_token);
+                }
+            }
+            Statement::Set(set) => {
+                self.validate(&set.diagnostics)?;
+                text.push_str(self.token(set.set_token));
+                text.push(' ');
+                text.push_str(&self.optional_expression(&set.target)?);
+                if let Some(equals_token) = set.equals_token {
+                    text.push(' ');
+                    text.push_str(self.token(equals_token));
+                    text.push(' ');
+                }
+                text.push_str(&self.optional_expression(&set.expression)?);
+                if let Some(semicolon_token) = set.semicolon_token {
+                    self.token(semicolon_token);
+                }
+            }
+        }
+        text.push(';');
+        Ok(text)
+    }
+
+    fn function(&mut self, function: &Function<'source>) -> Result<String, Error<'source>> {
+        self.validate(&function.diagnostics)?;
+        let mut text = self.token(function.with_token).to_string();
+        if let Some(argument) = &function.argument {
+            text.push(' ');
+            text.push_str(&self.binding(argument)?);
+        }
+        if let Some(colon_token) = function.colon_token {
+            text.push_str(self.token(colon_token));
+            text.push(' ');
+            text.push_str(&self.optional_expression(&function.input)?);
+        }
+        if let Some(single_arrow_token) = function.single_arrow_token {
+            text.push(' ');
+            text.push_str(self.token(single_arrow_token));
+            text.push(' ');
+            text.push_str(&self.optional_expression(&function.output)?);
+        }
+        if let Some(semicolon_token) = function.semicolon_token {
+            self.token(semicolon_token);
+        }
+        text.push(';');
+        Ok(text)
+    }
+
+    fn binding(&mut self, binding: &Binding<'source>) -> Result<String, Error<'source>> {
+        self.validate(&binding.diagnostics)?;
+        let text = match &binding.method {
+            BindingMethod::Single(token) | BindingMethod::Literal(token) => {
+                self.token(*token).to_string()
+            }
+            BindingMethod::Variant(variant) => {
+                let mut text = self.token(variant.constructor).to_string();
+                for field in &variant.fields {
+                    text.push_str(self.token(field.dot_token));
+                    text.push_str(self.token(field.field));
+                }
+                if let Some(binding) = &variant.binding {
+                    text.push(' ');
+                    text.push_str(&self.binding(binding)?);
+                }
+                text
+            }
+            BindingMethod::Numeric {
+                open_paren,
+                bindings,
+                rest,
+                close_paren,
+            } => {
+                let open = self.token(*open_paren);
+                let mut elements = Vec::new();
+                for binding in bindings {
+                    elements.push(self.binding(&binding.binding)?);
+                    if let Some(comma_token) = binding.comma_token {
+                        self.token(comma_token);
+                    }
+                }
+                if let Some(rest) = rest {
+                    elements.push(self.rest_binding(rest)?);
+                }
+                let close = self.closer(*close_paren);
+                format!("{open}{}{close}", elements.join(", "))
+            }
+            BindingMethod::Named {
+                open_brace,
+                bindings,
+                rest,
+                close_brace,
+            } => {
+                let open = self.token(*open_brace);
+                let mut elements = Vec::new();
+                for binding in bindings {
+                    let mut element = self.token(binding.field).to_string();
+                    if let Some(sub_binding) = &binding.binding {
+                        element.push_str(self.token(sub_binding.colon_token));
+                        element.push(' ');
+                        element.push_str(&self.binding(&sub_binding.binding)?);
+                    }
+                    if let Some(comma_token) = binding.comma_token {
+                        self.token(comma_token);
+                    }
+                    elements.push(element);
+                }
+                if let Some(rest) = rest {
+                    elements.push(self.rest_binding(rest)?);
+                }
+                let close = self.closer(*close_brace);
+                format!("{open}{}{close}", elements.join(", "))
+            }
+        };
+        Ok(text)
+    }
+
+    fn rest_binding(&mut self, rest: &RestBinding<'source>) -> Result<String, Error<'source>> {
+        let mut text = self.token(rest.ellipses_token).to_string();
+        text.push_str(&self.binding(&rest.binding)?);
+        if let Some(comma_token) = rest.comma_token {
+            self.token(comma_token);
+        }
+        Ok(text)
+    }
+
+    fn optional_expression(
+        &mut self,
+        expression: &Option<Box<Expression<'source>>>,
+    ) -> Result<String, Error<'source>> {
+        match expression {
+            Some(expression) => self.expression(expression),
+            None => Ok(String::new()),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression<'source>) -> Result<String, Error<'source>> {
+        self.validate(&expression.diagnostics)?;
+        self.nodes(&expression.contents)
+    }
+
+    /// Formats the (postfix) nodes of an expression.
+    ///
+    /// Parentheses are only written where the source had them,
+    /// since the nodes already reflect the precedence that the source was parsed with.
+    fn nodes(&mut self, nodes: &[Node<'source>]) -> Result<String, Error<'source>> {
+        let mut operands: Vec<String> = Vec::new();
+        for node in nodes {
+            let text = match node {
+                Node::Unit(open_paren, close_paren) => {
+                    format!("{}{}", self.token(*open_paren), self.token(*close_paren))
+                }
+                Node::Bool(_, token)
+                | Node::Number(token)
+                | Node::String(token)
+                | Node::Variable(token) => self.token(*token).to_string(),
+                Node::Interpolation(interpolation) => {
+                    self.validate(&interpolation.diagnostics)?;
+                    let mut text = self.token(interpolation.start).to_string();
+                    for segment in &interpolation.segments {
+                        text.push_str(&self.optional_expression(&segment.expression)?);
+                        if let Some(string) = segment.string {
+                            text.push_str(self.token(string));
+                        }
+                    }
+                    text
+                }
+                Node::Block {
+                    label,
+                    open_brace,
+                    block,
+                    close_brace,
+                } => {
+                    let mut text = String::new();
+                    if let Some(label) = label {
+                        text.push_str(self.token(label.label));
+                        if let Some(colon_token) = label.colon_token {
+                            text.push_str(self.token(colon_token));
+                        }
+                        text.push(' ');
+                    }
+                    text.push_str(&self.delimited(*open_brace, block, *close_brace)?);
+                    text
+                }
+                Node::If(if_block) => {
+                    let mut text = self.if_chain(if_block, true)?;
+                    text.push_str(self.closer(if_block.end_token));
+                    text
+                }
+                Node::Break(break_node) => {
+                    let mut text = self.token(break_node.break_token).to_string();
+                    if let Some(label) = break_node.label {
+                        text.push(' ');
+                        text.push_str(self.token(label));
+                    }
+                    if let Some(expression) = &break_node.expression {
+                        text.push(' ');
+                        text.push_str(&self.expression(expression)?);
+                    }
+                    text
+                }
+                Node::Match(match_block) => self.match_block(match_block)?,
+                Node::Enum(enumeration) => self.enumeration(enumeration)?,
+                Node::Positive(token)
+                | Node::Negative(token)
+                | Node::Not(token)
+                | Node::Deref(token) => {
+                    let operator = self.token(*token);
+                    let operand = operands.pop().unwrap_or_default();
+                    format!("{operator}{operand}")
+                }
+                Node::Name { name, colon_token } => {
+                    let operand = operands.pop().unwrap_or_default();
+                    format!(
+                        "{}{} {operand}",
+                        self.token(*name),
+                        self.token(*colon_token)
+                    )
+                }
+                Node::Field { dot_token, index } => {
+                    let operand = operands.pop().unwrap_or_default();
+                    format!("{operand}{}{}", self.token(*dot_token), self.token(*index))
+                }
+                Node::Group {
+                    open_paren,
+                    close_paren,
+                } => {
+                    let operand = operands.pop().unwrap_or_default();
+                    format!(
+                        "{}{operand}{}",
+                        self.token(*open_paren),
+                        self.token(*close_paren)
+                    )
+                }
+                Node::Call(_) => {
+                    // Calls are implicit, and borrow the token of their argument.
+                    let right = operands.pop().unwrap_or_default();
+                    let left = operands.pop().unwrap_or_default();
+                    format!("{left} {right}")
+                }
+                Node::Tuple(token) => {
+                    let right = operands.pop().unwrap_or_default();
+                    let left = operands.pop().unwrap_or_default();
+                    format!("{left}{} {right}", self.token(*token))
+                }
+                Node::Range(token) | Node::RangeInclusive(token) => {
+                    let right = operands.pop().unwrap_or_default();
+                    let left = operands.pop().unwrap_or_default();
+                    format!("{left}{}{right}", self.token(*token))
+                }
+                Node::Pipe(token)
+                | Node::Mul(token)
+                | Node::Div(token)
+                | Node::Add(token)
+                | Node::Sub(token)
+                | Node::BitwiseAnd(token)
+                | Node::BitwiseOr(token)
+                | Node::BitwiseXor(token)
+                | Node::EqualTo(token)
+                | Node::NotEqualTo(token)
+                | Node::Greater(token)
+                | Node::GreaterEqual(token)
+                | Node::Lesser(token)
+                | Node::LesserEqual(token)
+                | Node::LogicalAnd(token)
+                | Node::LogicalOr(token) => {
+                    let right = operands.pop().unwrap_or_default();
+                    let left = operands.pop().unwrap_or_default();
+                    format!("{left} {} {right}", self.token(*token))
+                }
+            };
+            operands.push(text);
+        }
+        Ok(operands.pop().unwrap_or_default())
+    }
+
+    /// Formats an `if` block and any `else if` blocks chained to it, up to their shared `end`.
+    ///
+    /// The result ends with whatever should separate it from the `end`.
+    fn if_chain(
+        &mut self,
+        if_block: &If<'source>,
+        allow_inline: bool,
+    ) -> Result<String, Error<'source>> {
+        self.validate(&if_block.diagnostics)?;
+        let if_token = self.token(if_block.if_token);
+        let condition = self.optional_expression(&if_block.condition)?;
+        let Some(then_token) = if_block.then_token else {
+            return Ok(format!("{if_token} {condition} "));
+        };
+        let first_closer = if_block.else_token.or(if_block.end_token);
+        let chained = if_block
+            .else_kind
+            .is_some_and(|else_kind| else_kind.lexigram == Lexigram::If);
+        if allow_inline
+            && !chained
+            && let Some((first, first_after)) =
+                self.inline(&if_block.first, then_token, first_closer)?
+        {
+            let second = match (if_block.else_token, if_block.else_kind) {
+                (Some(_), Some(else_kind)) => self
+                    .inline(&if_block.second, else_kind, if_block.end_token)?
+                    .map(Some),
+                _ => Some(None),
+            };
+            if let Some(second) = second {
+                let mut text = format!("{if_token} {condition} {}", self.token(then_token));
+                self.frame().after.extend(first_after);
+                if !first.is_empty() {
+                    text.push(' ');
+                    text.push_str(&first);
+                }
+                if let (Some(else_token), Some(else_kind), Some((second, second_after))) =
+                    (if_block.else_token, if_block.else_kind, second)
+                {
+                    text.push(' ');
+                    text.push_str(self.token(else_token));
+                    text.push(' ');
+                    text.push_str(self.token(else_kind));
+                    self.frame().after.extend(second_after);
+                    if !second.is_empty() {
+                        text.push(' ');
+                        text.push_str(&second);
+                    }
+                }
+                text.push(' ');
+                return Ok(text);
+            }
+        }
+        let then = self.opener(then_token);
+        let first = self.body(&if_block.first, first_closer)?;
+        let indentation = self.indentation();
+        let mut text = format!("{if_token} {condition} {then}\n{first}{indentation}");
+        if let (Some(else_token), Some(else_kind)) = (if_block.else_token, if_block.else_kind) {
+            text.push_str(self.closer(Some(else_token)));
+            text.push(' ');
+            if chained {
+                let BlockResult::Expression(Some(expression)) = &if_block.second.result else {
+                    unreachable!("`else if` must contain an if block");
+                };
+                let [Node::If(next)] = &expression.contents[..] else {
+                    unreachable!("`else if` must contain an if block");
+                };
+                text.push_str(&self.if_chain(next, false)?);
+            } else {
+                let then = self.opener(else_kind);
+                let second = self.body(&if_block.second, if_block.end_token)?;
+                text.push_str(&format!("{then}\n{second}{indentation}"));
+            }
+        }
+        Ok(text)
+    }
+
+    fn match_block(&mut self, match_block: &Match<'source>) -> Result<String, Error<'source>> {
+        self.validate(&match_block.diagnostics)?;
+        let match_token = self.token(match_block.match_token);
+        let expression = self.optional_expression(&match_block.expression)?;
+        let then = match match_block.then_token {
+            Some(then_token) => self.opener(then_token),
+            None => String::new(),
+        };
+        self.indent += 1;
+        let mut lines = Vec::new();
+        for case in &match_block.cases {
+            self.line(&mut lines, |this| this.case(case))?;
+        }
+        if let Some(end_token) = match_block.end_token {
+            lines.extend(
+                self.leading(end_token)
+                    .into_iter()
+                    .map(|line| line.map(String::from)),
+            );
+        }
+        let cases = self.render(lines);
+        self.indent -= 1;
+        let end = self.closer(match_block.end_token);
+        Ok(format!(
+            "{match_token} {expression} {then}\n{cases}{}{end}",
+            self.indentation()
+        ))
+    }
+
+    fn case(&mut self, case: &MatchCase<'source>) -> Result<String, Error<'source>> {
+        let mut text = String::new();
+        if let Some(let_token) = case.let_token {
+            text.push_str(self.token(let_token));
+            if let Some(binding) = case.binding {
+                text.push(' ');
+                text.push_str(self.token(binding));
+            }
+            if let Some(colon_token) = case.colon_token {
+                text.push_str(self.token(colon_token));
+                text.push(' ');
+                text.push_str(&self.optional_expression(&case.ty)?);
+            }
+            if let Some(equals_token) = case.equals_token {
+                text.push(' ');
+                text.push_str(self.token(equals_token));
+                text.push(' ');
+                text.push_str(&self.optional_expression(&case.case)?);
+            }
+        } else {
+            text.push_str(&self.optional_expression(&case.case)?);
+        }
+        if let Some(if_token) = case.if_token {
+            text.push(' ');
+            text.push_str(self.token(if_token));
+            text.push(' ');
+            text.push_str(&self.optional_expression(&case.guard)?);
+        }
+        if let Some(arrow_token) = case.arrow_token {
+            text.push(' ');
+            text.push_str(self.token(arrow_token));
+            text.push(' ');
+        }
+        text.push_str(&self.optional_expression(&case.expression)?);
+        if let Some(semicolon_token) = case.semicolon_token {
+            self.token(semicolon_token);
+        }
+        text.push(';');
+        Ok(text)
+    }
+
+    /// Formats an enum on one line if it can be,
+    /// or with each of its variants on their own line.
+    fn enumeration(&mut self, enumeration: &Enum<'source>) -> Result<String, Error<'source>> {
+        self.validate(&enumeration.diagnostics)?;
+        let end_comments = enumeration
+            .end_token
+            .is_some_and(|end_token| self.leading(end_token).iter().any(Option::is_some));
+        if self.trailing(enumeration.enum_token).is_none() && !end_comments {
+            self.frames.push(Frame::default());
+            let variants = self.optional_expression(&enumeration.variants);
+            let frame = self.frames.pop().expect("frame was just pushed");
+            let variants = variants?;
+            if frame.above.is_empty() && !variants.contains('\n') {
+                let enum_token = self.token(enumeration.enum_token);
+                self.frame().after.extend(frame.after);
+                let end = self.closer(enumeration.end_token);
+                return Ok(if variants.is_empty() {
+                    format!("{enum_token} {end}")
+                } else {
+                    format!("{enum_token} {variants} {end}")
+                });
+            }
+        }
+        let enum_token = self.opener(enumeration.enum_token);
+        self.indent += 1;
+        let mut lines = Vec::new();
+        if let Some(variants) = &enumeration.variants {
+            self.validate(&variants.diagnostics)?;
+            for (variant, comma_token) in tuple_elements(&variants.contents) {
+                self.line(&mut lines, |this| {
+                    let mut text = this.nodes(variant)?;
+                    if let Some(comma_token) = comma_token {
+                        text.push_str(this.token(comma_token));
+                    }
+                    Ok(text)
+                })?;
+            }
+        }
+        if let Some(end_token) = enumeration.end_token {
+            lines.extend(
+                self.leading(end_token)
+                    .into_iter()
+                    .map(|line| line.map(String::from)),
+            );
+        }
+        let variants = self.render(lines);
+        self.indent -= 1;
+        let end = self.closer(enumeration.end_token);
+        Ok(format!(
+            "{enum_token}\n{variants}{}{end}",
+            self.indentation()
+        ))
+    }
+}
+
+/// Splits the (postfix) nodes of a tuple into those of each of its elements,
+/// along with the comma which follows each element.
+fn tuple_elements<'a, 'source>(
+    nodes: &'a [Node<'source>],
+) -> Vec<(&'a [Node<'source>], Option<Token<'source>>)> {
+    // The index of the first node of each node's operand.
+    let mut starts = Vec::with_capacity(nodes.len());
+    let mut operands = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let start = match node {
+            Node::Unit(..)
+            | Node::Bool(..)
+            | Node::Number(_)
+            | Node::String(_)
+            | Node::Interpolation(_)
+            | Node::Variable(_)
+            | Node::Block { .. }
+            | Node::If(_)
+            | Node::Break(_)
+            | Node::Match(_)
+            | Node::Enum(_) => i,
+            Node::Positive(_)
+            | Node::Negative(_)
+            | Node::Not(_)
+            | Node::Deref(_)
+            | Node::Name { .. }
+            | Node::Field { .. }
+            | Node::Group { .. } => operands.pop().unwrap_or(i),
+            _ => {
+                operands.pop();
+                operands.pop().unwrap_or(i)
+            }
+        };
+        operands.push(start);
+        starts.push(start);
+    }
+    let mut elements = Vec::new();
+    let mut end = nodes.len();
+    let mut comma = None;
+    while let Some(Node::Tuple(comma_token)) = end.checked_sub(1).map(|last| &nodes[last]) {
+        let right = starts[end - 2];
+        elements.push((&nodes[right..end - 1], comma));
+        comma = Some(*comma_token);
+        end = right;
+    }
+    elements.push((&nodes[..end], comma));
+    elements.reverse();
+    elements
+}
@@ -0,0 +1,592 @@
+//! Incremental reparsing, for editors which reparse their buffer after every change.
+//!
+//! A program's statements are parsed the same way regardless of what precedes them,
+//! so the statements on either side of an edit can be copied from the previous tree
+//! rather than lexed and parsed again.
+
+use crate::*;
+use espy_eyes::Span;
+use std::ops::Range;
+
+/// A change to source code: the text within `range` is replaced by `replacement`.
+///
+/// `range` is a range of bytes in the source before the edit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit<'a> {
+    pub range: Range<usize>,
+    pub replacement: &'a str,
+}
+
+impl Edit<'_> {
+    /// Returns `source` with this edit applied.
+    pub fn apply(&self, source: &str) -> String {
+        let mut output =
+            String::with_capacity(source.len() - self.range.len() + self.replacement.len());
+        output.push_str(&source[..self.range.start]);
+        output.push_str(self.replacement);
+        output.push_str(&source[self.range.end..]);
+        output
+    }
+}
+
+/// The remainder of a block, as found in a previous tree.
+pub(crate) struct Tail<'source> {
+    pub(crate) statements: Vec<Statement<'source>>,
+    pub(crate) result: BlockResult<'source>,
+    pub(crate) errors: Vec<Error<'source>>,
+}
+
+pub(crate) type Splice<'a, 'source> = dyn FnMut(&Statement<'source>) -> Option<Tail<'source>> + 'a;
+
+impl<'source> Block<'source> {
+    /// Parses a program after `edit` has been applied to its source,
+    /// reusing the statements of this tree which the edit did not touch.
+    ///
+    /// `self` must have been produced by [`Block::program`] (or this function)
+    /// from the source before the edit, and `lexer` must lex the source after it.
+    /// The result is identical to calling [`Block::program`] on the new source.
+    ///
+    /// Statements of the top-level block and of any function it declares with `with`
+    /// are reused if they lie entirely before or after the edit.
+    /// Reused statements are still copied, but this is much cheaper than parsing them.
+    pub fn reparse<'new>(&self, lexer: Lexer<'new>, edit: &Edit) -> Box<Block<'new>> {
+        let mut reparse = Reparse {
+            start: edit.range.start as u32,
+            old_end: edit.range.end as u32,
+            delta: edit.replacement.len() as i64 - edit.range.len() as i64,
+            boundaries: Vec::new(),
+            spliced: false,
+            trailing: Vec::new(),
+        };
+        // Record every point in the previous tree from which the rest of it could be reused.
+        let mut block = self;
+        loop {
+            for (i, statement) in block.statements.iter().enumerate() {
+                if let Some(end) = statement_end(statement) {
+                    reparse.boundaries.push((end, block, i + 1));
+                }
+            }
+            let BlockResult::Function(function) = &block.result else {
+                break;
+            };
+            block = &function.block;
+        }
+
+        let mut program = reparse.block(self, lexer, 0);
+        if reparse.spliced {
+            // The end of the program was reused, so any trailing input was too.
+            reparse.trailing = self
+                .diagnostics
+                .errors
+                .iter()
+                .filter(|error| matches!(error, Error::TrailingInput(_)))
+                .map(|error| error.relocate(reparse.delta))
+                .collect();
+        }
+        program.diagnostics.errors.append(&mut reparse.trailing);
+        program
+    }
+}
+
+struct Reparse<'old, 'source, 'new> {
+    /// The start of the edit, which is the same before and after it.
+    start: u32,
+    /// The end of the edit in the previous source.
+    old_end: u32,
+    /// How far the edit moved the source after it.
+    delta: i64,
+    /// The offsets in the previous source after which the remainder of a block may be reused,
+    /// in ascending order, along with the block and the index of the statement which follows.
+    boundaries: Vec<(u32, &'old Block<'source>, usize)>,
+    spliced: bool,
+    trailing: Vec<Error<'new>>,
+}
+
+impl<'old, 'source, 'new> Reparse<'old, 'source, 'new> {
+    /// Rebuilds `old`, which begins at `start`.
+    fn block(&mut self, old: &Block<'source>, lexer: Lexer<'new>, start: u32) -> Box<Block<'new>> {
+        let reused = old
+            .statements
+            .iter()
+            .take_while(|statement| statement_end(statement).is_some_and(|end| end < self.start))
+            .count();
+        let start = old.statements[..reused]
+            .last()
+            .and_then(statement_end)
+            .unwrap_or(start);
+        let statements = old.statements[..reused]
+            .iter()
+            .map(|statement| statement.relocate(0))
+            .collect::<Vec<_>>();
+        let errors = old.diagnostics.errors.iter().filter(|error| {
+            !matches!(error, Error::TrailingInput(_))
+                && error_start(error).is_some_and(|error_start| error_start < start)
+        });
+
+        // A function's header may be reused if the edit is within its body.
+        if reused == old.statements.len()
+            && let BlockResult::Function(function) = &old.result
+            && let Some(semicolon_token) = function.semicolon_token
+            && semicolon_token.span.end() < self.start
+        {
+            let start = semicolon_token.span.end();
+            let block = self.block(&function.block, lexer, start);
+            // The function is the end of its block,
+            // so every diagnostic in the block is either before it or within its header.
+            let errors = old
+                .diagnostics
+                .errors
+                .iter()
+                .filter(|error| !matches!(error, Error::TrailingInput(_)))
+                .map(|error| error.relocate(0))
+                .collect();
+            return Block::build(
+                Function {
+                    with_token: function.with_token.relocate(0),
+                    argument: function.argument.relocate(0),
+                    colon_token: function.colon_token.relocate(0),
+                    input: function.input.relocate(0),
+                    single_arrow_token: function.single_arrow_token.relocate(0),
+                    output: function.output.relocate(0),
+                    semicolon_token: function.semicolon_token.relocate(0),
+                    block,
+                    diagnostics: function.diagnostics.relocate(0),
+                }
+                .into(),
+                Diagnostics { errors },
+                statements,
+            );
+        }
+
+        let diagnostics = Diagnostics {
+            errors: errors.map(|error| error.relocate(0)).collect(),
+        };
+        let mut lexer = lexer.seek(start).peekable();
+        let block = Block::parse(
+            &mut lexer,
+            statements,
+            diagnostics,
+            Some(&mut |statement| self.splice(statement)),
+        );
+        if !self.spliced {
+            let mut diagnostics = Diagnostics::default();
+            if let Some(token) = diagnostics.wrap(lexer.next()) {
+                diagnostics.errors.push(Error::TrailingInput(token));
+            }
+            self.trailing = diagnostics.errors;
+        }
+        block
+    }
+
+    /// Returns the remainder of a block from the previous tree,
+    /// if it begins where `statement` ends.
+    fn splice(&mut self, statement: &Statement<'new>) -> Option<Tail<'new>> {
+        let end = i64::from(statement_end(statement)?) - self.delta;
+        if end < i64::from(self.old_end) {
+            return None;
+        }
+        let index = self
+            .boundaries
+            .binary_search_by_key(&end, |(boundary, _, _)| i64::from(*boundary))
+            .ok()?;
+        let (boundary, block, next) = self.boundaries[index];
+        self.spliced = true;
+        Some(Tail {
+            statements: block.statements[next..]
+                .iter()
+                .map(|statement| statement.relocate(self.delta))
+                .collect(),
+            result: block.result.relocate(self.delta),
+            // Diagnostics without a position are from the block's function header,
+            // which is always part of the remainder.
+            errors: block
+                .diagnostics
+                .errors
+                .iter()
+                .filter(|error| {
+                    !matches!(error, Error::TrailingInput(_))
+                        && error_start(error).is_none_or(|error_start| error_start >= boundary)
+                })
+                .map(|error| error.relocate(self.delta))
+                .collect(),
+        })
+    }
+}
+
+/// The offset after a statement,
+/// if it was properly terminated by a semicolon.
+fn statement_end(statement: &Statement) -> Option<u32> {
+    let semicolon_token = match statement {
+        Statement::Evaluation(evaluation) => evaluation.semicolon_token,
+        Statement::Set(set) => set.semicolon_token,
+    };
+    semicolon_token.map(|token| token.span.end())
+}
+
+fn error_start(error: &Error) -> Option<u32> {
+    match error {
+        Error::Lexer(error) => Some(error.span.start),
+        Error::MissingToken { actual, .. } => actual.map(|token| token.span.start),
+        Error::UnexpectedCloseParen(token)
+        | Error::UnexpectedToken(token)
+        | Error::TrailingInput(token) => Some(token.span.start),
+        Error::ExpectedExpression | Error::IncompleteExpression => None,
+    }
+}
+
+/// Copies a syntax tree for use with a new source, moving its spans by `delta` bytes.
+trait Relocate<'new> {
+    type Output;
+    fn relocate(&self, delta: i64) -> Self::Output;
+}
+
+impl<'new> Relocate<'new> for Token<'_> {
+    type Output = Token<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        let start = (i64::from(self.span.start) + delta) as u32;
+        Token::new(Span::new(start, self.span.len), self.lexigram)
+    }
+}
+
+impl<'new, T: Relocate<'new>> Relocate<'new> for Option<T> {
+    type Output = Option<T::Output>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        self.as_ref().map(|inner| inner.relocate(delta))
+    }
+}
+
+impl<'new> Relocate<'new> for lexer::Error {
+    type Output = lexer::Error;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        lexer::Error {
+            span: Span::new((i64::from(self.span.start) + delta) as u32, self.span.len),
+            kind: self.kind,
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Error<'_> {
+    type Output = Error<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        match self {
+            Error::Lexer(error) => Error::Lexer(error.relocate(delta)),
+            Error::MissingToken { expected, actual } => Error::MissingToken {
+                expected,
+                actual: actual.relocate(delta),
+            },
+            Error::ExpectedExpression => Error::ExpectedExpression,
+            Error::UnexpectedCloseParen(token) => {
+                Error::UnexpectedCloseParen(token.relocate(delta))
+            }
+            Error::UnexpectedToken(token) => Error::UnexpectedToken(token.relocate(delta)),
+            Error::TrailingInput(token) => Error::TrailingInput(token.relocate(delta)),
+            Error::IncompleteExpression => Error::IncompleteExpression,
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Diagnostics<'_> {
+    type Output = Diagnostics<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        Diagnostics {
+            errors: self
+                .errors
+                .iter()
+                .map(|error| error.relocate(delta))
+                .collect(),
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Box<Block<'_>> {
+    type Output = Box<Block<'new>>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        Block::build(
+            self.result.relocate(delta),
+            self.diagnostics.relocate(delta),
+            self.statements
+                .iter()
+                .map(|statement| statement.relocate(delta))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl<'new> Relocate<'new> for BlockResult<'_> {
+    type Output = BlockResult<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        match self {
+            BlockResult::Expression(expression) => {
+                BlockResult::Expression(expression.relocate(delta))
+            }
+            BlockResult::Function(function) => BlockResult::Function(function.relocate(delta)),
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Function<'_> {
+    type Output = Function<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        Function {
+            with_token: self.with_token.relocate(delta),
+            argument: self.argument.relocate(delta),
+            colon_token: self.colon_token.relocate(delta),
+            input: self.input.relocate(delta),
+            single_arrow_token: self.single_arrow_token.relocate(delta),
+            output: self.output.relocate(delta),
+            semicolon_token: self.semicolon_token.relocate(delta),
+            block: self.block.relocate(delta),
+            diagnostics: self.diagnostics.relocate(delta),
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Statement<'_> {
+    type Output = Statement<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        match self {
+            Statement::Evaluation(evaluation) => Statement::Evaluation(Evaluation {
+                binding: evaluation.binding.as_ref().map(|binding| LetBinding {
+                    let_token: binding.let_token.relocate(delta),
+                    binding: binding.binding.relocate(delta),
+                    equals_token: binding.equals_token.relocate(delta),
+                }),
+                expression: evaluation.expression.relocate(delta),
+                else_branch: evaluation.else_branch.as_ref().map(|else_branch| {
+                    Box::new(LetElse {
+                        else_token: else_branch.else_token.relocate(delta),
+                        then_token: else_branch.then_token.relocate(delta),
+                        block: else_branch.block.relocate(delta),
+                        end_token: else_branch.end_token.relocate(delta),
+                    })
+                }),
+                semicolon_token: evaluation.semicolon_token.relocate(delta),
+                diagnostics: evaluation.diagnostics.relocate(delta),
+            }),
+            Statement::Set(set) => Statement::Set(Set {
+                set_token: set.set_token.relocate(delta),
+                target: set.target.relocate(delta),
+                equals_token: set.equals_token.relocate(delta),
+                expression: set.expression.relocate(delta),
+                semicolon_token: set.semicolon_token.relocate(delta),
+                diagnostics: set.diagnostics.relocate(delta),
+            }),
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Binding<'_> {
+    type Output = Binding<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        let method = match &self.method {
+            BindingMethod::Single(token) => BindingMethod::Single(token.relocate(delta)),
+            BindingMethod::Literal(token) => BindingMethod::Literal(token.relocate(delta)),
+            BindingMethod::Variant(variant) => BindingMethod::Variant(Box::new(VariantBinding {
+                constructor: variant.constructor.relocate(delta),
+                fields: variant
+                    .fields
+                    .iter()
+                    .map(|field| VariantField {
+                        dot_token: field.dot_token.relocate(delta),
+                        field: field.field.relocate(delta),
+                    })
+                    .collect(),
+                binding: variant.binding.relocate(delta),
+            })),
+            BindingMethod::Numeric {
+                open_paren,
+                bindings,
+                rest,
+                close_paren,
+            } => BindingMethod::Numeric {
+                open_paren: open_paren.relocate(delta),
+                bindings: bindings
+                    .iter()
+                    .map(|binding| NumericBinding {
+                        binding: binding.binding.relocate(delta),
+                        comma_token: binding.comma_token.relocate(delta),
+                    })
+                    .collect(),
+                rest: rest.relocate(delta),
+                close_paren: close_paren.relocate(delta),
+            },
+            BindingMethod::Named {
+                open_brace,
+                bindings,
+                rest,
+                close_brace,
+            } => BindingMethod::Named {
+                open_brace: open_brace.relocate(delta),
+                bindings: bindings
+                    .iter()
+                    .map(|binding| NamedBinding {
+                        field: binding.field.relocate(delta),
+                        binding: binding.binding.as_ref().map(|binding| NamedSubBinding {
+                            colon_token: binding.colon_token.relocate(delta),
+                            binding: binding.binding.relocate(delta),
+                        }),
+                        comma_token: binding.comma_token.relocate(delta),
+                    })
+                    .collect(),
+                rest: rest.relocate(delta),
+                close_brace: close_brace.relocate(delta),
+            },
+        };
+        Binding {
+            method,
+            diagnostics: self.diagnostics.relocate(delta),
+        }
+    }
+}
+
+impl<'new> Relocate<'new> for Box<RestBinding<'_>> {
+    type Output = Box<RestBinding<'new>>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        Box::new(RestBinding {
+            ellipses_token: self.ellipses_token.relocate(delta),
+            binding: self.binding.relocate(delta),
+            comma_token: self.comma_token.relocate(delta),
+        })
+    }
+}
+
+impl<'new> Relocate<'new> for Box<Expression<'_>> {
+    type Output = Box<Expression<'new>>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        Expression::build(
+            self.first_token.relocate(delta),
+            self.last_token.relocate(delta),
+            self.diagnostics.relocate(delta),
+            self.contents
+                .iter()
+                .map(|node| node.relocate(delta))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl<'new> Relocate<'new> for Node<'_> {
+    type Output = Node<'new>;
+    fn relocate(&self, delta: i64) -> Self::Output {
+        match self {
+            Node::Unit(open_paren, close_paren) => {
+                Node::Unit(open_paren.relocate(delta), close_paren.relocate(delta))
+            }
+            Node::Bool(value, token) => Node::Bool(*value, token.relocate(delta)),
+            Node::Number(token) => Node::Number(token.relocate(delta)),
+            Node::String(token) => Node::String(token.relocate(delta)),
+            Node::Interpolation(interpolation) => Node::Interpolation(Box::new(Interpolation {
+                start: interpolation.start.relocate(delta),
+                segments: interpolation
+                    .segments
+                    .iter()
+                    .map(|segment| InterpolationSegment {
+                        expression: segment.expression.relocate(delta),
+                        string: segment.string.relocate(delta),
+                    })
+                    .collect(),
+                diagnostics: interpolation.diagnostics.relocate(delta),
+            })),
+            Node::Variable(token) => Node::Variable(token.relocate(delta)),
+            Node::Block {
+                label,
+                open_brace,
+                block,
+                close_brace,
+            } => Node::Block {
+                label: label.as_ref().map(|label| Label {
+                    label: label.label.relocate(delta),
+                    colon_token: label.colon_token.relocate(delta),
+                }),
+                open_brace: open_brace.relocate(delta),
+                block: block.relocate(delta),
+                close_brace: close_brace.relocate(delta),
+            },
+            Node::If(if_block) => Node::If(Box::new(If {
+                if_token: if_block.if_token.relocate(delta),
+                condition: if_block.condition.relocate(delta),
+                then_token: if_block.then_token.relocate(delta),
+                first: if_block.first.relocate(delta),
+                else_token: if_block.else_token.relocate(delta),
+                else_kind: if_block.else_kind.relocate(delta),
+                second: if_block.second.relocate(delta),
+                end_token: if_block.end_token.relocate(delta),
+                diagnostics: if_block.diagnostics.relocate(delta),
+            })),
+            Node::Break(break_node) => Node::Break(Box::new(Break {
+                break_token: break_node.break_token.relocate(delta),
+                label: break_node.label.relocate(delta),
+                expression: break_node.expression.relocate(delta),
+            })),
+            Node::Match(match_block) => Node::Match(Match::build(
+                match_block.match_token.relocate(delta),
+                match_block.expression.relocate(delta),
+                match_block.then_token.relocate(delta),
+                match_block.end_token.relocate(delta),
+                match_block.diagnostics.relocate(delta),
+                match_block
+                    .cases
+                    .iter()
+                    .map(|case| MatchCase {
+                        let_token: case.let_token.relocate(delta),
+                        binding: case.binding.relocate(delta),
+                        colon_token: case.colon_token.relocate(delta),
+                        ty: case.ty.relocate(delta),
+                        equals_token: case.equals_token.relocate(delta),
+                        case: case.case.relocate(delta),
+                        if_token: case.if_token.relocate(delta),
+                        guard: case.guard.relocate(delta),
+                        arrow_token: case.arrow_token.relocate(delta),
+                        expression: case.expression.relocate(delta),
+                        semicolon_token: case.semicolon_token.relocate(delta),
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+            Node::Enum(enumeration) => Node::Enum(Box::new(Enum {
+                enum_token: enumeration.enum_token.relocate(delta),
+                variants: enumeration.variants.relocate(delta),
+                end_token: enumeration.end_token.relocate(delta),
+                diagnostics: enumeration.diagnostics.relocate(delta),
+            })),
+            Node::Pipe(token) => Node::Pipe(token.relocate(delta)),
+            Node::Call(token) => Node::Call(token.relocate(delta)),
+            Node::Positive(token) => Node::Positive(token.relocate(delta)),
+            Node::Negative(token) => Node::Negative(token.relocate(delta)),
+            Node::Not(token) => Node::Not(token.relocate(delta)),
+            Node::Deref(token) => Node::Deref(token.relocate(delta)),
+            Node::Mul(token) => Node::Mul(token.relocate(delta)),
+            Node::Div(token) => Node::Div(token.relocate(delta)),
+            Node::Add(token) => Node::Add(token.relocate(delta)),
+            Node::Sub(token) => Node::Sub(token.relocate(delta)),
+            Node::BitwiseAnd(token) => Node::BitwiseAnd(token.relocate(delta)),
+            Node::BitwiseOr(token) => Node::BitwiseOr(token.relocate(delta)),
+            Node::BitwiseXor(token) => Node::BitwiseXor(token.relocate(delta)),
+            Node::EqualTo(token) => Node::EqualTo(token.relocate(delta)),
+            Node::NotEqualTo(token) => Node::NotEqualTo(token.relocate(delta)),
+            Node::Greater(token) => Node::Greater(token.relocate(delta)),
+            Node::GreaterEqual(token) => Node::GreaterEqual(token.relocate(delta)),
+            Node::Lesser(token) => Node::Lesser(token.relocate(delta)),
+            Node::LesserEqual(token) => Node::LesserEqual(token.relocate(delta)),
+            Node::LogicalAnd(token) => Node::LogicalAnd(token.relocate(delta)),
+            Node::LogicalOr(token) => Node::LogicalOr(token.relocate(delta)),
+            Node::Range(token) => Node::Range(token.relocate(delta)),
+            Node::RangeInclusive(token) => Node::RangeInclusive(token.relocate(delta)),
+            Node::Name { name, colon_token } => Node::Name {
+                name: name.relocate(delta),
+                colon_token: colon_token.relocate(delta),
+            },
+            Node::Field { dot_token, index } => Node::Field {
+                dot_token: dot_token.relocate(delta),
+                index: index.relocate(delta),
+            },
+            Node::Tuple(token) => Node::Tuple(token.relocate(delta)),
+            Node::Group {
+                open_paren,
+                close_paren,
+            } => Node::Group {
+                open_paren: open_paren.relocate(delta),
+                close_paren: close_paren.relocate(delta),
+            },
+        }
+    }
+}
@@ -1,13 +1,27 @@
 use dst_factory::make_dst_factory;
-use espy_eyes::{self as lexer, Lexer, Lexigram, Token};
+use espy_eyes::{self as lexer, DocComments, Lexer, Lexigram, Span, Token};
 use std::iter::Peekable;
 
+mod format;
+mod incremental;
+#[cfg(feature = "serde")]
+mod schema;
 #[cfg(test)]
 mod tests;
+mod tokens;
+pub mod visit;
 
-#[derive(Debug, Eq, PartialEq)]
+pub use format::format;
+pub use incremental::Edit;
+use incremental::Splice;
+#[cfg(feature = "serde")]
+pub use schema::{Document, SCHEMA_VERSION};
+pub use visit::{Visitor, VisitorMut};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Error<'source> {
-    Lexer(lexer::Error<'source>),
+    Lexer(lexer::Error),
     MissingToken {
         /// Must contain at least one element.
         expected: &'static [Lexigram],
@@ -15,15 +29,22 @@ pub enum Error<'source> {
     },
     ExpectedExpression,
     UnexpectedCloseParen(Token<'source>),
+    /// A token which could neither begin a statement nor continue the one before it.
+    UnexpectedToken(Token<'source>),
+    /// A token which remained after the end of a program's top-level block,
+    /// such as an unmatched `}` or `end`.
+    TrailingInput(Token<'source>),
     IncompleteExpression,
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Diagnostic<'source> {
     Error(Error<'source>),
 }
 
 #[derive(Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Diagnostics<'source> {
     pub errors: Vec<Error<'source>>,
 }
@@ -64,19 +85,74 @@ impl<'source> Diagnostics<'source> {
         })
     }
 
+    /// Skips the remainder of a malformed statement so that parsing may resume after it.
+    ///
+    /// Skipping stops after a semicolon,
+    /// or before a token which ends the enclosing block (`end`, `else`, or `}`)
+    /// or begins a new statement (`let`, `set`, or `with`).
+    /// Brackets and `if`, `match`, and `enum` blocks are skipped in their entirety,
+    /// so a semicolon within them does not end the statement.
+    ///
+    /// Returns true if a semicolon was skipped.
+    /// The caller must have already reported the upcoming token if it was a lexer error.
+    fn synchronize(&mut self, lexer: &mut Peekable<Lexer<'source>>) -> bool {
+        let mut depth = 0_usize;
+        let mut previous = None;
+        while let Some(t) = lexer.peek().copied() {
+            let t = if previous.is_some() {
+                self.wrap(Some(t))
+            } else {
+                t.ok()
+            };
+            let lexigram = t.map(|t| t.lexigram);
+            match lexigram {
+                Some(Lexigram::Semicolon) if depth == 0 => {
+                    lexer.next();
+                    return true;
+                }
+                Some(
+                    Lexigram::End
+                    | Lexigram::Else
+                    | Lexigram::CloseBrace
+                    | Lexigram::Let
+                    | Lexigram::Set
+                    | Lexigram::With,
+                ) if depth == 0 => return false,
+                Some(
+                    Lexigram::OpenParen
+                    | Lexigram::OpenSquare
+                    | Lexigram::OpenBrace
+                    | Lexigram::StringStart
+                    | Lexigram::Match
+                    | Lexigram::Enum,
+                ) => depth += 1,
+                // `else if` shares the `end` of the `if` before it.
+                Some(Lexigram::If) if previous != Some(Some(Lexigram::Else)) => depth += 1,
+                Some(
+                    Lexigram::CloseParen
+                    | Lexigram::CloseSquare
+                    | Lexigram::CloseBrace
+                    | Lexigram::StringEnd
+                    | Lexigram::End,
+                ) => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            previous = Some(lexigram);
+            lexer.next();
+        }
+        false
+    }
+
     fn wrap(&mut self, t: Option<lexer::Result<'source>>) -> Option<Token<'source>> {
         match t? {
             Ok(t) => Some(t),
             Err(e) => {
                 let t = if let lexer::Error {
-                    origin,
+                    span,
                     kind: lexer::ErrorKind::ReservedSymbol,
                 } = e
                 {
-                    Some(Token {
-                        origin,
-                        lexigram: Lexigram::Ident,
-                    })
+                    Some(Token::new(span, Lexigram::Ident))
                 } else {
                     None
                 };
@@ -88,14 +164,22 @@ impl<'source> Diagnostics<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Node<'source> {
     Unit(Token<'source>, Token<'source>),
     Bool(bool, Token<'source>),
     Number(Token<'source>),
     String(Token<'source>),
+    Interpolation(Box<Interpolation<'source>>),
     Variable(Token<'source>),
-    Block(Box<Block<'source>>),
+    Block {
+        label: Option<Label<'source>>,
+        open_brace: Token<'source>,
+        block: Box<Block<'source>>,
+        close_brace: Option<Token<'source>>,
+    },
     If(Box<If<'source>>),
+    Break(Box<Break<'source>>),
     Match(Box<Match<'source>>),
     Enum(Box<Enum<'source>>),
 
@@ -103,6 +187,7 @@ pub enum Node<'source> {
     Call(Token<'source>),
     Positive(Token<'source>),
     Negative(Token<'source>),
+    Not(Token<'source>),
     Deref(Token<'source>),
     Mul(Token<'source>),
     Div(Token<'source>),
@@ -119,6 +204,8 @@ pub enum Node<'source> {
     LesserEqual(Token<'source>),
     LogicalAnd(Token<'source>),
     LogicalOr(Token<'source>),
+    Range(Token<'source>),
+    RangeInclusive(Token<'source>),
     Name {
         name: Token<'source>,
         colon_token: Token<'source>,
@@ -128,10 +215,19 @@ pub enum Node<'source> {
         index: Token<'source>,
     },
     Tuple(Token<'source>),
+    /// Marks the preceding operand as having been wrapped in parentheses.
+    ///
+    /// This has no effect on the expression's meaning,
+    /// but allows the original source to be reconstructed.
+    Group {
+        open_paren: Token<'source>,
+        close_paren: Token<'source>,
+    },
 }
 
 /// This type must not contain any incomplete expressions.
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[make_dst_factory(pub)]
 pub struct Expression<'source> {
     pub first_token: Option<Token<'source>>,
@@ -149,6 +245,7 @@ impl<'source> Expression<'source> {
             Pipe(Token<'source>),
             Positive(Token<'source>),
             Negative(Token<'source>),
+            Not(Token<'source>),
             Deref(Token<'source>),
             Mul(Token<'source>),
             Div(Token<'source>),
@@ -165,6 +262,8 @@ impl<'source> Expression<'source> {
             LesserEqual(Token<'source>),
             LogicalAnd(Token<'source>),
             LogicalOr(Token<'source>),
+            Range(Token<'source>),
+            RangeInclusive(Token<'source>),
             Name {
                 name: Token<'source>,
                 colon_token: Token<'source>,
@@ -180,21 +279,25 @@ impl<'source> Expression<'source> {
         impl<'source> Operation<'source> {
             fn precedence(self) -> usize {
                 match self {
-                    Operation::Field { .. } => 13,
-                    Operation::Positive(_) | Operation::Negative(_) | Operation::Deref(_) => 12,
-                    Operation::Mul(_) | Operation::Div(_) => 11,
-                    Operation::Add(_) | Operation::Sub(_) => 10,
-                    Operation::BitwiseAnd(_) => 9,
-                    Operation::BitwiseXor(_) => 8,
-                    Operation::BitwiseOr(_) => 7,
+                    Operation::Field { .. } => 14,
+                    Operation::Positive(_)
+                    | Operation::Negative(_)
+                    | Operation::Not(_)
+                    | Operation::Deref(_) => 13,
+                    Operation::Mul(_) | Operation::Div(_) => 12,
+                    Operation::Add(_) | Operation::Sub(_) => 11,
+                    Operation::BitwiseAnd(_) => 10,
+                    Operation::BitwiseXor(_) => 9,
+                    Operation::BitwiseOr(_) => 8,
                     Operation::EqualTo(_)
                     | Operation::NotEqualTo(_)
                     | Operation::Greater(_)
                     | Operation::GreaterEqual(_)
                     | Operation::Lesser(_)
-                    | Operation::LesserEqual(_) => 6,
-                    Operation::LogicalAnd(_) => 5,
-                    Operation::LogicalOr(_) => 4,
+                    | Operation::LesserEqual(_) => 7,
+                    Operation::LogicalAnd(_) => 6,
+                    Operation::LogicalOr(_) => 5,
+                    Operation::Range(_) | Operation::RangeInclusive(_) => 4,
                     Operation::Name { .. } => 3,
                     Operation::Tuple(_) => 2,
                     Operation::Pipe(_) | Operation::Call(_) => 1,
@@ -204,10 +307,13 @@ impl<'source> Expression<'source> {
 
             fn left_associative(self) -> bool {
                 match self {
-                    Operation::Field { .. }
-                    | Operation::Positive(_)
+                    // Prefix operators apply to whatever follows them,
+                    // so a chain like `!!x` must not pop its predecessors.
+                    Operation::Positive(_)
                     | Operation::Negative(_)
-                    | Operation::Deref(_)
+                    | Operation::Not(_)
+                    | Operation::Deref(_) => false,
+                    Operation::Field { .. }
                     | Operation::Mul(_)
                     | Operation::Div(_)
                     | Operation::Name { .. }
@@ -224,6 +330,8 @@ impl<'source> Expression<'source> {
                     | Operation::LesserEqual(_)
                     | Operation::LogicalAnd(_)
                     | Operation::LogicalOr(_)
+                    | Operation::Range(_)
+                    | Operation::RangeInclusive(_)
                     | Operation::Tuple(_)
                     | Operation::SubExpression(_)
                     | Operation::Call(_)
@@ -240,6 +348,7 @@ impl<'source> Expression<'source> {
                     Operation::Call(t) => Node::Call(t),
                     Operation::Positive(t) => Node::Positive(t),
                     Operation::Negative(t) => Node::Negative(t),
+                    Operation::Not(t) => Node::Not(t),
                     Operation::Deref(t) => Node::Deref(t),
                     Operation::Mul(t) => Node::Mul(t),
                     Operation::Div(t) => Node::Div(t),
@@ -256,6 +365,8 @@ impl<'source> Expression<'source> {
                     Operation::LesserEqual(t) => Node::LesserEqual(t),
                     Operation::LogicalAnd(t) => Node::LogicalAnd(t),
                     Operation::LogicalOr(t) => Node::LogicalOr(t),
+                    Operation::Range(t) => Node::Range(t),
+                    Operation::RangeInclusive(t) => Node::RangeInclusive(t),
                     Operation::Name { name, colon_token } => Node::Name { name, colon_token },
                     Operation::Tuple(t) => Node::Tuple(t),
                     Operation::SubExpression(_) => {
@@ -284,6 +395,7 @@ impl<'source> Expression<'source> {
                         | Lexigram::Caret
                         | Lexigram::Pipe
                         | Lexigram::DoubleEqual
+                        | Lexigram::Bang
                         | Lexigram::BangEqual
                         | Lexigram::Greater
                         | Lexigram::GreaterEqual
@@ -291,6 +403,8 @@ impl<'source> Expression<'source> {
                         | Lexigram::LesserEqual
                         | Lexigram::And
                         | Lexigram::Or
+                        | Lexigram::DotDot
+                        | Lexigram::DotDotEqual
                         | Lexigram::Comma
                         | Lexigram::Colon
                         | Lexigram::Triangle
@@ -353,6 +467,12 @@ impl<'source> Expression<'source> {
                     }
                     contents.push(Node::String(string));
                 }
+                lexi!(start @ StringStart) => {
+                    if !unary_position {
+                        op!(Call(start));
+                    }
+                    contents.push(Interpolation::from(&mut *lexer).into());
+                }
                 lexi!(discard @ Discard) => {
                     last_token = lexer.next().transpose().ok().flatten();
                     let colon_token = diagnostics.next_if(lexer, &[Lexigram::Colon]);
@@ -427,13 +547,61 @@ impl<'source> Expression<'source> {
                     }
                     stack.push(Operation::SubExpression(t));
                 }
-                lexi!(t @ OpenBrace) => {
+                lexi!(open_brace @ OpenBrace) => {
                     if !unary_position {
-                        push_with_precedence(&mut contents, &mut stack, Operation::Call(t));
+                        push_with_precedence(
+                            &mut contents,
+                            &mut stack,
+                            Operation::Call(open_brace),
+                        );
                     }
                     lexer.next();
-                    contents.push(Node::Block(Block::new(&mut *lexer)));
-                    diagnostics.expect(lexer.peek().copied(), &[Lexigram::CloseBrace]);
+                    let block = Block::new(&mut *lexer);
+                    let close_brace =
+                        diagnostics.expect(lexer.peek().copied(), &[Lexigram::CloseBrace]);
+                    contents.push(Node::Block {
+                        label: None,
+                        open_brace,
+                        block,
+                        close_brace,
+                    });
+                }
+                lexi!(label @ Label) => {
+                    if !unary_position {
+                        push_with_precedence(&mut contents, &mut stack, Operation::Call(label));
+                    }
+                    lexer.next();
+                    last_token = Some(label);
+                    let colon_token = diagnostics.next_if(lexer, &[Lexigram::Colon]);
+                    let Some(open_brace) = diagnostics.next_if(lexer, &[Lexigram::OpenBrace])
+                    else {
+                        continue;
+                    };
+                    let block = Block::new(&mut *lexer);
+                    let close_brace =
+                        diagnostics.expect(lexer.peek().copied(), &[Lexigram::CloseBrace]);
+                    contents.push(Node::Block {
+                        label: Some(Label { label, colon_token }),
+                        open_brace,
+                        block,
+                        close_brace,
+                    });
+                }
+                // The value of a `break` extends as far as possible,
+                // so the expression around it cannot continue.
+                Some(Token {
+                    lexigram: Lexigram::Break | Lexigram::Return,
+                    ..
+                }) if unary_position => {
+                    let break_node = Break::from(&mut *lexer);
+                    last_token = break_node
+                        .expression
+                        .as_ref()
+                        .and_then(|expression| expression.last_token)
+                        .or(break_node.label)
+                        .or(Some(break_node.break_token));
+                    contents.push(break_node.into());
+                    continue;
                 }
 
                 // # Operators
@@ -441,6 +609,7 @@ impl<'source> Expression<'source> {
                 lexi!(t @ Plus) if !unary_position => op!(Add(t)),
                 lexi!(t @ Minus) if unary_position => op!(Negative(t)),
                 lexi!(t @ Minus) if !unary_position => op!(Sub(t)),
+                lexi!(t @ Bang) if unary_position => op!(Not(t)),
                 // TODO: I'd rather use zig's `.*` for deref, but this causes unary position because the * looks like multiplication. What if ".*" was a token?
                 lexi!(t @ Star) if unary_position => op!(Deref(t)),
                 lexi!(t @ Star) if !unary_position => op!(Mul(t)),
@@ -456,9 +625,13 @@ impl<'source> Expression<'source> {
                 lexi!(t @ LesserEqual) if !unary_position => op!(LesserEqual(t)),
                 lexi!(t @ And) if !unary_position => op!(LogicalAnd(t)),
                 lexi!(t @ Or) if !unary_position => op!(LogicalOr(t)),
+                lexi!(t @ DotDot) if !unary_position => op!(Range(t)),
+                lexi!(t @ DotDotEqual) if !unary_position => op!(RangeInclusive(t)),
                 lexi!(t @ Triangle) if !unary_position => op!(Pipe(t)),
                 lexi!(t @ Comma) if !unary_position => op!(Tuple(t)),
-                lexi!(  @ If) => contents.push(If::from(&mut *lexer).into()),
+                // An `if` following a value ends the expression,
+                // which allows it to be used as a match case's guard.
+                lexi!(  @ If) if unary_position => contents.push(If::from(&mut *lexer).into()),
                 lexi!(  @ Match) => contents.push(Match::new(&mut *lexer).into()),
                 lexi!(  @ Enum) => contents.push(Enum::from(&mut *lexer).into()),
                 lexi!(t @ CloseParen) if unary_position => {
@@ -482,7 +655,12 @@ impl<'source> Expression<'source> {
                     {
                         contents.push(op.into());
                     }
-                    if !matches!(stack.pop(), Some(Operation::SubExpression(_))) {
+                    if let Some(Operation::SubExpression(open_paren)) = stack.pop() {
+                        contents.push(Node::Group {
+                            open_paren,
+                            close_paren: t,
+                        });
+                    } else {
                         diagnostics.errors.push(Error::UnexpectedCloseParen(t))
                     }
                 }
@@ -515,7 +693,70 @@ impl<'source> Expression<'source> {
     }
 }
 
+/// The name of a block (such as `'outer:`), which `break` may refer to.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Label<'source> {
+    pub label: Token<'source>,
+    pub colon_token: Option<Token<'source>>,
+}
+
+/// An early exit, which either leaves a block (`break`) or its function (`return`).
+///
+/// An unlabeled `break` leaves the innermost block that is surrounded by braces.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Break<'source> {
+    /// Either `break` or `return`.
+    pub break_token: Token<'source>,
+    /// The label of the block to leave, which only `break` may specify.
+    pub label: Option<Token<'source>>,
+    /// The value to leave with, which is unit if absent.
+    pub expression: Option<Box<Expression<'source>>>,
+}
+
+impl<'source> From<Break<'source>> for Node<'source> {
+    fn from(break_node: Break<'source>) -> Self {
+        Self::Break(Box::new(break_node))
+    }
+}
+
+impl<'source> From<&mut Peekable<Lexer<'source>>> for Break<'source> {
+    fn from(lexer: &mut Peekable<Lexer<'source>>) -> Self {
+        let break_token = lexer
+            .next()
+            .transpose()
+            .ok()
+            .flatten()
+            .expect("caller must have peeked a token");
+        let label = if break_token.lexigram == Lexigram::Break {
+            lexer
+                .next_if(|t| {
+                    matches!(
+                        t,
+                        Ok(Token {
+                            lexigram: Lexigram::Label,
+                            ..
+                        })
+                    )
+                })
+                .transpose()
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+        let expression = Expression::new(lexer);
+        Self {
+            break_token,
+            label,
+            expression,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct If<'source> {
     pub if_token: Token<'source>,
     pub condition: Option<Box<Expression<'source>>>,
@@ -602,17 +843,27 @@ impl<'source> From<&mut Peekable<Lexer<'source>>> for If<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MatchCase<'source> {
     pub let_token: Option<Token<'source>>,
     pub binding: Option<Token<'source>>,
+    pub colon_token: Option<Token<'source>>,
+    /// Only values of this type are accepted by the case.
+    pub ty: Option<Box<Expression<'source>>>,
     pub equals_token: Option<Token<'source>>,
     pub case: Option<Box<Expression<'source>>>,
+    pub if_token: Option<Token<'source>>,
+    /// A condition which must also be true for the case to accept a value.
+    ///
+    /// The guard is evaluated after the value is bound, so it may refer to it.
+    pub guard: Option<Box<Expression<'source>>>,
     pub arrow_token: Option<Token<'source>>,
     pub expression: Option<Box<Expression<'source>>>,
     pub semicolon_token: Option<Token<'source>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[make_dst_factory(pub)]
 pub struct Match<'source> {
     pub match_token: Token<'source>,
@@ -645,43 +896,79 @@ impl<'source> Match<'source> {
 
         // ew
         loop {
-            let (let_token, binding, equals_token, case) = if let let_token @ Some(Token {
-                lexigram: Lexigram::Let,
-                ..
-            }) =
-                diagnostics.wrap(lexer.peek().copied())
-            {
-                lexer.next();
-                let binding = diagnostics.next_if(lexer, &[Lexigram::Ident, Lexigram::Discard]);
-                let (equal_token, case) = if let equal_token @ Some(Token {
-                    lexigram: Lexigram::SingleEqual,
+            let (let_token, binding, colon_token, ty, equals_token, case) =
+                if let let_token @ Some(Token {
+                    lexigram: Lexigram::Let,
                     ..
                 }) = diagnostics.wrap(lexer.peek().copied())
                 {
                     lexer.next();
-                    let case = diagnostics.expect_expression(lexer);
-                    (equal_token, case)
+                    let binding = diagnostics.next_if(lexer, &[Lexigram::Ident, Lexigram::Discard]);
+                    let (colon_token, ty) = if let colon_token @ Some(Token {
+                        lexigram: Lexigram::Colon,
+                        ..
+                    }) = diagnostics.wrap(lexer.peek().copied())
+                    {
+                        lexer.next();
+                        let ty = diagnostics.expect_expression(lexer);
+                        (colon_token, ty)
+                    } else {
+                        (None, None)
+                    };
+                    let (equal_token, case) = if let equal_token @ Some(Token {
+                        lexigram: Lexigram::SingleEqual,
+                        ..
+                    }) = diagnostics.wrap(lexer.peek().copied())
+                    {
+                        lexer.next();
+                        let case = diagnostics.expect_expression(lexer);
+                        (equal_token, case)
+                    } else {
+                        (None, None)
+                    };
+                    (let_token, binding, colon_token, ty, equal_token, case)
                 } else {
-                    (None, None)
+                    let case = diagnostics.expect_expression(lexer);
+                    (None, None, None, None, None, case)
                 };
-                (let_token, binding, equal_token, case)
+            let (if_token, guard) = if let if_token @ Some(Token {
+                lexigram: Lexigram::If,
+                ..
+            }) = diagnostics.wrap(lexer.peek().copied())
+            {
+                lexer.next();
+                (if_token, diagnostics.expect_expression(lexer))
             } else {
-                let case = diagnostics.expect_expression(lexer);
-                (None, None, None, case)
+                (None, None)
             };
             let arrow_token = diagnostics.next_if(lexer, &[Lexigram::DoubleArrow]);
             let expression = diagnostics.expect_expression(lexer);
             let semicolon_token = diagnostics.next_if(lexer, &[Lexigram::Semicolon]);
+            // A malformed case may be skipped if it was terminated by a semicolon,
+            // or if another case seems to follow it.
+            let recovered = semicolon_token.is_none()
+                && (diagnostics.synchronize(lexer)
+                    || matches!(
+                        lexer.peek(),
+                        Some(Ok(Token {
+                            lexigram: Lexigram::Let,
+                            ..
+                        }))
+                    ));
             cases.push(MatchCase {
                 let_token,
                 binding,
+                colon_token,
+                ty,
                 equals_token,
                 case,
+                if_token,
+                guard,
                 arrow_token,
                 expression,
                 semicolon_token,
             });
-            if semicolon_token.is_none()
+            if (semicolon_token.is_none() && !recovered)
                 || diagnostics
                     .wrap(lexer.peek().copied())
                     .is_some_and(|t| t.lexigram == Lexigram::End)
@@ -702,6 +989,7 @@ impl<'source> Match<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Enum<'source> {
     pub enum_token: Token<'source>,
     pub variants: Option<Box<Expression<'source>>>,
@@ -709,6 +997,56 @@ pub struct Enum<'source> {
     pub diagnostics: Diagnostics<'source>,
 }
 
+impl<'source> Enum<'source> {
+    /// Returns the name of each of this enum's variants.
+    ///
+    /// A variant's doc comment is the doc comment of its name.
+    pub fn variant_names(&self) -> Vec<Token<'source>> {
+        // The variant names within each operand that has not yet been consumed by an operator.
+        let mut operands: Vec<Vec<Token<'source>>> = Vec::new();
+        for node in self.variants.iter().flat_map(|variants| &variants.contents) {
+            match node {
+                Node::Unit(..)
+                | Node::Bool(..)
+                | Node::Number(_)
+                | Node::String(_)
+                | Node::Interpolation(_)
+                | Node::Variable(_)
+                | Node::Block { .. }
+                | Node::If(_)
+                | Node::Break(_)
+                | Node::Match(_)
+                | Node::Enum(_) => operands.push(Vec::new()),
+                Node::Name { name, .. } => {
+                    operands.pop();
+                    operands.push(vec![*name]);
+                }
+                Node::Positive(_)
+                | Node::Negative(_)
+                | Node::Not(_)
+                | Node::Deref(_)
+                | Node::Field { .. }
+                | Node::Group { .. } => {
+                    operands.pop();
+                    operands.push(Vec::new());
+                }
+                Node::Tuple(_) => {
+                    let right = operands.pop().unwrap_or_default();
+                    let mut left = operands.pop().unwrap_or_default();
+                    left.extend(right);
+                    operands.push(left);
+                }
+                _ => {
+                    operands.pop();
+                    operands.pop();
+                    operands.push(Vec::new());
+                }
+            }
+        }
+        operands.pop().unwrap_or_default()
+    }
+}
+
 impl<'source> From<Enum<'source>> for Node<'source> {
     fn from(struct_block: Enum<'source>) -> Self {
         Self::Enum(Box::new(struct_block))
@@ -735,7 +1073,70 @@ impl<'source> From<&mut Peekable<Lexer<'source>>> for Enum<'source> {
     }
 }
 
+/// A string containing interpolated expressions, such as `"Hello, {name}!"`.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Interpolation<'source> {
+    /// The text before the first expression, as a [`Lexigram::StringStart`] token.
+    pub start: Token<'source>,
+    pub segments: Vec<InterpolationSegment<'source>>,
+    pub diagnostics: Diagnostics<'source>,
+}
+
+/// An expression within an interpolated string, and the text following it.
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InterpolationSegment<'source> {
+    pub expression: Option<Box<Expression<'source>>>,
+    /// A [`Lexigram::StringMiddle`] token if another expression follows,
+    /// or a [`Lexigram::StringEnd`] token otherwise.
+    pub string: Option<Token<'source>>,
+}
+
+impl<'source> From<Interpolation<'source>> for Node<'source> {
+    fn from(interpolation: Interpolation<'source>) -> Self {
+        Self::Interpolation(Box::new(interpolation))
+    }
+}
+
+impl<'source> From<&mut Peekable<Lexer<'source>>> for Interpolation<'source> {
+    fn from(lexer: &mut Peekable<Lexer<'source>>) -> Self {
+        let start = lexer
+            .next()
+            .transpose()
+            .ok()
+            .flatten()
+            .expect("caller must have peeked a token");
+        let mut diagnostics = Diagnostics::default();
+        let mut segments = Vec::new();
+        loop {
+            let expression = diagnostics.expect_expression(lexer);
+            let string = diagnostics.expect(
+                lexer.peek().copied(),
+                &[Lexigram::StringMiddle, Lexigram::StringEnd],
+            );
+            segments.push(InterpolationSegment { expression, string });
+            // The final `StringEnd` is left for the caller, like the `end` of other blocks.
+            if let Some(Token {
+                lexigram: Lexigram::StringMiddle,
+                ..
+            }) = string
+            {
+                lexer.next();
+            } else {
+                break;
+            }
+        }
+        Self {
+            start,
+            segments,
+            diagnostics,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Statement<'source> {
     Evaluation(Evaluation<'source>),
     /// Technically this could be an expression too but i actually think being statement based for assignments is a feature.
@@ -743,9 +1144,12 @@ pub enum Statement<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Evaluation<'source> {
     pub binding: Option<LetBinding<'source>>,
     pub expression: Option<Box<Expression<'source>>>,
+    /// Only present for `let` statements.
+    pub else_branch: Option<Box<LetElse<'source>>>,
     pub semicolon_token: Option<Token<'source>>,
     pub diagnostics: Diagnostics<'source>,
 }
@@ -762,9 +1166,56 @@ impl<'source> Evaluation<'source> {
         let binding = Binding::new(lexer)
             .map_err(|e| diagnostics.errors.push(e))
             .ok();
+        // If the binding is malformed and not followed by an equals sign,
+        // there's no telling where the expression begins.
+        if binding.is_none()
+            && !matches!(
+                lexer.peek(),
+                Some(Ok(Token {
+                    lexigram: Lexigram::SingleEqual,
+                    ..
+                }))
+            )
+        {
+            diagnostics.synchronize(lexer);
+            return Evaluation {
+                binding: Some(LetBinding {
+                    let_token,
+                    binding,
+                    equals_token: None,
+                }),
+                expression: None,
+                else_branch: None,
+                semicolon_token: None,
+                diagnostics,
+            };
+        }
         let equals_token = diagnostics.next_if(lexer, &[Lexigram::SingleEqual]);
         let expression = diagnostics.expect_expression(lexer);
+        let else_branch = if let Some(
+            else_token @ Token {
+                lexigram: Lexigram::Else,
+                ..
+            },
+        ) = diagnostics.wrap(lexer.peek().copied())
+        {
+            lexer.next();
+            let then_token = diagnostics.next_if(lexer, &[Lexigram::Then]);
+            let block = Block::new(&mut *lexer);
+            let end_token = diagnostics.next_if(lexer, &[Lexigram::End]);
+            Some(Box::new(LetElse {
+                else_token,
+                then_token,
+                block,
+                end_token,
+            }))
+        } else {
+            None
+        };
         let semicolon_token = diagnostics.next_if(lexer, &[Lexigram::Semicolon]);
+        if semicolon_token.is_none() {
+            diagnostics.synchronize(lexer);
+        }
 
         Evaluation {
             binding: Some(LetBinding {
@@ -773,6 +1224,7 @@ impl<'source> Evaluation<'source> {
                 equals_token,
             }),
             expression,
+            else_branch,
             semicolon_token,
             diagnostics,
         }
@@ -793,6 +1245,7 @@ impl<'source> Evaluation<'source> {
             Ok(Evaluation {
                 binding: None,
                 expression,
+                else_branch: None,
                 semicolon_token: Some(semicolon_token),
                 diagnostics: Diagnostics::default(),
             })
@@ -803,13 +1256,33 @@ impl<'source> Evaluation<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LetBinding<'source> {
     pub let_token: Token<'source>,
     pub binding: Option<Binding<'source>>,
     pub equals_token: Option<Token<'source>>,
 }
 
+/// The block evaluated when a `let` statement's binding is refuted,
+/// which becomes the result of the block containing the statement.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LetElse<'source> {
+    pub else_token: Token<'source>,
+    pub then_token: Option<Token<'source>>,
+    pub block: Box<Block<'source>>,
+    pub end_token: Option<Token<'source>>,
+}
+
+impl LetBinding<'_> {
+    /// The doc comment preceding the `let` keyword, if any.
+    pub fn doc_comment(&self, doc_comments: &DocComments) -> Option<Span> {
+        doc_comments.get(self.let_token)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Set<'source> {
     pub set_token: Token<'source>,
     pub target: Option<Box<Expression<'source>>>,
@@ -832,6 +1305,9 @@ impl<'source> Set<'source> {
         let equals_token = diagnostics.next_if(lexer, &[Lexigram::SingleEqual]);
         let expression = diagnostics.expect_expression(lexer);
         let semicolon_token = diagnostics.next_if(lexer, &[Lexigram::Semicolon]);
+        if semicolon_token.is_none() {
+            diagnostics.synchronize(lexer);
+        }
 
         Set {
             set_token,
@@ -845,12 +1321,14 @@ impl<'source> Set<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NumericBinding<'source> {
     pub binding: Binding<'source>,
     pub comma_token: Option<Token<'source>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedBinding<'source> {
     pub field: Token<'source>,
     pub binding: Option<NamedSubBinding<'source>>,
@@ -858,33 +1336,83 @@ pub struct NamedBinding<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedSubBinding<'source> {
     pub colon_token: Token<'source>,
     pub binding: Binding<'source>,
 }
 
+/// Binds the fields of a tuple which were not bound by the rest of its pattern,
+/// such as `...tail` in `(head, ...tail)`.
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RestBinding<'source> {
+    pub ellipses_token: Token<'source>,
+    pub binding: Binding<'source>,
+    pub comma_token: Option<Token<'source>>,
+}
+
+/// Matches a value created by an enum variant's (or option's) constructor,
+/// such as `Result.Ok value`, and binds its contents.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VariantBinding<'source> {
+    /// The variable which holds the constructor, or the enum it belongs to.
+    pub constructor: Token<'source>,
+    /// Must contain at least one element.
+    pub fields: Box<[VariantField<'source>]>,
+    /// The variant's contents are left unbound if this is absent.
+    pub binding: Option<Binding<'source>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VariantField<'source> {
+    pub dot_token: Token<'source>,
+    pub field: Token<'source>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BindingMethod<'source> {
     Single(Token<'source>),
+    /// Matches values equal to a number, string, or boolean literal.
+    Literal(Token<'source>),
+    Variant(Box<VariantBinding<'source>>),
     Numeric {
         open_paren: Token<'source>,
         bindings: Box<[NumericBinding<'source>]>,
+        rest: Option<Box<RestBinding<'source>>>,
         close_paren: Option<Token<'source>>,
     },
     Named {
         open_brace: Token<'source>,
         bindings: Box<[NamedBinding<'source>]>,
+        rest: Option<Box<RestBinding<'source>>>,
         close_brace: Option<Token<'source>>,
     },
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Binding<'source> {
     pub method: BindingMethod<'source>,
     pub diagnostics: Diagnostics<'source>,
 }
 
 impl<'source> Binding<'source> {
+    /// The tokens which may begin a binding.
+    const STARTS: &'static [Lexigram] = &[
+        Lexigram::Ident,
+        Lexigram::Discard,
+        Lexigram::OpenParen,
+        Lexigram::OpenBrace,
+        Lexigram::Number,
+        Lexigram::String,
+        Lexigram::True,
+        Lexigram::False,
+    ];
+
     fn new(lexer: &mut Peekable<Lexer<'source>>) -> Result<Self, Error<'source>> {
         match lexer.peek().copied().transpose().map_err(Error::Lexer)? {
             Some(
@@ -894,8 +1422,61 @@ impl<'source> Binding<'source> {
                 },
             ) => {
                 lexer.next();
+                let mut diagnostics = Diagnostics::default();
+                let mut fields = Vec::new();
+                if t.lexigram == Lexigram::Ident {
+                    while let Some(Ok(dot_token)) = lexer.next_if(|t| {
+                        matches!(
+                            t,
+                            Ok(Token {
+                                lexigram: Lexigram::Dot,
+                                ..
+                            })
+                        )
+                    }) {
+                        let Some(field) =
+                            diagnostics.next_if(lexer, &[Lexigram::Ident, Lexigram::Number])
+                        else {
+                            break;
+                        };
+                        fields.push(VariantField { dot_token, field });
+                    }
+                }
+                if fields.is_empty() && diagnostics.errors.is_empty() {
+                    return Ok(Binding {
+                        method: BindingMethod::Single(t),
+                        diagnostics,
+                    });
+                }
+                // The contents of a variant may be left unbound, as in `let (Color.Red, x) = ...`.
+                let binding = if diagnostics
+                    .wrap(lexer.peek().copied())
+                    .is_some_and(|t| Binding::STARTS.contains(&t.lexigram))
+                {
+                    Binding::new(lexer)
+                        .map_err(|e| diagnostics.errors.push(e))
+                        .ok()
+                } else {
+                    None
+                };
                 Ok(Binding {
-                    method: BindingMethod::Single(t),
+                    method: BindingMethod::Variant(Box::new(VariantBinding {
+                        constructor: t,
+                        fields: fields.into_boxed_slice(),
+                        binding,
+                    })),
+                    diagnostics,
+                })
+            }
+            Some(
+                t @ Token {
+                    lexigram: Lexigram::Number | Lexigram::String | Lexigram::True | Lexigram::False,
+                    ..
+                },
+            ) => {
+                lexer.next();
+                Ok(Binding {
+                    method: BindingMethod::Literal(t),
                     diagnostics: Diagnostics::default(),
                 })
             }
@@ -907,15 +1488,25 @@ impl<'source> Binding<'source> {
             ) => {
                 let mut diagnostics = Diagnostics::default();
                 let mut bindings = Vec::new();
+                let mut rest = None;
                 lexer.next();
                 loop {
                     let t = diagnostics.wrap(lexer.peek().copied());
-                    if let Some(Token {
-                        lexigram: Lexigram::CloseParen,
-                        ..
-                    }) = t
-                    {
-                        break;
+                    match t {
+                        Some(Token {
+                            lexigram: Lexigram::CloseParen,
+                            ..
+                        }) => break,
+                        Some(
+                            ellipses_token @ Token {
+                                lexigram: Lexigram::Ellipses,
+                                ..
+                            },
+                        ) => {
+                            rest = RestBinding::new(ellipses_token, lexer, &mut diagnostics);
+                            break;
+                        }
+                        _ => {}
                     }
                     match Binding::new(lexer) {
                         Ok(binding) => {
@@ -939,6 +1530,11 @@ impl<'source> Binding<'source> {
                                     Lexigram::Discard,
                                     Lexigram::OpenParen,
                                     Lexigram::OpenBrace,
+                                    Lexigram::Number,
+                                    Lexigram::String,
+                                    Lexigram::True,
+                                    Lexigram::False,
+                                    Lexigram::Ellipses,
                                     Lexigram::CloseParen,
                                 ],
                                 actual: t,
@@ -952,6 +1548,7 @@ impl<'source> Binding<'source> {
                     method: BindingMethod::Numeric {
                         open_paren,
                         bindings: bindings.into_boxed_slice(),
+                        rest,
                         close_paren,
                     },
                     diagnostics,
@@ -965,6 +1562,7 @@ impl<'source> Binding<'source> {
             ) => {
                 let mut diagnostics = Diagnostics::default();
                 let mut bindings = Vec::new();
+                let mut rest = None;
                 lexer.next();
                 loop {
                     match diagnostics.wrap(lexer.peek().copied()) {
@@ -972,6 +1570,15 @@ impl<'source> Binding<'source> {
                             lexigram: Lexigram::CloseBrace,
                             ..
                         }) => break,
+                        Some(
+                            ellipses_token @ Token {
+                                lexigram: Lexigram::Ellipses,
+                                ..
+                            },
+                        ) => {
+                            rest = RestBinding::new(ellipses_token, lexer, &mut diagnostics);
+                            break;
+                        }
                         Some(
                             field @ Token {
                                 lexigram: Lexigram::Ident,
@@ -1034,7 +1641,11 @@ impl<'source> Binding<'source> {
                         }
                         actual => {
                             diagnostics.errors.push(Error::MissingToken {
-                                expected: &[Lexigram::Ident, Lexigram::CloseBrace],
+                                expected: &[
+                                    Lexigram::Ident,
+                                    Lexigram::Ellipses,
+                                    Lexigram::CloseBrace,
+                                ],
                                 actual,
                             });
                             break;
@@ -1046,25 +1657,82 @@ impl<'source> Binding<'source> {
                     method: BindingMethod::Named {
                         open_brace,
                         bindings: bindings.into_boxed_slice(),
+                        rest,
                         close_brace,
                     },
                     diagnostics,
                 })
             }
             actual => Err(Error::MissingToken {
-                expected: &[
-                    Lexigram::Ident,
-                    Lexigram::Discard,
-                    Lexigram::OpenParen,
-                    Lexigram::OpenBrace,
-                ],
+                expected: Self::STARTS,
                 actual,
             }),
         }
     }
+
+    /// Returns true if this binding may fail to match a value,
+    /// because it contains literals or enum variants.
+    pub fn is_refutable(&self) -> bool {
+        let rest_is_refutable = |rest: &Option<Box<RestBinding>>| {
+            rest.as_ref()
+                .is_some_and(|rest| rest.binding.is_refutable())
+        };
+        match &self.method {
+            BindingMethod::Single(_) => false,
+            BindingMethod::Literal(_) | BindingMethod::Variant(_) => true,
+            BindingMethod::Numeric { bindings, rest, .. } => {
+                bindings
+                    .iter()
+                    .any(|binding| binding.binding.is_refutable())
+                    || rest_is_refutable(rest)
+            }
+            BindingMethod::Named { bindings, rest, .. } => {
+                bindings.iter().any(|binding| {
+                    binding
+                        .binding
+                        .as_ref()
+                        .is_some_and(|sub_binding| sub_binding.binding.is_refutable())
+                }) || rest_is_refutable(rest)
+            }
+        }
+    }
+}
+
+impl<'source> RestBinding<'source> {
+    /// Parses the binding following an ellipses token.
+    ///
+    /// A rest binding must be the final binding of its tuple,
+    /// so it may be followed by an optional trailing comma.
+    fn new(
+        ellipses_token: Token<'source>,
+        lexer: &mut Peekable<Lexer<'source>>,
+        diagnostics: &mut Diagnostics<'source>,
+    ) -> Option<Box<Self>> {
+        lexer.next();
+        match Binding::new(lexer) {
+            Ok(binding) => {
+                let comma_token = diagnostics
+                    .wrap(lexer.peek().copied())
+                    .filter(|t| t.lexigram == Lexigram::Comma);
+                if comma_token.is_some() {
+                    lexer.next();
+                }
+                Some(Box::new(RestBinding {
+                    ellipses_token,
+                    binding,
+                    comma_token,
+                }))
+            }
+            Err(e) => {
+                diagnostics.errors.push(e);
+                None
+            }
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Function<'source> {
     pub with_token: Token<'source>,
     pub argument: Option<Binding<'source>>,
@@ -1078,15 +1746,19 @@ pub struct Function<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
-#[expect(
-    clippy::large_enum_variant,
-    reason = "this is already inside of a (very large) boxed block"
-)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BlockResult<'source> {
     Expression(Option<Box<Expression<'source>>>),
     Function(Function<'source>),
 }
 
+impl Function<'_> {
+    /// The doc comment preceding the `with` keyword, if any.
+    pub fn doc_comment(&self, doc_comments: &DocComments) -> Option<Span> {
+        doc_comments.get(self.with_token)
+    }
+}
+
 impl BlockResult<'_> {
     pub fn is_empty(&self) -> bool {
         match self {
@@ -1115,6 +1787,7 @@ impl<'source> From<Function<'source>> for BlockResult<'source> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[make_dst_factory(pub)]
 pub struct Block<'source> {
     pub result: BlockResult<'source>,
@@ -1129,9 +1802,32 @@ impl Default for Box<Block<'_>> {
 }
 
 impl<'source> Block<'source> {
+    /// Parses an entire program.
+    ///
+    /// Unlike [`Block::new`], this reports any input which remains after the top-level block.
+    pub fn program(lexer: &mut Peekable<Lexer<'source>>) -> Box<Self> {
+        let mut block = Self::new(lexer);
+        if let Some(token) = block.diagnostics.wrap(lexer.next()) {
+            block.diagnostics.errors.push(Error::TrailingInput(token));
+        }
+        block
+    }
+
     pub fn new(lexer: &mut Peekable<Lexer<'source>>) -> Box<Self> {
-        let mut diagnostics = Diagnostics::default();
-        let mut statements = Vec::new();
+        Self::parse(lexer, Vec::new(), Diagnostics::default(), None)
+    }
+
+    /// Continues parsing a block after the given statements.
+    ///
+    /// If provided, `splice` is offered each statement as it is parsed,
+    /// and may return the remainder of the block to end parsing early.
+    /// This is propagated to the body of a function, but not to any other inner blocks.
+    fn parse(
+        lexer: &mut Peekable<Lexer<'source>>,
+        mut statements: Vec<Statement<'source>>,
+        mut diagnostics: Diagnostics<'source>,
+        mut splice: Option<&mut Splice<'_, 'source>>,
+    ) -> Box<Self> {
         loop {
             let statement = match diagnostics.wrap(lexer.peek().copied()) {
                 Some(Token {
@@ -1180,7 +1876,10 @@ impl<'source> Block<'source> {
                     };
                     let semicolon_token =
                         st_diagnostics.next_if(&mut *lexer, &[Lexigram::Semicolon]);
-                    let block = Block::new(&mut *lexer);
+                    if semicolon_token.is_none() {
+                        st_diagnostics.synchronize(lexer);
+                    }
+                    let block = Block::parse(lexer, Vec::new(), Diagnostics::default(), splice);
 
                     return Self::build(
                         Function {
@@ -1202,15 +1901,63 @@ impl<'source> Block<'source> {
                 _ => match Evaluation::try_expression(&mut *lexer) {
                     Ok(evaluation) => Statement::Evaluation(evaluation),
                     Err(expression) => {
-                        return Self::build(
-                            BlockResult::Expression(expression),
-                            diagnostics,
-                            statements,
-                        );
+                        let mut st_diagnostics = Diagnostics::default();
+                        let ends_block = |lexer: &mut Peekable<Lexer<'source>>| {
+                            matches!(
+                                lexer.peek(),
+                                None | Some(Ok(Token {
+                                    lexigram: Lexigram::End | Lexigram::Else | Lexigram::CloseBrace,
+                                    ..
+                                }))
+                            )
+                        };
+                        if !ends_block(lexer) {
+                            match lexer.peek().copied() {
+                                // Don't pile more errors onto an expression that is already malformed.
+                                _ if expression
+                                    .as_ref()
+                                    .is_some_and(|x| !x.diagnostics.errors.is_empty()) => {}
+                                Some(Ok(actual)) if expression.is_some() => {
+                                    st_diagnostics.errors.push(Error::MissingToken {
+                                        expected: &[Lexigram::Semicolon],
+                                        actual: Some(actual),
+                                    });
+                                }
+                                Some(Ok(actual)) => {
+                                    st_diagnostics.errors.push(Error::UnexpectedToken(actual));
+                                }
+                                // Lexer errors are reported when they are first peeked.
+                                Some(Err(_)) | None => {}
+                            }
+                        }
+                        if !st_diagnostics.synchronize(lexer) && ends_block(lexer) {
+                            // The final expression of a block is its result,
+                            // so it isn't followed by a semicolon.
+                            diagnostics.errors.append(&mut st_diagnostics.errors);
+                            return Self::build(
+                                BlockResult::Expression(expression),
+                                diagnostics,
+                                statements,
+                            );
+                        }
+                        Statement::Evaluation(Evaluation {
+                            binding: None,
+                            expression,
+                            else_branch: None,
+                            semicolon_token: None,
+                            diagnostics: st_diagnostics,
+                        })
                     }
                 },
             };
             statements.push(statement);
+            if let Some(splice) = &mut splice
+                && let Some(tail) = splice(&statements[statements.len() - 1])
+            {
+                statements.extend(tail.statements);
+                diagnostics.errors.extend(tail.errors);
+                return Self::build(tail.result, diagnostics, statements);
+            }
         }
     }
 }
@@ -0,0 +1,38 @@
+use crate::Block;
+use serde::Serialize;
+
+/// The version of the serialized syntax tree's format.
+///
+/// This is incremented whenever a change to the syntax tree changes its serialized form,
+/// such as renaming a field or adding a node.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// A program's syntax tree, tagged with the [`SCHEMA_VERSION`] it was serialized with.
+///
+/// Structures are serialized as maps of their fields,
+/// and enums are externally tagged by the name of their variant.
+/// Spans are the byte offsets of their `start` and `end` within the source.
+///
+/// ```rust
+/// use espy_ears::{Block, Document};
+/// use espy_eyes::Lexer;
+///
+/// let block = Block::program(&mut Lexer::from("1 + 2").peekable());
+/// let json = serde_json::to_value(Document::new(&block)).unwrap();
+/// assert_eq!(json["version"], espy_ears::SCHEMA_VERSION);
+/// assert_eq!(json["program"]["result"]["Expression"]["contents"][1]["Number"]["span"]["start"], 4);
+/// ```
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Document<'a, 'source> {
+    pub version: u32,
+    pub program: &'a Block<'source>,
+}
+
+impl<'a, 'source> Document<'a, 'source> {
+    pub fn new(program: &'a Block<'source>) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            program,
+        }
+    }
+}
@@ -1,11 +1,9 @@
 use crate::*;
+use espy_eyes::{DocComments, Span};
 
 macro_rules! token {
     ($name:ident: $lexigram:ident = $origin:literal) => {
-        const $name: Token = Token {
-            origin: $origin,
-            lexigram: Lexigram::$lexigram,
-        };
+        const $name: Token = Token::new(Span::new(0, $origin.len() as u32), Lexigram::$lexigram);
     };
 }
 
@@ -25,62 +23,90 @@ token!(CLOSE_BRACE: CloseBrace = "}");
 token!(THEN: Then = "then");
 token!(WITH: With = "with");
 token!(DOUBLE_ARROW: DoubleArrow = "=>");
+token!(ELLIPSES: Ellipses = "...");
 token!(SEMICOLON: Semicolon = ";");
 token!(SINGLE_EQUAL: SingleEqual = "=");
 
-fn ident<'source>(origin: &'source str) -> Token<'source> {
-    Token {
-        origin,
-        lexigram: Lexigram::Ident,
-    }
+/// Moves a token to the given byte offset of the source.
+trait At {
+    fn at(self, start: u32) -> Self;
 }
 
-fn number<'source>(origin: &'source str) -> Token<'source> {
-    Token {
-        origin,
-        lexigram: Lexigram::Number,
+impl At for Token<'_> {
+    fn at(mut self, start: u32) -> Self {
+        self.span.start = start;
+        self
     }
 }
 
+fn ident<'source>(origin: &str, at: u32) -> Token<'source> {
+    Token::new(Span::new(at, origin.len() as u32), Lexigram::Ident)
+}
+
+fn number<'source>(origin: &str, at: u32) -> Token<'source> {
+    Token::new(Span::new(at, origin.len() as u32), Lexigram::Number)
+}
+
 macro_rules! node {
     ($name:ident: $node:ident = $origin:literal as $lexigram:ident) => {
-        const $name: Node = Node::$node(Token {
-            origin: $origin,
-            lexigram: Lexigram::$lexigram,
-        });
+        fn $name<'source>(at: u32) -> Node<'source> {
+            Node::$node(Token::new(
+                Span::new(at, $origin.len() as u32),
+                Lexigram::$lexigram,
+            ))
+        }
     };
 }
 
-fn number_node<'source>(origin: &'source str) -> Node<'source> {
-    Node::Number(number(origin))
+fn number_node<'source>(origin: &str, at: u32) -> Node<'source> {
+    Node::Number(number(origin, at))
+}
+
+fn variable<'source>(origin: &str, at: u32) -> Node<'source> {
+    Node::Variable(ident(origin, at))
 }
 
-fn variable<'source>(origin: &'source str) -> Node<'source> {
-    Node::Variable(ident(origin))
+fn group<'source>(open_paren: u32, close_paren: u32) -> Node<'source> {
+    Node::Group {
+        open_paren: OPEN_PAREN.at(open_paren),
+        close_paren: CLOSE_PAREN.at(close_paren),
+    }
 }
 
-node!(PIPE: Pipe = "|>" as Triangle);
-node!(MUL: Mul = "*" as Star);
-node!(BITWISE_AND: BitwiseAnd = "&" as Ampersand);
-node!(BITWISE_XOR: BitwiseXor = "^" as Caret);
-node!(BITWISE_OR: BitwiseOr = "|" as Pipe);
-node!(TUPLE: Tuple = "," as Comma);
+node!(pipe: Pipe = "|>" as Triangle);
+node!(mul: Mul = "*" as Star);
+node!(add: Add = "+" as Plus);
+node!(bitwise_and: BitwiseAnd = "&" as Ampersand);
+node!(bitwise_xor: BitwiseXor = "^" as Caret);
+node!(bitwise_or: BitwiseOr = "|" as Pipe);
+node!(tuple: Tuple = "," as Comma);
+node!(range: Range = ".." as DotDot);
+node!(range_inclusive: RangeInclusive = "..=" as DotDotEqual);
 
+/// A `let` statement beginning at `at`, written as `let {origin} = {expression};`.
 fn binding<'source>(
-    origin: &'source str,
+    at: u32,
+    origin: &str,
     expression: Box<Expression<'source>>,
 ) -> Statement<'source> {
+    let ident = ident(origin, at + 4);
+    let semicolon = expression
+        .last_token
+        .expect("expression must not be empty")
+        .span
+        .end();
     Statement::Evaluation(Evaluation {
         binding: Some(LetBinding {
-            let_token: LET,
+            let_token: LET.at(at),
             binding: Some(Binding {
-                method: BindingMethod::Single(ident(origin)),
+                method: BindingMethod::Single(ident),
                 diagnostics: Diagnostics::default(),
             }),
-            equals_token: Some(SINGLE_EQUAL),
+            equals_token: Some(SINGLE_EQUAL.at(ident.span.end() + 1)),
         }),
         expression: Some(expression),
-        semicolon_token: Some(SEMICOLON),
+        else_branch: None,
+        semicolon_token: Some(SEMICOLON.at(semicolon)),
         diagnostics: Diagnostics::default(),
     })
 }
@@ -117,20 +143,20 @@ fn named_tuple() {
     let source = "x: 1, y: 2";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
-        ident("x"),
-        number("2"),
+        ident("x", 0),
+        number("2", 9),
         [
-            number_node("1"),
+            number_node("1", 3),
             Node::Name {
-                name: ident("x"),
-                colon_token: COLON,
+                name: ident("x", 0),
+                colon_token: COLON.at(1),
             },
-            number_node("2"),
+            number_node("2", 9),
             Node::Name {
-                name: ident("y"),
-                colon_token: COLON,
+                name: ident("y", 6),
+                colon_token: COLON.at(7),
             },
-            TUPLE,
+            tuple(4),
         ]
         .into_iter(),
     ));
@@ -143,23 +169,34 @@ fn block_expression() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = statements(
         [binding(
+            0,
             "x",
             expression(
-                OPEN_BRACE,
-                CLOSE_BRACE,
-                [Node::Block(Block::build(
-                    expression(
-                        ident("y"),
-                        number("3"),
-                        [variable("y"), number_node("3"), MUL].into_iter(),
-                    )
-                    .into(),
-                    Diagnostics::default(),
-                    [binding(
-                        "y",
-                        expression(number("2"), number("2"), [number_node("2")].into_iter()),
-                    )],
-                ))]
+                OPEN_BRACE.at(8),
+                CLOSE_BRACE.at(27),
+                [Node::Block {
+                    label: None,
+                    open_brace: OPEN_BRACE.at(8),
+                    block: Block::build(
+                        expression(
+                            ident("y", 21),
+                            number("3", 25),
+                            [variable("y", 21), number_node("3", 25), mul(23)].into_iter(),
+                        )
+                        .into(),
+                        Diagnostics::default(),
+                        [binding(
+                            10,
+                            "y",
+                            expression(
+                                number("2", 18),
+                                number("2", 18),
+                                [number_node("2", 18)].into_iter(),
+                            ),
+                        )],
+                    ),
+                    close_brace: Some(CLOSE_BRACE.at(27)),
+                }]
                 .into_iter(),
             ),
         )]
@@ -174,31 +211,32 @@ fn if_expression() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = statements(
         [binding(
+            0,
             "x",
             expression(
-                IF,
-                END,
+                IF.at(8),
+                END.at(40),
                 [If {
-                    if_token: IF,
+                    if_token: IF.at(8),
                     condition: Some(expression(
-                        ident("condition"),
-                        ident("condition"),
-                        [variable("condition")].into_iter(),
+                        ident("condition", 11),
+                        ident("condition", 11),
+                        [variable("condition", 11)].into_iter(),
                     )),
-                    then_token: Some(THEN),
+                    then_token: Some(THEN.at(21)),
                     first: result(expression(
-                        number("1"),
-                        number("1"),
-                        [number_node("1")].into_iter(),
+                        number("1", 26),
+                        number("1", 26),
+                        [number_node("1", 26)].into_iter(),
                     )),
-                    else_token: Some(ELSE),
-                    else_kind: Some(THEN),
+                    else_token: Some(ELSE.at(28)),
+                    else_kind: Some(THEN.at(33)),
                     second: result(expression(
-                        number("2"),
-                        number("2"),
-                        [number_node("2")].into_iter(),
+                        number("2", 38),
+                        number("2", 38),
+                        [number_node("2", 38)].into_iter(),
                     )),
-                    end_token: Some(END),
+                    end_token: Some(END.at(40)),
                     diagnostics: Diagnostics::default(),
                 }
                 .into()]
@@ -216,55 +254,56 @@ fn if_else() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = statements(
         [binding(
+            0,
             "x",
             expression(
-                IF,
-                END,
+                IF.at(8),
+                END.at(61),
                 [If {
-                    if_token: IF,
+                    if_token: IF.at(8),
                     condition: Some(expression(
-                        ident("condition"),
-                        ident("condition"),
-                        [variable("condition")].into_iter(),
+                        ident("condition", 11),
+                        ident("condition", 11),
+                        [variable("condition", 11)].into_iter(),
                     )),
-                    then_token: Some(THEN),
+                    then_token: Some(THEN.at(21)),
                     first: result(expression(
-                        number("1"),
-                        number("1"),
-                        [number_node("1")].into_iter(),
+                        number("1", 26),
+                        number("1", 26),
+                        [number_node("1", 26)].into_iter(),
                     )),
-                    else_token: Some(ELSE),
-                    else_kind: Some(IF),
+                    else_token: Some(ELSE.at(28)),
+                    else_kind: Some(IF.at(33)),
                     second: result(expression(
                         None,
                         None,
                         [If {
-                            if_token: IF,
+                            if_token: IF.at(33),
                             condition: Some(expression(
-                                ident("other"),
-                                ident("other"),
-                                [variable("other")].into_iter(),
+                                ident("other", 36),
+                                ident("other", 36),
+                                [variable("other", 36)].into_iter(),
                             )),
-                            then_token: Some(THEN),
+                            then_token: Some(THEN.at(42)),
                             first: result(expression(
-                                number("2"),
-                                number("2"),
-                                [number_node("2")].into_iter(),
+                                number("2", 47),
+                                number("2", 47),
+                                [number_node("2", 47)].into_iter(),
                             )),
-                            else_token: Some(ELSE),
-                            else_kind: Some(THEN),
+                            else_token: Some(ELSE.at(49)),
+                            else_kind: Some(THEN.at(54)),
                             second: result(expression(
-                                number("3"),
-                                number("3"),
-                                [number_node("3")].into_iter(),
+                                number("3", 59),
+                                number("3", 59),
+                                [number_node("3", 59)].into_iter(),
                             )),
-                            end_token: Some(END),
+                            end_token: Some(END.at(61)),
                             diagnostics: Diagnostics::default(),
                         }
                         .into()]
                         .into_iter(),
                     )),
-                    end_token: Some(END),
+                    end_token: Some(END.at(61)),
                     diagnostics: Diagnostics::default(),
                 }
                 .into()]
@@ -281,12 +320,12 @@ fn incomplete_expression() {
     let source = "1 * 2,";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(Expression::build(
-        Some(number("1")),
-        Some(COMMA),
+        Some(number("1", 0)),
+        Some(COMMA.at(5)),
         Diagnostics {
             errors: vec![Error::IncompleteExpression],
         },
-        [number_node("1"), number_node("2"), MUL],
+        [number_node("1", 0), number_node("2", 4), mul(2)],
     ));
     assert_eq!(actual, expected);
 }
@@ -300,23 +339,24 @@ fn malformed_binding() {
             binding: Some(LetBinding {
                 let_token: LET,
                 binding: Some(Binding {
-                    method: BindingMethod::Single(ident("x")),
+                    method: BindingMethod::Single(ident("x", 4)),
                     diagnostics: Diagnostics::default(),
                 }),
                 equals_token: None,
             }),
             // Despite being malformed, this expression is still parsed correctly!
             expression: Some(expression(
-                number("2"),
-                number("2"),
-                [number_node("2")].into_iter(),
+                number("2", 6),
+                number("2", 6),
+                [number_node("2", 6)].into_iter(),
             )),
+            else_branch: None,
             semicolon_token: None,
             diagnostics: Diagnostics {
                 errors: vec![
                     Error::MissingToken {
                         expected: &[Lexigram::SingleEqual],
-                        actual: Some(number("2")),
+                        actual: Some(number("2", 6)),
                     },
                     Error::MissingToken {
                         expected: &[Lexigram::Semicolon],
@@ -330,21 +370,144 @@ fn malformed_binding() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn rest_bindings() {
+    let source = "let (head, ...tail) = xs; let {name, ...rest} = record;";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let single = |origin, at| Binding {
+        method: BindingMethod::Single(ident(origin, at)),
+        diagnostics: Diagnostics::default(),
+    };
+    let expected = statements(
+        [
+            Statement::Evaluation(Evaluation {
+                binding: Some(LetBinding {
+                    let_token: LET,
+                    binding: Some(Binding {
+                        method: BindingMethod::Numeric {
+                            open_paren: OPEN_PAREN.at(4),
+                            bindings: [NumericBinding {
+                                binding: single("head", 5),
+                                comma_token: Some(COMMA.at(9)),
+                            }]
+                            .into(),
+                            rest: Some(Box::new(RestBinding {
+                                ellipses_token: ELLIPSES.at(11),
+                                binding: single("tail", 14),
+                                comma_token: None,
+                            })),
+                            close_paren: Some(CLOSE_PAREN.at(18)),
+                        },
+                        diagnostics: Diagnostics::default(),
+                    }),
+                    equals_token: Some(SINGLE_EQUAL.at(20)),
+                }),
+                expression: Some(expression(
+                    ident("xs", 22),
+                    ident("xs", 22),
+                    [variable("xs", 22)].into_iter(),
+                )),
+                else_branch: None,
+                semicolon_token: Some(SEMICOLON.at(24)),
+                diagnostics: Diagnostics::default(),
+            }),
+            Statement::Evaluation(Evaluation {
+                binding: Some(LetBinding {
+                    let_token: LET.at(26),
+                    binding: Some(Binding {
+                        method: BindingMethod::Named {
+                            open_brace: OPEN_BRACE.at(30),
+                            bindings: [NamedBinding {
+                                field: ident("name", 31),
+                                binding: None,
+                                comma_token: Some(COMMA.at(35)),
+                            }]
+                            .into(),
+                            rest: Some(Box::new(RestBinding {
+                                ellipses_token: ELLIPSES.at(37),
+                                binding: single("rest", 40),
+                                comma_token: None,
+                            })),
+                            close_brace: Some(CLOSE_BRACE.at(44)),
+                        },
+                        diagnostics: Diagnostics::default(),
+                    }),
+                    equals_token: Some(SINGLE_EQUAL.at(46)),
+                }),
+                expression: Some(expression(
+                    ident("record", 48),
+                    ident("record", 48),
+                    [variable("record", 48)].into_iter(),
+                )),
+                else_branch: None,
+                semicolon_token: Some(SEMICOLON.at(54)),
+                diagnostics: Diagnostics::default(),
+            }),
+        ]
+        .into_iter(),
+    );
+    assert_eq!(actual, expected);
+}
+
 #[test]
 fn bitwise_operators() {
     let source = "1 | 2 & 3 ^ 4";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
-        number("1"),
-        number("4"),
+        number("1", 0),
+        number("4", 12),
+        [
+            number_node("1", 0),
+            number_node("2", 4),
+            number_node("3", 8),
+            bitwise_and(6),
+            number_node("4", 12),
+            bitwise_xor(10),
+            bitwise_or(2),
+        ]
+        .into_iter(),
+    ));
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn range_operators() {
+    let source = "0..n + 1, 1..=n";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let expected = result(expression(
+        number("0", 0),
+        ident("n", 14),
         [
-            number_node("1"),
-            number_node("2"),
-            number_node("3"),
-            BITWISE_AND,
-            number_node("4"),
-            BITWISE_XOR,
-            BITWISE_OR,
+            number_node("0", 0),
+            variable("n", 3),
+            number_node("1", 7),
+            add(5),
+            range(1),
+            number_node("1", 10),
+            variable("n", 14),
+            range_inclusive(11),
+            tuple(8),
+        ]
+        .into_iter(),
+    ));
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn not_operator() {
+    let source = "!a and !!b";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let bang = |at| Token::new(Span::new(at, 1), Lexigram::Bang);
+    let expected = result(expression(
+        bang(0),
+        ident("b", 9),
+        [
+            variable("a", 1),
+            Node::Not(bang(0)),
+            variable("b", 9),
+            Node::Not(bang(8)),
+            Node::Not(bang(7)),
+            Node::LogicalAnd(Token::new(Span::new(3, 3), Lexigram::And)),
         ]
         .into_iter(),
     ));
@@ -356,16 +519,18 @@ fn nested_parens() {
     let source = "1 | (2 & (3 ^ 4))";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
-        number("1"),
-        CLOSE_PAREN,
+        number("1", 0),
+        CLOSE_PAREN.at(16),
         [
-            number_node("1"),
-            number_node("2"),
-            number_node("3"),
-            number_node("4"),
-            BITWISE_XOR,
-            BITWISE_AND,
-            BITWISE_OR,
+            number_node("1", 0),
+            number_node("2", 5),
+            number_node("3", 10),
+            number_node("4", 14),
+            bitwise_xor(12),
+            group(9, 15),
+            bitwise_and(7),
+            group(4, 16),
+            bitwise_or(2),
         ]
         .into_iter(),
     ));
@@ -377,20 +542,20 @@ fn pipe_operator() {
     let source = "square 2 |> add 4 |> square ()";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
-        ident("square"),
-        CLOSE_PAREN,
+        ident("square", 0),
+        CLOSE_PAREN.at(29),
         [
-            variable("square"),
-            number_node("2"),
-            Node::Call(number("2")),
-            variable("add"),
-            PIPE,
-            number_node("4"),
-            Node::Call(number("4")),
-            variable("square"),
-            PIPE,
-            Node::Unit(OPEN_PAREN, CLOSE_PAREN),
-            Node::Call(OPEN_PAREN),
+            variable("square", 0),
+            number_node("2", 7),
+            Node::Call(number("2", 7)),
+            variable("add", 12),
+            pipe(9),
+            number_node("4", 16),
+            Node::Call(number("4", 16)),
+            variable("square", 21),
+            pipe(18),
+            Node::Unit(OPEN_PAREN.at(28), CLOSE_PAREN.at(29)),
+            Node::Call(OPEN_PAREN.at(28)),
         ]
         .into_iter(),
     ));
@@ -403,28 +568,33 @@ fn function() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = Block::build(
         Function {
-            with_token: WITH,
+            with_token: WITH.at(18),
             argument: Some(Binding {
-                method: BindingMethod::Single(ident("x")),
+                method: BindingMethod::Single(ident("x", 23)),
                 diagnostics: Diagnostics::default(),
             }),
             colon_token: None,
             input: None,
             single_arrow_token: None,
             output: None,
-            semicolon_token: Some(SEMICOLON),
+            semicolon_token: Some(SEMICOLON.at(24)),
             block: result(expression(
-                ident("x"),
-                ident("captured"),
-                [variable("x"), variable("captured"), MUL].into_iter(),
+                ident("x", 26),
+                ident("captured", 30),
+                [variable("x", 26), variable("captured", 30), mul(28)].into_iter(),
             )),
             diagnostics: Diagnostics::default(),
         }
         .into(),
         Diagnostics::default(),
         [binding(
+            0,
             "captured",
-            expression(number("1"), number("1"), [number_node("1")].into_iter()),
+            expression(
+                number("1", 15),
+                number("1", 15),
+                [number_node("1", 15)].into_iter(),
+            ),
         )],
     );
     assert_eq!(actual, expected);
@@ -436,45 +606,61 @@ fn match_expression() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
         MATCH,
-        END,
+        END.at(41),
         [Match::build(
             MATCH,
             Some(expression(
-                number("0"),
-                number("0"),
-                [number_node("0")].into_iter(),
+                number("0", 6),
+                number("0", 6),
+                [number_node("0", 6)].into_iter(),
             )),
-            Some(THEN),
-            Some(END),
+            Some(THEN.at(8)),
+            Some(END.at(41)),
             Diagnostics::default(),
             [
                 MatchCase {
-                    let_token: Some(LET),
-                    binding: Some(ident("x")),
-                    equals_token: Some(SINGLE_EQUAL),
-                    case: expression(number("1"), number("1"), [number_node("1")].into_iter())
-                        .into(),
-                    arrow_token: Some(DOUBLE_ARROW),
+                    let_token: Some(LET.at(13)),
+                    binding: Some(ident("x", 17)),
+                    colon_token: None,
+                    ty: None,
+                    equals_token: Some(SINGLE_EQUAL.at(19)),
+                    case: expression(
+                        number("1", 21),
+                        number("1", 21),
+                        [number_node("1", 21)].into_iter(),
+                    )
+                    .into(),
+                    if_token: None,
+                    guard: None,
+                    arrow_token: Some(DOUBLE_ARROW.at(23)),
                     expression: Some(expression(
-                        ident("x"),
-                        number("2"),
-                        [variable("x"), number_node("2"), MUL].into_iter(),
+                        ident("x", 26),
+                        number("2", 30),
+                        [variable("x", 26), number_node("2", 30), mul(28)].into_iter(),
                     )),
-                    semicolon_token: Some(SEMICOLON),
+                    semicolon_token: Some(SEMICOLON.at(31)),
                 },
                 MatchCase {
                     let_token: None,
                     binding: None,
+                    colon_token: None,
+                    ty: None,
                     equals_token: None,
-                    case: expression(number("3"), number("3"), [number_node("3")].into_iter())
-                        .into(),
-                    arrow_token: Some(DOUBLE_ARROW),
+                    case: expression(
+                        number("3", 33),
+                        number("3", 33),
+                        [number_node("3", 33)].into_iter(),
+                    )
+                    .into(),
+                    if_token: None,
+                    guard: None,
+                    arrow_token: Some(DOUBLE_ARROW.at(35)),
                     expression: Some(expression(
-                        number("4"),
-                        number("4"),
-                        [number_node("4")].into_iter(),
+                        number("4", 38),
+                        number("4", 38),
+                        [number_node("4", 38)].into_iter(),
                     )),
-                    semicolon_token: Some(SEMICOLON),
+                    semicolon_token: Some(SEMICOLON.at(39)),
                 },
             ],
         )
@@ -490,31 +676,32 @@ fn enum_creation() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = statements(
         [binding(
+            0,
             "Option",
             expression(
-                ENUM,
-                END,
+                ENUM.at(13),
+                END.at(38),
                 [Node::Enum(Box::new(Enum {
-                    enum_token: ENUM,
+                    enum_token: ENUM.at(13),
                     variants: Some(expression(
-                        ident("Some"),
-                        CLOSE_PAREN,
+                        ident("Some", 18),
+                        CLOSE_PAREN.at(36),
                         [
-                            variable("any"),
+                            variable("any", 24),
                             Node::Name {
-                                name: ident("Some"),
-                                colon_token: COLON,
+                                name: ident("Some", 18),
+                                colon_token: COLON.at(22),
                             },
-                            Node::Unit(OPEN_PAREN, CLOSE_PAREN),
+                            Node::Unit(OPEN_PAREN.at(35), CLOSE_PAREN.at(36)),
                             Node::Name {
-                                name: ident("None"),
-                                colon_token: COLON,
+                                name: ident("None", 29),
+                                colon_token: COLON.at(33),
                             },
-                            TUPLE,
+                            tuple(27),
                         ]
                         .into_iter(),
                     )),
-                    end_token: Some(END),
+                    end_token: Some(END.at(38)),
                     diagnostics: Diagnostics::default(),
                 }))]
                 .into_iter(),
@@ -530,22 +717,22 @@ fn field_precedence() {
     let source = "something.field |> Iterator.next ()";
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = result(expression(
-        ident("something"),
-        CLOSE_PAREN,
+        ident("something", 0),
+        CLOSE_PAREN.at(34),
         [
-            variable("something"),
+            variable("something", 0),
             Node::Field {
-                dot_token: DOT,
-                index: ident("field"),
+                dot_token: DOT.at(9),
+                index: ident("field", 10),
             },
-            variable("Iterator"),
+            variable("Iterator", 19),
             Node::Field {
-                dot_token: DOT,
-                index: ident("next"),
+                dot_token: DOT.at(27),
+                index: ident("next", 28),
             },
-            PIPE,
-            Node::Unit(OPEN_PAREN, CLOSE_PAREN),
-            Node::Call(OPEN_PAREN),
+            pipe(16),
+            Node::Unit(OPEN_PAREN.at(33), CLOSE_PAREN.at(34)),
+            Node::Call(OPEN_PAREN.at(33)),
         ]
         .into_iter(),
     ));
@@ -558,13 +745,13 @@ fn tuple_indexing() {
     let actual = Block::new(&mut Lexer::from(source).peekable());
     let expected = Block::build(
         expression(
-            ident("x"),
-            number("1"),
+            ident("x", 14),
+            number("1", 16),
             [
-                variable("x"),
+                variable("x", 14),
                 Node::Field {
-                    dot_token: DOT,
-                    index: number("1"),
+                    dot_token: DOT.at(15),
+                    index: number("1", 16),
                 },
             ]
             .into_iter(),
@@ -572,11 +759,12 @@ fn tuple_indexing() {
         .into(),
         Diagnostics::default(),
         [binding(
+            0,
             "x",
             expression(
-                number("1"),
-                number("2"),
-                [number_node("1"), number_node("2"), TUPLE].into_iter(),
+                number("1", 8),
+                number("2", 11),
+                [number_node("1", 8), number_node("2", 11), tuple(9)].into_iter(),
             ),
         )],
     );
@@ -587,20 +775,726 @@ fn tuple_indexing() {
 fn string() {
     let source = "\"string\"";
     let actual = Block::new(&mut Lexer::from(source).peekable());
+    let string = Token::new(Span::new(0, 8), Lexigram::String);
     let expected = result(expression(
-        Token {
-            origin: "\"string\"",
-            lexigram: Lexigram::String,
-        },
-        Token {
-            origin: "\"string\"",
-            lexigram: Lexigram::String,
-        },
-        [Node::String(Token {
-            origin: "\"string\"",
-            lexigram: Lexigram::String,
-        })]
+        string,
+        string,
+        [Node::String(string)].into_iter(),
+    ));
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn interpolated_string() {
+    let source = "\"a{x}b{1}\"";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let start = Token::new(Span::new(0, 3), Lexigram::StringStart);
+    let middle = Token::new(Span::new(4, 3), Lexigram::StringMiddle);
+    let end = Token::new(Span::new(8, 2), Lexigram::StringEnd);
+    let expected = result(expression(
+        start,
+        end,
+        [Node::Interpolation(Box::new(Interpolation {
+            start,
+            segments: vec![
+                InterpolationSegment {
+                    expression: Some(expression(
+                        ident("x", 3),
+                        ident("x", 3),
+                        [variable("x", 3)].into_iter(),
+                    )),
+                    string: Some(middle),
+                },
+                InterpolationSegment {
+                    expression: Some(expression(
+                        number("1", 7),
+                        number("1", 7),
+                        [number_node("1", 7)].into_iter(),
+                    )),
+                    string: Some(end),
+                },
+            ],
+            diagnostics: Diagnostics::default(),
+        }))]
         .into_iter(),
     ));
     assert_eq!(actual, expected);
+
+    // Errors within an interpolated expression point inside of the string.
+    let source = "\"{x ]}\"";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let BlockResult::Expression(Some(result)) = &actual.result else {
+        panic!("expected an expression");
+    };
+    let [Node::Interpolation(interpolation)] = &result.contents else {
+        panic!("expected an interpolated string");
+    };
+    assert_eq!(
+        interpolation.diagnostics.errors,
+        [Error::MissingToken {
+            expected: &[Lexigram::StringMiddle, Lexigram::StringEnd],
+            actual: Some(Token::new(Span::new(4, 1), Lexigram::CloseSquare)),
+        }]
+    );
+}
+
+#[test]
+fn invalid_digit() {
+    let source = "0b102";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let error = || {
+        Error::Lexer(lexer::Error {
+            span: Span::new(4, 1),
+            kind: lexer::ErrorKind::InvalidDigit,
+        })
+    };
+    let expected = Block::build(
+        Expression::build(
+            None,
+            None,
+            Diagnostics {
+                errors: vec![error()],
+            },
+            [],
+        )
+        .into(),
+        Diagnostics {
+            errors: vec![error()],
+        },
+        [],
+    );
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn lossless() {
+    let source = "# leading comment
+let Option = enum Some: any, None: () end; # trailing comment
+let (a, ...rest) = 1, 2, 3;
+let {x, y: z} = x: 1, y: 2;
+
+let f = {
+    with n: i64 -> i64;
+    n * (n + 1)   # math
+};
+set counter = *counter + !a;
+let m = match f 2 then
+    1 => \"one\";
+    let v => if v > 2 and true then v else if false then 0 else then -v end;
+end;
+(f 3).0 |> g (), 0..=10, `raw ident`
+
+# comment at the end of the file
+";
+    let block = Block::new(&mut Lexer::from(source).peekable());
+    assert_eq!(
+        block.tokens()[0].leading_trivia(source, None).slice(source),
+        "# leading comment\n"
+    );
+    assert_eq!(block.to_source(source), source);
+}
+
+#[test]
+fn error_recovery() {
+    let source = "let a = 1 ] 2; let + = b; set c = 4 5 ]; c ] d; d";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let close_square = |at| Token::new(Span::new(at, 1), Lexigram::CloseSquare);
+    let errors = |statement: &Statement<'_>| match statement {
+        Statement::Evaluation(evaluation) => evaluation.diagnostics.errors.len(),
+        Statement::Set(set) => set.diagnostics.errors.len(),
+    };
+    assert!(actual.diagnostics.errors.is_empty());
+    assert_eq!(actual.statements.len(), 4);
+    assert_eq!(
+        actual.statements.iter().map(errors).collect::<Vec<_>>(),
+        [1, 1, 1, 1]
+    );
+    let Statement::Evaluation(evaluation) = &actual.statements[0] else {
+        panic!("expected a let statement");
+    };
+    assert_eq!(
+        evaluation.diagnostics.errors[0],
+        Error::MissingToken {
+            expected: &[Lexigram::Semicolon],
+            actual: Some(close_square(10)),
+        }
+    );
+    let Statement::Evaluation(evaluation) = &actual.statements[1] else {
+        panic!("expected a let statement");
+    };
+    assert_eq!(
+        evaluation.diagnostics.errors[0],
+        Error::MissingToken {
+            expected: &[
+                Lexigram::Ident,
+                Lexigram::Discard,
+                Lexigram::OpenParen,
+                Lexigram::OpenBrace,
+                Lexigram::Number,
+                Lexigram::String,
+                Lexigram::True,
+                Lexigram::False,
+            ],
+            actual: Some(Token::new(Span::new(19, 1), Lexigram::Plus)),
+        }
+    );
+    let Statement::Set(set) = &actual.statements[2] else {
+        panic!("expected a set statement");
+    };
+    assert_eq!(
+        set.diagnostics.errors[0],
+        Error::MissingToken {
+            expected: &[Lexigram::Semicolon],
+            actual: Some(close_square(38)),
+        }
+    );
+    let Statement::Evaluation(evaluation) = &actual.statements[3] else {
+        panic!("expected an expression statement");
+    };
+    assert_eq!(
+        evaluation.diagnostics.errors[0],
+        Error::MissingToken {
+            expected: &[Lexigram::Semicolon],
+            actual: Some(close_square(43)),
+        }
+    );
+    assert_eq!(
+        actual.result,
+        BlockResult::Expression(Some(expression(
+            ident("d", 48),
+            ident("d", 48),
+            [variable("d", 48)].into_iter(),
+        )))
+    );
+}
+
+#[test]
+fn nested_error_recovery() {
+    let source = "let f = { let x = 1 ]; x }; match f then 1 => ]; 2 => 3; end";
+    let actual = Block::new(&mut Lexer::from(source).peekable());
+    let Statement::Evaluation(evaluation) = &actual.statements[0] else {
+        panic!("expected a let statement");
+    };
+    assert!(evaluation.diagnostics.errors.is_empty());
+    let [Node::Block { block, .. }] = &evaluation.expression.as_ref().unwrap().contents else {
+        panic!("expected a block");
+    };
+    // The inner statement's error doesn't consume the rest of the block.
+    assert_eq!(block.statements.len(), 1);
+    assert_eq!(
+        block.result,
+        BlockResult::Expression(Some(expression(
+            ident("x", 23),
+            ident("x", 23),
+            [variable("x", 23)].into_iter(),
+        )))
+    );
+    let BlockResult::Expression(Some(result)) = &actual.result else {
+        panic!("expected a match expression");
+    };
+    let [Node::Match(match_block)] = &result.contents else {
+        panic!("expected a match expression");
+    };
+    // Both cases are parsed, and the match is still closed by its `end`.
+    assert_eq!(match_block.cases.len(), 2);
+    assert_eq!(match_block.end_token, Some(END.at(57)));
+    assert_eq!(
+        match_block.diagnostics.errors,
+        [
+            Error::ExpectedExpression,
+            Error::MissingToken {
+                expected: &[Lexigram::Semicolon],
+                actual: Some(Token::new(Span::new(46, 1), Lexigram::CloseSquare)),
+            }
+        ]
+    );
+}
+
+#[test]
+fn trailing_input() {
+    let source = "1 } 2";
+    let actual = Block::program(&mut Lexer::from(source).peekable());
+    let expected = Block::build(
+        expression(
+            number("1", 0),
+            number("1", 0),
+            [number_node("1", 0)].into_iter(),
+        )
+        .into(),
+        Diagnostics {
+            errors: vec![Error::TrailingInput(CLOSE_BRACE.at(2))],
+        },
+        [],
+    );
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn incremental_reparse() {
+    let source = "let a = 1;
+let b = { a + 2 };
+set c = a;
+with x: i64 -> i64;
+let d = x * 2;
+let e = d + b;
+d, e";
+    let edits = [
+        // Within the first statement.
+        Edit {
+            range: 8..9,
+            replacement: "10",
+        },
+        // Within a statement in the middle.
+        Edit {
+            range: 25..26,
+            replacement: "b * 3",
+        },
+        // Within the function body.
+        Edit {
+            range: 73..74,
+            replacement: "2 + 2",
+        },
+        // Within the final expression.
+        Edit {
+            range: source.len() - 1..source.len(),
+            replacement: "e, a",
+        },
+        // Introducing an error, which is recovered from at the next semicolon.
+        Edit {
+            range: 19..19,
+            replacement: "} ",
+        },
+        // Introducing trailing input.
+        Edit {
+            range: source.len()..source.len(),
+            replacement: " end",
+        },
+        // Introducing a new function.
+        Edit {
+            range: 11..11,
+            replacement: "with y;\n",
+        },
+        // Removing the function header.
+        Edit {
+            range: 40..60,
+            replacement: "",
+        },
+        // Across several statements.
+        Edit {
+            range: 15..50,
+            replacement: "0;\nlet c = ",
+        },
+        // Removing everything.
+        Edit {
+            range: 0..source.len(),
+            replacement: "",
+        },
+    ];
+    let old = Block::program(&mut Lexer::from(source).peekable());
+    for edit in &edits {
+        let new_source = edit.apply(source);
+        assert_eq!(
+            old.reparse(Lexer::from(&*new_source), edit),
+            Block::program(&mut Lexer::from(&*new_source).peekable()),
+            "{new_source:?}"
+        );
+    }
+
+    // Trivia is preserved across reused statements.
+    let source = "let a = 1; # one\nlet b = 2; # two\na + b # three\n";
+    let edit = Edit {
+        range: 25..26,
+        replacement: "20",
+    };
+    let new_source = edit.apply(source);
+    let old = Block::program(&mut Lexer::from(source).peekable());
+    let new = old.reparse(Lexer::from(&*new_source), &edit);
+    assert_eq!(
+        new,
+        Block::program(&mut Lexer::from(&*new_source).peekable())
+    );
+    assert_eq!(new.to_source(&new_source), new_source);
+}
+
+#[test]
+fn doc_comments() {
+    let source = "## A value which may be absent.
+## Use `Some` or `None`.
+let Option = enum
+    ## Contains a value.
+    Some: any,
+    # Not a doc comment.
+    None: (),
+end;
+let x = 1; ## Not a doc comment either.
+## Broken by the next comment.
+# An ordinary comment.
+let f = {
+    ## Adds one.
+    with n;
+    n + 1
+};
+";
+    let block = Block::new(&mut Lexer::from(source).peekable());
+    let doc_comments = DocComments::new(source);
+    let doc = |span: Option<Span>| span.map(|span| span.slice(source));
+    let [
+        Statement::Evaluation(option),
+        Statement::Evaluation(x),
+        Statement::Evaluation(f),
+    ] = &block.statements[..]
+    else {
+        panic!("expected three let statements");
+    };
+    let binding = |evaluation: &Evaluation| {
+        evaluation
+            .binding
+            .as_ref()
+            .unwrap()
+            .doc_comment(&doc_comments)
+    };
+    assert_eq!(
+        doc(binding(option)),
+        Some("## A value which may be absent.\n## Use `Some` or `None`.")
+    );
+    assert_eq!(
+        doc_comments
+            .text(option.binding.as_ref().unwrap().let_token, source)
+            .as_deref(),
+        Some("A value which may be absent.\nUse `Some` or `None`.")
+    );
+    let [Node::Enum(enumeration)] = &option.expression.as_ref().unwrap().contents else {
+        panic!("expected an enum");
+    };
+    let variants = enumeration.variant_names();
+    assert_eq!(
+        variants
+            .iter()
+            .map(|name| (name.origin(source), doc(doc_comments.get(*name))))
+            .collect::<Vec<_>>(),
+        [("Some", Some("## Contains a value.")), ("None", None)]
+    );
+    assert_eq!(binding(x), None);
+    assert_eq!(binding(f), None);
+    let [Node::Block { block, .. }] = &f.expression.as_ref().unwrap().contents else {
+        panic!("expected a block");
+    };
+    let BlockResult::Function(function) = &block.result else {
+        panic!("expected a function");
+    };
+    assert_eq!(
+        doc(function.doc_comment(&doc_comments)),
+        Some("## Adds one.")
+    );
+}
+
+#[test]
+fn refutable_bindings() {
+    let source = "let Result.Ok (1, x) = r else then 0 end; let (Color.Red, _) = c;";
+    let block = Block::new(&mut Lexer::from(source).peekable());
+    assert!(block.diagnostics.errors.is_empty());
+    let [Statement::Evaluation(first), Statement::Evaluation(second)] = &block.statements[..]
+    else {
+        panic!("expected two let statements");
+    };
+    assert!(first.diagnostics.errors.is_empty());
+    let binding = first.binding.as_ref().unwrap().binding.as_ref().unwrap();
+    assert!(binding.is_refutable());
+    let BindingMethod::Variant(variant) = &binding.method else {
+        panic!("expected a variant binding");
+    };
+    assert_eq!(variant.constructor, ident("Result", 4));
+    assert_eq!(
+        variant.fields[..],
+        [VariantField {
+            dot_token: DOT.at(10),
+            field: ident("Ok", 11),
+        }]
+    );
+    let Some(Binding {
+        method: BindingMethod::Numeric { bindings, .. },
+        ..
+    }) = &variant.binding
+    else {
+        panic!("expected a numeric binding");
+    };
+    assert_eq!(
+        bindings[0].binding.method,
+        BindingMethod::Literal(number("1", 15))
+    );
+    let else_branch = first.else_branch.as_ref().unwrap();
+    let BlockResult::Expression(Some(result)) = &else_branch.block.result else {
+        panic!("expected an expression");
+    };
+    assert_eq!(result.contents[..], [number_node("0", 35)]);
+    assert_eq!(
+        else_branch.end_token,
+        Some(Token::new(Span::new(37, 3), Lexigram::End))
+    );
+
+    assert!(second.else_branch.is_none());
+    let binding = second.binding.as_ref().unwrap().binding.as_ref().unwrap();
+    let BindingMethod::Numeric { bindings, .. } = &binding.method else {
+        panic!("expected a numeric binding");
+    };
+    let BindingMethod::Variant(variant) = &bindings[0].binding.method else {
+        panic!("expected a variant binding");
+    };
+    assert_eq!(variant.binding, None);
+    assert_eq!(
+        block.to_source(source).replace(' ', ""),
+        source.replace(' ', "")
+    );
+}
+
+#[test]
+fn match_guards_and_types() {
+    let source = "match x then let n: i64 if n > 0 => n; 0 if y => 1; end";
+    let block = Block::new(&mut Lexer::from(source).peekable());
+    assert!(block.diagnostics.errors.is_empty());
+    let BlockResult::Expression(Some(expression)) = &block.result else {
+        panic!("expected an expression");
+    };
+    let [Node::Match(match_block)] = &expression.contents[..] else {
+        panic!("expected a match");
+    };
+    assert!(match_block.diagnostics.errors.is_empty());
+    let [first, second] = &match_block.cases else {
+        panic!("expected two cases");
+    };
+    assert_eq!(first.binding, Some(ident("n", 17)));
+    assert_eq!(first.colon_token, Some(COLON.at(18)));
+    assert_eq!(
+        first.ty.as_ref().unwrap().contents[..],
+        [variable("i64", 20)]
+    );
+    assert_eq!(first.case, None);
+    assert_eq!(
+        first.if_token,
+        Some(Token::new(Span::new(24, 2), Lexigram::If))
+    );
+    assert_eq!(
+        first.guard.as_ref().unwrap().contents[..],
+        [
+            variable("n", 27),
+            number_node("0", 31),
+            Node::Greater(Token::new(Span::new(29, 1), Lexigram::Greater)),
+        ]
+    );
+    assert_eq!(
+        second.case.as_ref().unwrap().contents[..],
+        [number_node("0", 39)]
+    );
+    assert_eq!(
+        second.guard.as_ref().unwrap().contents[..],
+        [variable("y", 44)]
+    );
+}
+
+#[test]
+fn early_exits() {
+    let source = "let x = 'outer: { break 'outer 1 + 2 }; return x";
+    let block = Block::new(&mut Lexer::from(source).peekable());
+    assert!(block.diagnostics.errors.is_empty());
+    let [Statement::Evaluation(evaluation)] = &block.statements[..] else {
+        panic!("expected a let statement");
+    };
+    let [
+        Node::Block {
+            label: Some(label),
+            block: labeled,
+            ..
+        },
+    ] = &evaluation.expression.as_ref().unwrap().contents[..]
+    else {
+        panic!("expected a labeled block");
+    };
+    let outer = Token::new(Span::new(8, 6), Lexigram::Label);
+    assert_eq!(label.label, outer);
+    assert_eq!(label.colon_token, Some(COLON.at(14)));
+    let BlockResult::Expression(Some(result)) = &labeled.result else {
+        panic!("expected an expression");
+    };
+    let [Node::Break(break_node)] = &result.contents[..] else {
+        panic!("expected a break");
+    };
+    assert_eq!(
+        break_node.break_token,
+        Token::new(Span::new(18, 5), Lexigram::Break)
+    );
+    assert_eq!(break_node.label, Some(outer.at(24)));
+    assert_eq!(
+        break_node.expression.as_ref().unwrap().contents[..],
+        [number_node("1", 31), number_node("2", 35), add(33)]
+    );
+    let BlockResult::Expression(Some(result)) = &block.result else {
+        panic!("expected an expression");
+    };
+    assert_eq!(
+        result.contents[..],
+        [Node::Break(Box::new(Break {
+            break_token: Token::new(Span::new(40, 6), Lexigram::Return),
+            label: None,
+            expression: Some(Expression::build(
+                Some(ident("x", 47)),
+                Some(ident("x", 47)),
+                Diagnostics::default(),
+                [variable("x", 47)],
+            )),
+        }))]
+    );
+    assert_eq!(
+        block.to_source(source).replace(' ', ""),
+        source.replace(' ', "")
+    );
+}
+
+#[test]
+fn format_canonical() {
+    let source = "# leading comment
+
+## adds two numbers
+let add = {with (a,b): (i64,i64) -> i64;   a+b};
+let point = x:1, y:2;   # trailing
+let sum = (1..=3)|>fold 0,add;
+let sign = if point.x>0 then \"pos\" else if point.x<0 then \"neg\" else then \"zero\" end;
+let Shape = enum
+  ## a circle
+  Circle: i64, Square: i64
+end;
+let area = match Shape.Circle 3 then let r = Shape.Circle => 3*r*r; let s = Shape.Square if s>1 => {let q = s*s; q}; let _ => 0;end;
+
+
+let Shape.Circle c = shape else then 0 end;
+let v = 'outer: { if a == 1 then break 'outer -a end; return !b };
+with host; # function
+# before end
+host.print \"{v}\"
+";
+    let expected = "# leading comment
+
+## adds two numbers
+let add = {
+  with (a, b): (i64, i64) -> i64;
+  a + b
+};
+let point = x: 1, y: 2; # trailing
+let sum = (1..=3) |> fold 0, add;
+let sign = if point.x > 0 then
+  \"pos\"
+else if point.x < 0 then
+  \"neg\"
+else then
+  \"zero\"
+end;
+let Shape = enum
+  ## a circle
+  Circle: i64,
+  Square: i64
+end;
+let area = match Shape.Circle 3 then
+  let r = Shape.Circle => 3 * r * r;
+  let s = Shape.Square if s > 1 => {
+    let q = s * s;
+    q
+  };
+  let _ => 0;
+end;
+
+let Shape.Circle c = shape else then 0 end;
+let v = 'outer: {
+  if a == 1 then break 'outer -a end;
+  return !b
+};
+with host; # function
+# before end
+host.print \"{v}\"
+";
+    let actual = format(source).unwrap();
+    assert_eq!(actual, expected);
+    assert_eq!(format(&actual).unwrap(), actual);
+    assert!(format("let = 1;").is_err());
+}
+
+#[test]
+fn visitors() {
+    struct Variables<'source>(&'source str, Vec<&'source str>);
+
+    impl<'source> Visitor<'source> for Variables<'source> {
+        fn visit_node(&mut self, node: &Node<'source>) {
+            if let Node::Variable(token) = node {
+                self.1.push(token.origin(self.0));
+            }
+            visit::walk_node(self, node);
+        }
+    }
+
+    struct Negate;
+
+    impl<'source> VisitorMut<'source> for Negate {
+        fn visit_node_mut(&mut self, node: &mut Node<'source>) {
+            if let Node::Bool(value, _) = node {
+                *value = !*value;
+            }
+            visit::walk_node_mut(self, node);
+        }
+    }
+
+    let source = "let O.Some x = a else then b end;
+set c = if d then \"{e}\" else then true end;
+let f = match g then let h if i => j; end;
+with k: l -> m;
+'n: { break 'n enum V: o end }";
+    let mut block = Block::program(&mut Lexer::from(source).peekable());
+    assert_eq!(block.diagnostics.errors, []);
+
+    let mut variables = Variables(source, Vec::new());
+    variables.visit_block(&block);
+    assert_eq!(
+        variables.1,
+        ["a", "b", "c", "d", "e", "g", "i", "j", "l", "m", "o"]
+    );
+
+    Negate.visit_block_mut(&mut block);
+    let Statement::Set(set) = &block.statements[1] else {
+        panic!("expected set statement");
+    };
+    let Some(Node::If(if_block)) = set.expression.as_ref().unwrap().contents.first() else {
+        panic!("expected if block");
+    };
+    assert!(matches!(
+        if_block.second.result,
+        BlockResult::Expression(Some(ref expression))
+            if matches!(expression.contents[..], [Node::Bool(false, _)])
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize() {
+    let source = "let x = true;\n\"{x}\"";
+    let block = Block::program(&mut Lexer::from(source).peekable());
+    let json = serde_json::to_value(Document::new(&block)).unwrap();
+    assert_eq!(json["version"], SCHEMA_VERSION);
+
+    let evaluation = &json["program"]["statements"][0]["Evaluation"];
+    let binding = &evaluation["binding"];
+    assert_eq!(binding["let_token"]["lexigram"], "Let");
+    assert_eq!(
+        binding["binding"]["method"]["Single"]["span"],
+        serde_json::json!({"start": 4, "end": 5})
+    );
+    assert_eq!(evaluation["expression"]["contents"][0]["Bool"][0], true);
+
+    let interpolation = &json["program"]["result"]["Expression"]["contents"][0]["Interpolation"];
+    assert_eq!(
+        interpolation["segments"][0]["expression"]["contents"][0]["Variable"]["span"],
+        serde_json::json!({"start": 16, "end": 17})
+    );
+
+    let block = Block::program(&mut Lexer::from("let = 1;").peekable());
+    let json = serde_json::to_value(&*block).unwrap();
+    assert!(
+        json["statements"][0]["Evaluation"]["diagnostics"]["errors"][0]["MissingToken"].is_object()
+    );
 }
@@ -0,0 +1,306 @@
+//! Recovery of a syntax tree's tokens, for reproducing its source.
+
+use crate::*;
+
+impl<'source> Block<'source> {
+    /// Returns every token within the block, in the order they appeared in the source.
+    ///
+    /// Tokens attached to diagnostics are not included.
+    pub fn tokens(&self) -> Vec<Token<'source>> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        // Expressions are stored in postfix order, so their tokens are out of place.
+        tokens.sort_by_key(|token| token.span.start);
+        // Some tokens are shared between nodes, such as the `end` of an `else if` chain.
+        tokens.dedup_by_key(|token| token.span.start);
+        tokens
+    }
+
+    /// Reconstructs source code from the block's tokens and their trivia,
+    /// given the `source` that the block was parsed from.
+    ///
+    /// If the source was parsed without errors, this is identical to the original source.
+    pub fn to_source(&self, source: &str) -> String {
+        let mut output = String::new();
+        let mut previous = None;
+        for token in self.tokens() {
+            output.push_str(token.leading_trivia(source, previous).slice(source));
+            output.push_str(token.origin(source));
+            output.push_str(token.trailing_trivia(source).slice(source));
+            previous = Some(token);
+        }
+        output
+    }
+}
+
+trait CollectTokens<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>);
+}
+
+impl<'source> CollectTokens<'source> for Token<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        tokens.push(*self);
+    }
+}
+
+impl<'source, T: CollectTokens<'source>> CollectTokens<'source> for Option<T> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        if let Some(inner) = self {
+            inner.collect_tokens(tokens);
+        }
+    }
+}
+
+impl<'source, T: CollectTokens<'source> + ?Sized> CollectTokens<'source> for Box<T> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        (**self).collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Block<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        for statement in &self.statements {
+            match statement {
+                Statement::Evaluation(evaluation) => evaluation.collect_tokens(tokens),
+                Statement::Set(set) => set.collect_tokens(tokens),
+            }
+        }
+        match &self.result {
+            BlockResult::Expression(expression) => expression.collect_tokens(tokens),
+            BlockResult::Function(function) => function.collect_tokens(tokens),
+        }
+    }
+}
+
+impl<'source> CollectTokens<'source> for Evaluation<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        if let Some(binding) = &self.binding {
+            binding.let_token.collect_tokens(tokens);
+            binding.binding.collect_tokens(tokens);
+            binding.equals_token.collect_tokens(tokens);
+        }
+        self.expression.collect_tokens(tokens);
+        if let Some(else_branch) = &self.else_branch {
+            else_branch.else_token.collect_tokens(tokens);
+            else_branch.then_token.collect_tokens(tokens);
+            else_branch.block.collect_tokens(tokens);
+            else_branch.end_token.collect_tokens(tokens);
+        }
+        self.semicolon_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Set<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.set_token.collect_tokens(tokens);
+        self.target.collect_tokens(tokens);
+        self.equals_token.collect_tokens(tokens);
+        self.expression.collect_tokens(tokens);
+        self.semicolon_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Function<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.with_token.collect_tokens(tokens);
+        self.argument.collect_tokens(tokens);
+        self.colon_token.collect_tokens(tokens);
+        self.input.collect_tokens(tokens);
+        self.single_arrow_token.collect_tokens(tokens);
+        self.output.collect_tokens(tokens);
+        self.semicolon_token.collect_tokens(tokens);
+        self.block.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Binding<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        match &self.method {
+            BindingMethod::Single(token) | BindingMethod::Literal(token) => {
+                token.collect_tokens(tokens)
+            }
+            BindingMethod::Variant(variant) => {
+                variant.constructor.collect_tokens(tokens);
+                for field in &variant.fields {
+                    field.dot_token.collect_tokens(tokens);
+                    field.field.collect_tokens(tokens);
+                }
+                variant.binding.collect_tokens(tokens);
+            }
+            BindingMethod::Numeric {
+                open_paren,
+                bindings,
+                rest,
+                close_paren,
+            } => {
+                open_paren.collect_tokens(tokens);
+                for binding in bindings {
+                    binding.binding.collect_tokens(tokens);
+                    binding.comma_token.collect_tokens(tokens);
+                }
+                rest.collect_tokens(tokens);
+                close_paren.collect_tokens(tokens);
+            }
+            BindingMethod::Named {
+                open_brace,
+                bindings,
+                rest,
+                close_brace,
+            } => {
+                open_brace.collect_tokens(tokens);
+                for binding in bindings {
+                    binding.field.collect_tokens(tokens);
+                    if let Some(sub_binding) = &binding.binding {
+                        sub_binding.colon_token.collect_tokens(tokens);
+                        sub_binding.binding.collect_tokens(tokens);
+                    }
+                    binding.comma_token.collect_tokens(tokens);
+                }
+                rest.collect_tokens(tokens);
+                close_brace.collect_tokens(tokens);
+            }
+        }
+    }
+}
+
+impl<'source> CollectTokens<'source> for RestBinding<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.ellipses_token.collect_tokens(tokens);
+        self.binding.collect_tokens(tokens);
+        self.comma_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Expression<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        // `first_token` and `last_token` duplicate tokens within the contents.
+        for node in &self.contents {
+            match node {
+                // Calls are implicit, and borrow the token of their argument.
+                Node::Call(_) => {}
+                Node::Unit(open_paren, close_paren) => {
+                    open_paren.collect_tokens(tokens);
+                    close_paren.collect_tokens(tokens);
+                }
+                Node::Bool(_, token)
+                | Node::Number(token)
+                | Node::String(token)
+                | Node::Variable(token)
+                | Node::Pipe(token)
+                | Node::Positive(token)
+                | Node::Negative(token)
+                | Node::Not(token)
+                | Node::Deref(token)
+                | Node::Mul(token)
+                | Node::Div(token)
+                | Node::Add(token)
+                | Node::Sub(token)
+                | Node::BitwiseAnd(token)
+                | Node::BitwiseOr(token)
+                | Node::BitwiseXor(token)
+                | Node::EqualTo(token)
+                | Node::NotEqualTo(token)
+                | Node::Greater(token)
+                | Node::GreaterEqual(token)
+                | Node::Lesser(token)
+                | Node::LesserEqual(token)
+                | Node::LogicalAnd(token)
+                | Node::LogicalOr(token)
+                | Node::Range(token)
+                | Node::RangeInclusive(token)
+                | Node::Tuple(token) => token.collect_tokens(tokens),
+                Node::Block {
+                    label,
+                    open_brace,
+                    block,
+                    close_brace,
+                } => {
+                    if let Some(label) = label {
+                        label.label.collect_tokens(tokens);
+                        label.colon_token.collect_tokens(tokens);
+                    }
+                    open_brace.collect_tokens(tokens);
+                    block.collect_tokens(tokens);
+                    close_brace.collect_tokens(tokens);
+                }
+                Node::If(if_block) => if_block.collect_tokens(tokens),
+                Node::Break(break_node) => {
+                    break_node.break_token.collect_tokens(tokens);
+                    break_node.label.collect_tokens(tokens);
+                    break_node.expression.collect_tokens(tokens);
+                }
+                Node::Match(match_block) => match_block.collect_tokens(tokens),
+                Node::Enum(enumeration) => enumeration.collect_tokens(tokens),
+                Node::Interpolation(interpolation) => interpolation.collect_tokens(tokens),
+                Node::Name { name, colon_token } => {
+                    name.collect_tokens(tokens);
+                    colon_token.collect_tokens(tokens);
+                }
+                Node::Field { dot_token, index } => {
+                    dot_token.collect_tokens(tokens);
+                    index.collect_tokens(tokens);
+                }
+                Node::Group {
+                    open_paren,
+                    close_paren,
+                } => {
+                    open_paren.collect_tokens(tokens);
+                    close_paren.collect_tokens(tokens);
+                }
+            }
+        }
+    }
+}
+
+impl<'source> CollectTokens<'source> for If<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.if_token.collect_tokens(tokens);
+        self.condition.collect_tokens(tokens);
+        self.then_token.collect_tokens(tokens);
+        self.first.collect_tokens(tokens);
+        self.else_token.collect_tokens(tokens);
+        self.else_kind.collect_tokens(tokens);
+        self.second.collect_tokens(tokens);
+        self.end_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Match<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.match_token.collect_tokens(tokens);
+        self.expression.collect_tokens(tokens);
+        self.then_token.collect_tokens(tokens);
+        for case in &self.cases {
+            case.let_token.collect_tokens(tokens);
+            case.binding.collect_tokens(tokens);
+            case.colon_token.collect_tokens(tokens);
+            case.ty.collect_tokens(tokens);
+            case.equals_token.collect_tokens(tokens);
+            case.case.collect_tokens(tokens);
+            case.if_token.collect_tokens(tokens);
+            case.guard.collect_tokens(tokens);
+            case.arrow_token.collect_tokens(tokens);
+            case.expression.collect_tokens(tokens);
+            case.semicolon_token.collect_tokens(tokens);
+        }
+        self.end_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Enum<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.enum_token.collect_tokens(tokens);
+        self.variants.collect_tokens(tokens);
+        self.end_token.collect_tokens(tokens);
+    }
+}
+
+impl<'source> CollectTokens<'source> for Interpolation<'source> {
+    fn collect_tokens(&self, tokens: &mut Vec<Token<'source>>) {
+        self.start.collect_tokens(tokens);
+        for segment in &self.segments {
+            segment.expression.collect_tokens(tokens);
+            segment.string.collect_tokens(tokens);
+        }
+    }
+}
@@ -0,0 +1,593 @@
+//! Traversal of syntax trees.
+//!
+//! Each method of [`Visitor`] (and [`VisitorMut`]) visits the children of its argument by default,
+//! by calling the `walk` function of the same name.
+//! Implementors override the methods for the parts of the tree they are interested in,
+//! and call the `walk` function themselves to continue into their children.
+//!
+//! ```rust
+//! use espy_ears::{Block, Node, visit::{self, Visitor}};
+//! use espy_eyes::{Lexer, Token};
+//!
+//! struct Variables<'source>(Vec<Token<'source>>);
+//!
+//! impl<'source> Visitor<'source> for Variables<'source> {
+//!     fn visit_node(&mut self, node: &Node<'source>) {
+//!         if let Node::Variable(token) = node {
+//!             self.0.push(*token);
+//!         }
+//!         visit::walk_node(self, node);
+//!     }
+//! }
+//!
+//! let source = "let x = { y + z }; x";
+//! let block = Block::program(&mut Lexer::from(source).peekable());
+//! let mut variables = Variables(Vec::new());
+//! variables.visit_block(&block);
+//! assert_eq!(variables.0.len(), 3);
+//! ```
+
+use crate::*;
+
+/// Visits the nodes of a syntax tree by reference.
+pub trait Visitor<'source> {
+    fn visit_block(&mut self, block: &Block<'source>) {
+        walk_block(self, block);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement<'source>) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_evaluation(&mut self, evaluation: &Evaluation<'source>) {
+        walk_evaluation(self, evaluation);
+    }
+
+    fn visit_set(&mut self, set: &Set<'source>) {
+        walk_set(self, set);
+    }
+
+    fn visit_function(&mut self, function: &Function<'source>) {
+        walk_function(self, function);
+    }
+
+    fn visit_binding(&mut self, binding: &Binding<'source>) {
+        walk_binding(self, binding);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression<'source>) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_node(&mut self, node: &Node<'source>) {
+        walk_node(self, node);
+    }
+
+    fn visit_interpolation(&mut self, interpolation: &Interpolation<'source>) {
+        walk_interpolation(self, interpolation);
+    }
+
+    fn visit_if(&mut self, if_block: &If<'source>) {
+        walk_if(self, if_block);
+    }
+
+    fn visit_break(&mut self, break_node: &Break<'source>) {
+        walk_break(self, break_node);
+    }
+
+    fn visit_match(&mut self, match_block: &Match<'source>) {
+        walk_match(self, match_block);
+    }
+
+    fn visit_match_case(&mut self, case: &MatchCase<'source>) {
+        walk_match_case(self, case);
+    }
+
+    fn visit_enum(&mut self, enumeration: &Enum<'source>) {
+        walk_enum(self, enumeration);
+    }
+}
+
+pub fn walk_block<'source, V: Visitor<'source> + ?Sized>(visitor: &mut V, block: &Block<'source>) {
+    for statement in &block.statements {
+        visitor.visit_statement(statement);
+    }
+    match &block.result {
+        BlockResult::Expression(expression) => {
+            if let Some(expression) = expression {
+                visitor.visit_expression(expression);
+            }
+        }
+        BlockResult::Function(function) => visitor.visit_function(function),
+    }
+}
+
+pub fn walk_statement<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    statement: &Statement<'source>,
+) {
+    match statement {
+        Statement::Evaluation(evaluation) => visitor.visit_evaluation(evaluation),
+        Statement::Set(set) => visitor.visit_set(set),
+    }
+}
+
+pub fn walk_evaluation<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    evaluation: &Evaluation<'source>,
+) {
+    if let Some(binding) = evaluation
+        .binding
+        .as_ref()
+        .and_then(|binding| binding.binding.as_ref())
+    {
+        visitor.visit_binding(binding);
+    }
+    if let Some(expression) = &evaluation.expression {
+        visitor.visit_expression(expression);
+    }
+    if let Some(else_branch) = &evaluation.else_branch {
+        visitor.visit_block(&else_branch.block);
+    }
+}
+
+pub fn walk_set<'source, V: Visitor<'source> + ?Sized>(visitor: &mut V, set: &Set<'source>) {
+    if let Some(target) = &set.target {
+        visitor.visit_expression(target);
+    }
+    if let Some(expression) = &set.expression {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_function<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    function: &Function<'source>,
+) {
+    if let Some(argument) = &function.argument {
+        visitor.visit_binding(argument);
+    }
+    if let Some(input) = &function.input {
+        visitor.visit_expression(input);
+    }
+    if let Some(output) = &function.output {
+        visitor.visit_expression(output);
+    }
+    visitor.visit_block(&function.block);
+}
+
+pub fn walk_binding<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    binding: &Binding<'source>,
+) {
+    match &binding.method {
+        BindingMethod::Single(_) | BindingMethod::Literal(_) => {}
+        BindingMethod::Variant(variant) => {
+            if let Some(binding) = &variant.binding {
+                visitor.visit_binding(binding);
+            }
+        }
+        BindingMethod::Numeric { bindings, rest, .. } => {
+            for binding in bindings {
+                visitor.visit_binding(&binding.binding);
+            }
+            if let Some(rest) = rest {
+                visitor.visit_binding(&rest.binding);
+            }
+        }
+        BindingMethod::Named { bindings, rest, .. } => {
+            for binding in bindings {
+                if let Some(sub_binding) = &binding.binding {
+                    visitor.visit_binding(&sub_binding.binding);
+                }
+            }
+            if let Some(rest) = rest {
+                visitor.visit_binding(&rest.binding);
+            }
+        }
+    }
+}
+
+pub fn walk_expression<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    expression: &Expression<'source>,
+) {
+    for node in &expression.contents {
+        visitor.visit_node(node);
+    }
+}
+
+pub fn walk_node<'source, V: Visitor<'source> + ?Sized>(visitor: &mut V, node: &Node<'source>) {
+    match node {
+        Node::Interpolation(interpolation) => visitor.visit_interpolation(interpolation),
+        Node::Block { block, .. } => visitor.visit_block(block),
+        Node::If(if_block) => visitor.visit_if(if_block),
+        Node::Break(break_node) => visitor.visit_break(break_node),
+        Node::Match(match_block) => visitor.visit_match(match_block),
+        Node::Enum(enumeration) => visitor.visit_enum(enumeration),
+        Node::Unit(..)
+        | Node::Bool(..)
+        | Node::Number(_)
+        | Node::String(_)
+        | Node::Variable(_)
+        | Node::Pipe(_)
+        | Node::Call(_)
+        | Node::Positive(_)
+        | Node::Negative(_)
+        | Node::Not(_)
+        | Node::Deref(_)
+        | Node::Mul(_)
+        | Node::Div(_)
+        | Node::Add(_)
+        | Node::Sub(_)
+        | Node::BitwiseAnd(_)
+        | Node::BitwiseOr(_)
+        | Node::BitwiseXor(_)
+        | Node::EqualTo(_)
+        | Node::NotEqualTo(_)
+        | Node::Greater(_)
+        | Node::GreaterEqual(_)
+        | Node::Lesser(_)
+        | Node::LesserEqual(_)
+        | Node::LogicalAnd(_)
+        | Node::LogicalOr(_)
+        | Node::Range(_)
+        | Node::RangeInclusive(_)
+        | Node::Name { .. }
+        | Node::Field { .. }
+        | Node::Tuple(_)
+        | Node::Group { .. } => {}
+    }
+}
+
+pub fn walk_interpolation<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    interpolation: &Interpolation<'source>,
+) {
+    for segment in &interpolation.segments {
+        if let Some(expression) = &segment.expression {
+            visitor.visit_expression(expression);
+        }
+    }
+}
+
+pub fn walk_if<'source, V: Visitor<'source> + ?Sized>(visitor: &mut V, if_block: &If<'source>) {
+    if let Some(condition) = &if_block.condition {
+        visitor.visit_expression(condition);
+    }
+    visitor.visit_block(&if_block.first);
+    visitor.visit_block(&if_block.second);
+}
+
+pub fn walk_break<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    break_node: &Break<'source>,
+) {
+    if let Some(expression) = &break_node.expression {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_match<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    match_block: &Match<'source>,
+) {
+    if let Some(expression) = &match_block.expression {
+        visitor.visit_expression(expression);
+    }
+    for case in &match_block.cases {
+        visitor.visit_match_case(case);
+    }
+}
+
+pub fn walk_match_case<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    case: &MatchCase<'source>,
+) {
+    for expression in [&case.ty, &case.case, &case.guard, &case.expression]
+        .into_iter()
+        .flatten()
+    {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_enum<'source, V: Visitor<'source> + ?Sized>(
+    visitor: &mut V,
+    enumeration: &Enum<'source>,
+) {
+    if let Some(variants) = &enumeration.variants {
+        visitor.visit_expression(variants);
+    }
+}
+
+/// Visits the nodes of a syntax tree by mutable reference.
+///
+/// This is identical to [`Visitor`], except that nodes may be modified as they are visited.
+pub trait VisitorMut<'source> {
+    fn visit_block_mut(&mut self, block: &mut Block<'source>) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement<'source>) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_evaluation_mut(&mut self, evaluation: &mut Evaluation<'source>) {
+        walk_evaluation_mut(self, evaluation);
+    }
+
+    fn visit_set_mut(&mut self, set: &mut Set<'source>) {
+        walk_set_mut(self, set);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut Function<'source>) {
+        walk_function_mut(self, function);
+    }
+
+    fn visit_binding_mut(&mut self, binding: &mut Binding<'source>) {
+        walk_binding_mut(self, binding);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression<'source>) {
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_node_mut(&mut self, node: &mut Node<'source>) {
+        walk_node_mut(self, node);
+    }
+
+    fn visit_interpolation_mut(&mut self, interpolation: &mut Interpolation<'source>) {
+        walk_interpolation_mut(self, interpolation);
+    }
+
+    fn visit_if_mut(&mut self, if_block: &mut If<'source>) {
+        walk_if_mut(self, if_block);
+    }
+
+    fn visit_break_mut(&mut self, break_node: &mut Break<'source>) {
+        walk_break_mut(self, break_node);
+    }
+
+    fn visit_match_mut(&mut self, match_block: &mut Match<'source>) {
+        walk_match_mut(self, match_block);
+    }
+
+    fn visit_match_case_mut(&mut self, case: &mut MatchCase<'source>) {
+        walk_match_case_mut(self, case);
+    }
+
+    fn visit_enum_mut(&mut self, enumeration: &mut Enum<'source>) {
+        walk_enum_mut(self, enumeration);
+    }
+}
+
+pub fn walk_block_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    block: &mut Block<'source>,
+) {
+    for statement in &mut block.statements {
+        visitor.visit_statement_mut(statement);
+    }
+    match &mut block.result {
+        BlockResult::Expression(expression) => {
+            if let Some(expression) = expression {
+                visitor.visit_expression_mut(expression);
+            }
+        }
+        BlockResult::Function(function) => visitor.visit_function_mut(function),
+    }
+}
+
+pub fn walk_statement_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    statement: &mut Statement<'source>,
+) {
+    match statement {
+        Statement::Evaluation(evaluation) => visitor.visit_evaluation_mut(evaluation),
+        Statement::Set(set) => visitor.visit_set_mut(set),
+    }
+}
+
+pub fn walk_evaluation_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    evaluation: &mut Evaluation<'source>,
+) {
+    if let Some(binding) = evaluation
+        .binding
+        .as_mut()
+        .and_then(|binding| binding.binding.as_mut())
+    {
+        visitor.visit_binding_mut(binding);
+    }
+    if let Some(expression) = &mut evaluation.expression {
+        visitor.visit_expression_mut(expression);
+    }
+    if let Some(else_branch) = &mut evaluation.else_branch {
+        visitor.visit_block_mut(&mut else_branch.block);
+    }
+}
+
+pub fn walk_set_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    set: &mut Set<'source>,
+) {
+    if let Some(target) = &mut set.target {
+        visitor.visit_expression_mut(target);
+    }
+    if let Some(expression) = &mut set.expression {
+        visitor.visit_expression_mut(expression);
+    }
+}
+
+pub fn walk_function_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    function: &mut Function<'source>,
+) {
+    if let Some(argument) = &mut function.argument {
+        visitor.visit_binding_mut(argument);
+    }
+    if let Some(input) = &mut function.input {
+        visitor.visit_expression_mut(input);
+    }
+    if let Some(output) = &mut function.output {
+        visitor.visit_expression_mut(output);
+    }
+    visitor.visit_block_mut(&mut function.block);
+}
+
+pub fn walk_binding_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    binding: &mut Binding<'source>,
+) {
+    match &mut binding.method {
+        BindingMethod::Single(_) | BindingMethod::Literal(_) => {}
+        BindingMethod::Variant(variant) => {
+            if let Some(binding) = &mut variant.binding {
+                visitor.visit_binding_mut(binding);
+            }
+        }
+        BindingMethod::Numeric { bindings, rest, .. } => {
+            for binding in bindings {
+                visitor.visit_binding_mut(&mut binding.binding);
+            }
+            if let Some(rest) = rest {
+                visitor.visit_binding_mut(&mut rest.binding);
+            }
+        }
+        BindingMethod::Named { bindings, rest, .. } => {
+            for binding in bindings {
+                if let Some(sub_binding) = &mut binding.binding {
+                    visitor.visit_binding_mut(&mut sub_binding.binding);
+                }
+            }
+            if let Some(rest) = rest {
+                visitor.visit_binding_mut(&mut rest.binding);
+            }
+        }
+    }
+}
+
+pub fn walk_expression_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    expression: &mut Expression<'source>,
+) {
+    for node in &mut expression.contents {
+        visitor.visit_node_mut(node);
+    }
+}
+
+pub fn walk_node_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    node: &mut Node<'source>,
+) {
+    match node {
+        Node::Interpolation(interpolation) => visitor.visit_interpolation_mut(interpolation),
+        Node::Block { block, .. } => visitor.visit_block_mut(block),
+        Node::If(if_block) => visitor.visit_if_mut(if_block),
+        Node::Break(break_node) => visitor.visit_break_mut(break_node),
+        Node::Match(match_block) => visitor.visit_match_mut(match_block),
+        Node::Enum(enumeration) => visitor.visit_enum_mut(enumeration),
+        Node::Unit(..)
+        | Node::Bool(..)
+        | Node::Number(_)
+        | Node::String(_)
+        | Node::Variable(_)
+        | Node::Pipe(_)
+        | Node::Call(_)
+        | Node::Positive(_)
+        | Node::Negative(_)
+        | Node::Not(_)
+        | Node::Deref(_)
+        | Node::Mul(_)
+        | Node::Div(_)
+        | Node::Add(_)
+        | Node::Sub(_)
+        | Node::BitwiseAnd(_)
+        | Node::BitwiseOr(_)
+        | Node::BitwiseXor(_)
+        | Node::EqualTo(_)
+        | Node::NotEqualTo(_)
+        | Node::Greater(_)
+        | Node::GreaterEqual(_)
+        | Node::Lesser(_)
+        | Node::LesserEqual(_)
+        | Node::LogicalAnd(_)
+        | Node::LogicalOr(_)
+        | Node::Range(_)
+        | Node::RangeInclusive(_)
+        | Node::Name { .. }
+        | Node::Field { .. }
+        | Node::Tuple(_)
+        | Node::Group { .. } => {}
+    }
+}
+
+pub fn walk_interpolation_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    interpolation: &mut Interpolation<'source>,
+) {
+    for segment in &mut interpolation.segments {
+        if let Some(expression) = &mut segment.expression {
+            visitor.visit_expression_mut(expression);
+        }
+    }
+}
+
+pub fn walk_if_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    if_block: &mut If<'source>,
+) {
+    if let Some(condition) = &mut if_block.condition {
+        visitor.visit_expression_mut(condition);
+    }
+    visitor.visit_block_mut(&mut if_block.first);
+    visitor.visit_block_mut(&mut if_block.second);
+}
+
+pub fn walk_break_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    break_node: &mut Break<'source>,
+) {
+    if let Some(expression) = &mut break_node.expression {
+        visitor.visit_expression_mut(expression);
+    }
+}
+
+pub fn walk_match_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    match_block: &mut Match<'source>,
+) {
+    if let Some(expression) = &mut match_block.expression {
+        visitor.visit_expression_mut(expression);
+    }
+    for case in &mut match_block.cases {
+        visitor.visit_match_case_mut(case);
+    }
+}
+
+pub fn walk_match_case_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    case: &mut MatchCase<'source>,
+) {
+    for expression in [
+        &mut case.ty,
+        &mut case.case,
+        &mut case.guard,
+        &mut case.expression,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        visitor.visit_expression_mut(expression);
+    }
+}
+
+pub fn walk_enum_mut<'source, V: VisitorMut<'source> + ?Sized>(
+    visitor: &mut V,
+    enumeration: &mut Enum<'source>,
+) {
+    if let Some(variants) = &mut enumeration.variants {
+        visitor.visit_expression_mut(variants);
+    }
+}
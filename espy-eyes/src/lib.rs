@@ -12,15 +12,26 @@
 //!     println!("{token:?}");
 //! }
 //! ```
+//!
+//! Tokens refer to their source code by [`Span`]s,
+//! which a [`SourceFile`] can resolve into text and line/column positions.
+//!
+//! Whitespace and comments ("trivia") are not recorded by tokens, to keep them small.
+//! [`Token::leading_trivia`] and [`Token::trailing_trivia`] recover them from the source instead,
+//! so that tools may reproduce the original source exactly.
+
+use std::{borrow::Cow, collections::HashMap, marker::PhantomData, ops::Range};
 
 /// The semantic meaning of a token.
 ///
 /// This is usually called the "token type",
 /// but "lexigram" is used to avoid conflict with Rust's `type` keyword.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Lexigram {
     // Keywords
     And,
+    Break,
     Discard,
     Else,
     End,
@@ -30,6 +41,7 @@ pub enum Lexigram {
     Let,
     Match,
     Or,
+    Return,
     Set,
     Then,
     True,
@@ -70,28 +82,135 @@ pub enum Lexigram {
 
     // Values
     Ident,
+    /// An identifier prefixed by a single quote (such as `'outer`),
+    /// which names a block for `break` to exit.
+    Label,
     Number,
     String,
+    /// The beginning of an interpolated string, up to and including the first `{`.
+    ///
+    /// An interpolated string such as `"Hello, {name}!"` is lexed as a `StringStart` (`"Hello, {`),
+    /// the tokens of the embedded expression (`name`), and a `StringEnd` (`}!"`).
+    /// Additional expressions are separated by `StringMiddle` tokens (`}, {`).
+    StringStart,
+    /// A part of an interpolated string between two expressions, including both braces.
+    StringMiddle,
+    /// The end of an interpolated string, from the closing `}` of its last expression.
+    StringEnd,
+}
+
+/// A range of source code, as a byte offset and length.
+///
+/// Sources are limited to 4GiB so that spans (and the tokens containing them) stay small.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+    pub start: u32,
+    pub len: u32,
+}
+
+impl Span {
+    pub const fn new(start: u32, len: u32) -> Self {
+        Self { start, len }
+    }
+
+    pub const fn end(self) -> u32 {
+        self.start + self.len
+    }
+
+    pub const fn range(self) -> Range<usize> {
+        self.start as usize..self.end() as usize
+    }
+
+    /// Returns the text of `source` that this span covers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span is out of bounds or does not fall on character boundaries,
+    /// which can happen if the span came from a different source.
+    pub fn slice(self, source: &str) -> &str {
+        &source[self.range()]
+    }
+}
+
+/// Spans are serialized as the byte offsets of their start and end,
+/// which is more convenient for tools that slice the source themselves.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Span {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut span = serializer.serialize_struct("Span", 2)?;
+        span.serialize_field("start", &self.start)?;
+        span.serialize_field("end", &self.end())?;
+        span.end()
+    }
 }
 
 /// A unit of espy source code.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token<'source> {
-    /// Tracks the string slice that this token originated from.
-    ///
-    /// Pointer arithmetic may be used to derive the range of the source string that the represents;
-    /// use the `origin_range` function for this purpose.
-    // TODO: An AST's memory footprint could be reduced substantially by shrinking this field. (u32s = half, u32 with u16 len = one third)
-    pub origin: &'source str,
+    /// The range of the source string that this token originated from.
+    pub span: Span,
     /// The semantic meaning of the token.
     ///
     /// This is usually called the token's "type",
     /// but "lexigram" is used to avoid conflict with Rust's `type` keyword.
     pub lexigram: Lexigram,
+    /// Tokens are only meaningful alongside the source they were lexed from.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source: PhantomData<&'source str>,
 }
 
 impl Token<'_> {
-    pub fn resolve(&self) -> Result<'static, String, EscapeError> {
+    pub const fn new(span: Span, lexigram: Lexigram) -> Self {
+        Self {
+            span,
+            lexigram,
+            source: PhantomData,
+        }
+    }
+
+    /// Returns the text of `source` that this token originated from.
+    pub fn origin<'a>(&self, source: &'a str) -> &'a str {
+        self.span.slice(source)
+    }
+
+    /// Returns the span of the whitespace and comments preceding this token within `source`,
+    /// given the token before it (or `None` if this is the first token).
+    ///
+    /// This begins after the previous token's [trailing trivia](Token::trailing_trivia),
+    /// and includes any input between the two tokens which failed to lex.
+    ///
+    /// Tokens don't record their trivia, so it is lexed again from `source` on each call.
+    /// This takes the place of the lexer's former mode for recording trivia,
+    /// which made every token larger whether or not its trivia was needed.
+    pub fn leading_trivia(&self, source: &str, previous: Option<Token>) -> Span {
+        let start = previous.map_or(0, |previous| previous.trailing_trivia(source).end());
+        Span::new(start, self.span.start - start)
+    }
+
+    /// Returns the span of the whitespace and comments following this token within `source`,
+    /// up to the end of its line.
+    ///
+    /// If no tokens follow, this extends to the end of the source.
+    ///
+    /// Like [leading trivia](Token::leading_trivia), this is recomputed from `source`.
+    pub fn trailing_trivia(&self, source: &str) -> Span {
+        let mut lexer = Lexer::from(source).seek(self.span.end());
+        let trivia = lexer.skip_trailing_trivia();
+        lexer.span(trivia)
+    }
+
+    /// Returns the value that this token represents within `source`,
+    /// with any quotes and escape sequences resolved.
+    ///
+    /// Raw strings, and strings and identifiers without escape sequences,
+    /// are borrowed from `source` unchanged.
+    /// Multi-line strings have their indentation removed before escape sequences are resolved.
+    pub fn resolve<'a>(&self, source: &'a str) -> Result<'static, Cow<'a, str>, EscapeError> {
         fn resolve_escape(chars: &mut std::str::Chars) -> Result<'static, char, EscapeError> {
             let escaped = match chars
                 .next()
@@ -104,6 +223,9 @@ impl Token<'_> {
                 '0' => '\0',
                 // used by strings
                 '"' => '"',
+                // used by interpolated strings
+                '{' => '{',
+                '}' => '}',
                 // used by raw identifiers
                 '`' => '`',
                 // unused, but available
@@ -131,39 +253,97 @@ impl Token<'_> {
             Ok(escaped)
         }
 
-        match self.lexigram {
-            Lexigram::String => {
-                let mut s = String::new();
-                // trim quotes
-                let mut chars = self.origin[1..(self.origin.len() - 1)].chars();
-                while let Some(c) = chars.next() {
-                    if c == '\\' {
-                        s.push(resolve_escape(&mut chars)?);
-                    } else {
-                        s.push(c);
-                    }
+        fn resolve_escapes(contents: &str) -> Result<'static, Cow<'_, str>, EscapeError> {
+            if !contents.contains('\\') {
+                return Ok(Cow::Borrowed(contents));
+            }
+            let mut s = String::new();
+            let mut chars = contents.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    s.push(resolve_escape(&mut chars)?);
+                } else {
+                    s.push(c);
                 }
-                Ok(s)
             }
-            Lexigram::Ident if self.origin.starts_with('`') => {
-                let mut s = String::new();
+            Ok(Cow::Owned(s))
+        }
+
+        let origin = self.origin(source);
+        match self.lexigram {
+            Lexigram::String if origin.starts_with('r') => {
+                // trim the `r`, quotes, and hashes
+                let hashes = origin[1..].len() - origin[1..].trim_start_matches('#').len();
+                Ok(Cow::Borrowed(
+                    &origin[2 + hashes..origin.len() - 1 - hashes],
+                ))
+            }
+            Lexigram::String if origin.starts_with(MULTI_LINE_QUOTE) => {
+                let contents =
+                    &origin[MULTI_LINE_QUOTE.len()..origin.len() - MULTI_LINE_QUOTE.len()];
+                Ok(Cow::Owned(
+                    resolve_escapes(&strip_indentation(contents))?.into_owned(),
+                ))
+            }
+            Lexigram::String
+            | Lexigram::StringStart
+            | Lexigram::StringMiddle
+            | Lexigram::StringEnd => {
+                // trim quotes (or the braces of an interpolated string)
+                resolve_escapes(&origin[1..(origin.len() - 1)])
+            }
+            Lexigram::Ident if origin.starts_with('`') => {
                 // trim quotes
-                let mut chars = self.origin[1..(self.origin.len() - 1)].chars();
-                while let Some(c) = chars.next() {
-                    if c == '\\' {
-                        s.push(resolve_escape(&mut chars)?);
-                    } else {
-                        s.push(c);
-                    }
-                }
-                Ok(s)
+                resolve_escapes(&origin[1..(origin.len() - 1)])
             }
-            _ => Ok(self.origin.into()),
+            _ => Ok(Cow::Borrowed(origin)),
         }
     }
 }
 
+const MULTI_LINE_QUOTE: &str = "\"\"\"";
+
+/// Removes the line breaks following the opening quotes of a multi-line string
+/// and preceding its closing quotes,
+/// along with the indentation common to every line.
+///
+/// Lines containing only whitespace do not affect the indentation and are left empty,
+/// except for the line of the closing quotes, whose indentation is also removed from every line.
+fn strip_indentation(contents: &str) -> String {
+    fn indentation(line: &str) -> usize {
+        line.len() - line.trim_start_matches([' ', '\t']).len()
+    }
+    let is_blank = |line: &&str| line.trim_start_matches([' ', '\t', '\r']).is_empty();
+
+    let contents = contents
+        .strip_prefix("\r\n")
+        .or_else(|| contents.strip_prefix('\n'))
+        .unwrap_or(contents);
+    let mut lines = contents.split('\n').collect::<Vec<_>>();
+    // Closing quotes on their own line are not part of the string.
+    let closing_indentation = lines.pop_if(|line| is_blank(line)).map(indentation);
+    let indentation = lines
+        .iter()
+        .filter(|line| !is_blank(line))
+        .map(|line| indentation(line))
+        .chain(closing_indentation)
+        .min()
+        .unwrap_or(0);
+    let mut s = String::with_capacity(contents.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if !is_blank(&line) {
+            s.push_str(&line[indentation..]);
+        }
+    }
+    s
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EscapeError {
     MissingOpenBrace,
     MissingCloseBrace,
@@ -172,6 +352,7 @@ pub enum EscapeError {
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ErrorKind {
     /// A character with no meaning to the lexer.
     ///
@@ -185,29 +366,183 @@ pub enum ErrorKind {
     ReservedSymbol,
     /// A quote character was encountered but never terminated.
     UnterminatedString,
+    /// A raw string was never terminated by a quote followed by as many `#`s as began it.
+    UnterminatedRawString,
     /// A backtick character was encountered but never terminated.
     UnterminatedIdentifier,
+    /// A number literal contained a digit outside of its radix (such as `0b2` or `0xG`).
+    ///
+    /// The span of this error is the first invalid digit.
+    InvalidDigit,
+    /// A number literal's radix prefix (`0x`, `0o`, or `0b`) was not followed by any digits.
+    MissingDigits,
+    /// Interpolated strings and the braces within them were nested more than 64 levels deep.
+    ///
+    /// The span of this error is the `{` which exceeded the limit.
+    InterpolationTooDeep,
+    /// The source was larger than 4GiB, which spans cannot address.
+    ///
+    /// The span of this error is empty, and no tokens are produced.
+    SourceTooLarge,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct Error<'source> {
-    pub origin: &'source str,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Error {
+    pub span: Span,
     pub kind: ErrorKind,
 }
 
-pub type Result<'source, T = Token<'source>, E = Error<'source>> = std::result::Result<T, E>;
+pub type Result<'source, T = Token<'source>, E = Error> = std::result::Result<T, E>;
+
+/// A source string along with the positions of its lines,
+/// for resolving [`Span`]s into text and line/column numbers.
+#[derive(Clone, Debug)]
+pub struct SourceFile<'source> {
+    text: &'source str,
+    /// The byte offset of the start of each line.
+    line_starts: Box<[u32]>,
+}
+
+impl<'source> SourceFile<'source> {
+    pub fn new(text: &'source str) -> Self {
+        let line_starts = [0]
+            .into_iter()
+            .chain(
+                text.char_indices()
+                    .filter(|(_, c)| *c == '\n')
+                    // Spans can't refer to lines past 4GiB anyways.
+                    .map_while(|(i, _)| u32::try_from(i + 1).ok()),
+            )
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub fn text(&self) -> &'source str {
+        self.text
+    }
+
+    /// Returns the text that `span` covers.
+    pub fn slice(&self, span: Span) -> &'source str {
+        span.slice(self.text)
+    }
+
+    /// Returns the line and column of a byte offset, both starting from 1.
+    ///
+    /// Columns are counted in characters rather than bytes.
+    pub fn location(&self, offset: u32) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1] as usize;
+        let column = 1 + self.text[line_start..offset as usize].chars().count();
+        (line, column)
+    }
+
+    /// Returns the lines and columns of the start and end of `span`.
+    pub fn span_location(&self, span: Span) -> ((usize, usize), (usize, usize)) {
+        (self.location(span.start), self.location(span.end()))
+    }
+
+    pub fn lexer(&self) -> Lexer<'source> {
+        Lexer::from(self.text)
+    }
+}
+
+/// The `##` doc comments of a source, by the span of the token which each one documents.
+///
+/// Tokens don't record their doc comments, so that they stay small.
+/// Instead, the source is lexed again,
+/// and only the comments preceding `let`, `with`, and identifier tokens
+/// (which may name an enum variant) are kept.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DocComments {
+    spans: HashMap<Span, Span>,
+}
+
+impl DocComments {
+    pub fn new(source: &str) -> Self {
+        let mut lexer = Lexer::from(source);
+        let mut spans = HashMap::new();
+        // The end of the previous token (or lexer error), where the next token's leading trivia begins.
+        let mut end = 0;
+        while let Some(result) = Iterator::next(&mut lexer) {
+            let span = match result {
+                Ok(token) => token.span,
+                Err(error) => error.span,
+            };
+            if let Ok(token) = result
+                && matches!(
+                    token.lexigram,
+                    Lexigram::Let | Lexigram::With | Lexigram::Ident
+                )
+                && let Some(doc_comment) =
+                    lexer.doc_comment(&source[end as usize..span.start as usize])
+            {
+                spans.insert(span, doc_comment);
+            }
+            end = span.end();
+        }
+        Self { spans }
+    }
+
+    /// The span of the run of `##` comment lines directly preceding `token`, if any.
+    pub fn get(&self, token: Token) -> Option<Span> {
+        self.spans.get(&token.span).copied()
+    }
+
+    /// Documents `token` with `doc_comment`, replacing any doc comment it had.
+    pub fn insert(&mut self, token: Token, doc_comment: Span) {
+        self.spans.insert(token.span, doc_comment);
+    }
+
+    /// Returns the text of `token`'s doc comment within `source`,
+    /// with the `##` (and a single space after it) removed from each line.
+    pub fn text(&self, token: Token, source: &str) -> Option<String> {
+        let lines = self.get(token)?.slice(source).lines().map(|line| {
+            let line = line.trim_start().trim_end_matches('\r');
+            let line = line.strip_prefix("##").unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line)
+        });
+        Some(lines.collect::<Vec<_>>().join("\n"))
+    }
+}
 
 /// An iterator that produces tokens.
 ///
 /// This should be made [`Peekable`](std::iter::Peekable) using the [`Iterator::peekable`] function prior to parsing.
+/// Sources larger than 4GiB are not supported, because token spans use 32-bit offsets.
 #[derive(Copy, Clone, Default)]
 pub struct Lexer<'source> {
+    source: &'source str,
     cursor: &'source str,
+    /// How many braces are open within interpolated strings.
+    ///
+    /// This is only counted while `interpolations` is non-zero.
+    braces: u32,
+    /// A bit for each open brace, set if the brace began an expression in an interpolated string.
+    interpolations: u64,
 }
 
 impl<'source> From<&'source str> for Lexer<'source> {
     fn from(source: &'source str) -> Self {
-        Self { cursor: source }
+        Self {
+            source,
+            cursor: source,
+            braces: 0,
+            interpolations: 0,
+        }
+    }
+}
+
+impl Lexer<'_> {
+    /// Moves the lexer to a byte offset of its source, which should lie between tokens
+    /// and outside of any interpolated string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds or not on a character boundary.
+    pub fn seek(mut self, offset: u32) -> Self {
+        self.cursor = &self.source[offset as usize..];
+        self
     }
 }
 
@@ -234,18 +569,143 @@ impl<'source> Lexer<'source> {
     fn peek(&self) -> Option<char> {
         self.cursor.chars().next()
     }
+
+    /// Consumes the contents of a string up to its closing quote,
+    /// or up to the `{` of an interpolated expression.
+    ///
+    /// Returns true if the string was closed.
+    fn string(&mut self, root: &'source str) -> Result<'source, bool> {
+        loop {
+            match self.next() {
+                Some('\\') => {
+                    self.next();
+                }
+                Some('"') => return Ok(true),
+                Some('{') => {
+                    if self.braces == u64::BITS {
+                        let len = self.cursor.as_ptr() as usize - root.as_ptr() as usize;
+                        return Err(Error {
+                            span: self.span(&root[len - 1..len]),
+                            kind: ErrorKind::InterpolationTooDeep,
+                        });
+                    }
+                    self.interpolations |= 1 << self.braces;
+                    self.braces += 1;
+                    return Ok(false);
+                }
+                None => {
+                    return Err(Error {
+                        span: self.span(root),
+                        kind: ErrorKind::UnterminatedString,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the span of a slice of the source.
+    ///
+    /// Oversized sources are rejected before any tokens are lexed, so offsets always fit in 32 bits.
+    fn span(&self, slice: &str) -> Span {
+        Span::new(
+            (slice.as_ptr() as usize - self.source.as_ptr() as usize) as u32,
+            slice.len() as u32,
+        )
+    }
+
+    /// Consumes whitespace and comments, returning the slice that was skipped.
+    fn skip_trivia(&mut self) -> &'source str {
+        let root = self.cursor;
+        while let Some(skipped) = self.next_if(|c| matches!(c, ' ' | '\n' | '\r' | '\t' | '#')) {
+            if skipped == '#' {
+                while self.next_if(|c| c != '\n').is_some() {}
+            }
+        }
+        &root[..root.len() - self.cursor.len()]
+    }
+
+    /// Finds the run of `##` comments closest to the end of `leading`.
+    ///
+    /// Only comments which begin their line are considered,
+    /// and any other comment between them and the token breaks the run.
+    fn doc_comment(&self, leading: &'source str) -> Option<Span> {
+        let mut doc_comment: Option<Span> = None;
+        let mut line_start = leading.as_ptr() == self.source.as_ptr();
+        for line in leading.split('\n') {
+            let comment = line.trim_start_matches([' ', '\t', '\r']);
+            if line_start && comment.starts_with("##") {
+                let span = self.span(comment.trim_end_matches('\r'));
+                doc_comment = Some(
+                    doc_comment.map_or(span, |doc| Span::new(doc.start, span.end() - doc.start)),
+                );
+            } else if comment.starts_with('#') {
+                doc_comment = None;
+            }
+            line_start = true;
+        }
+        doc_comment
+    }
+
+    /// Consumes whitespace and comments up to the end of the line,
+    /// or to the end of the source if no tokens follow.
+    fn skip_trailing_trivia(&mut self) -> &'source str {
+        let root = self.cursor;
+        let mut rest = *self;
+        rest.skip_trivia();
+        if rest.cursor.is_empty() {
+            self.cursor = rest.cursor;
+        } else {
+            while let Some(skipped) = self.next_if(|c| matches!(c, ' ' | '\t' | '#')) {
+                if skipped == '#' {
+                    while self.next_if(|c| c != '\n').is_some() {}
+                }
+            }
+        }
+        &root[..root.len() - self.cursor.len()]
+    }
 }
 
 impl<'source> Iterator for Lexer<'source> {
     type Item = Result<'source>;
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(skipped) = self.next_if(|c| matches!(c, ' ' | '\n' | '\r' | '\t' | '#')) {
-            if skipped == '#' {
-                while self.next_if(|c| c != '\n').is_some() {}
+        if u32::try_from(self.source.len()).is_err() {
+            if self.cursor.is_empty() {
+                return None;
             }
+            self.cursor = &self.source[self.source.len()..];
+            return Some(Err(Error {
+                span: Span::default(),
+                kind: ErrorKind::SourceTooLarge,
+            }));
         }
+        self.skip_trivia();
         let root = self.cursor;
         let lexigram = match self.next()? {
+            // Raw String
+            'r' if self.cursor.trim_start_matches('#').starts_with('"') => {
+                let hashes = self.cursor.len() - self.cursor.trim_start_matches('#').len();
+                self.cursor = &self.cursor[hashes + 1..];
+                loop {
+                    match self.next() {
+                        Some('"')
+                            if self.cursor.len() >= hashes
+                                && self.cursor.as_bytes()[..hashes].iter().all(|c| *c == b'#') =>
+                        {
+                            self.cursor = &self.cursor[hashes..];
+                            break;
+                        }
+                        None => {
+                            return Some(Err(Error {
+                                span: self.span(root),
+                                kind: ErrorKind::UnterminatedRawString,
+                            }));
+                        }
+                        _ => {}
+                    }
+                }
+                Lexigram::String
+            }
             // Ident
             'A'..='Z' | 'a'..='z' | '_' => {
                 let mut length = 1;
@@ -262,18 +722,19 @@ impl<'source> Iterator for Lexer<'source> {
                 }
                 let ident = &root[0..length];
                 match ident {
-                    "array" | "as" | "async" | "await" | "break" | "case" | "class" | "const"
+                    "array" | "as" | "async" | "await" | "case" | "class" | "const"
                     | "continue" | "do" | "dyn" | "fn" | "for" | "impl" | "import" | "in"
                     | "include" | "iterator" | "loop" | "macro" | "mod" | "move" | "never"
-                    | "priv" | "pub" | "ref" | "require" | "return" | "safe" | "static"
-                    | "struct" | "super" | "switch" | "trait" | "try" | "tuple" | "type"
-                    | "union" | "unsafe" | "use" | "where" | "while" | "yield" => {
+                    | "priv" | "pub" | "ref" | "require" | "safe" | "static" | "struct"
+                    | "super" | "switch" | "trait" | "try" | "tuple" | "type" | "union"
+                    | "unsafe" | "use" | "where" | "while" | "yield" => {
                         return Some(Err(Error {
-                            origin: ident,
+                            span: self.span(ident),
                             kind: ErrorKind::ReservedSymbol,
                         }));
                     }
                     "and" => Lexigram::And,
+                    "break" => Lexigram::Break,
                     "else" => Lexigram::Else,
                     "end" => Lexigram::End,
                     "enum" => Lexigram::Enum,
@@ -282,6 +743,7 @@ impl<'source> Iterator for Lexer<'source> {
                     "let" => Lexigram::Let,
                     "match" => Lexigram::Match,
                     "or" => Lexigram::Or,
+                    "return" => Lexigram::Return,
                     "set" => Lexigram::Set,
                     "then" => Lexigram::Then,
                     "true" => Lexigram::True,
@@ -290,22 +752,86 @@ impl<'source> Iterator for Lexer<'source> {
                     _ => Lexigram::Ident,
                 }
             }
+            // Label
+            '\'' if self
+                .cursor
+                .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') =>
+            {
+                while self
+                    .next_if(|c| {
+                        matches!(
+                            c,
+                            'A'..='Z' | 'a'..='z' | '_' | '0'..='9'
+                        )
+                    })
+                    .is_some()
+                {}
+                Lexigram::Label
+            }
             // Number
+            '0' if self.next_if(|c| matches!(c, 'x' | 'o' | 'b')).is_some() => {
+                let radix = match root[1..].chars().next() {
+                    Some('x') => 16,
+                    Some('o') => 8,
+                    _ => 2,
+                };
+                // Consume every alphanumeric character so that invalid digits are reported
+                // rather than being interpreted as the start of a new token.
+                let digits = self.cursor;
+                let mut invalid_digit = None;
+                let mut has_digits = false;
+                while let Some(c) = self.next_if(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    if c == '_' {
+                        continue;
+                    }
+                    has_digits = true;
+                    if !c.is_digit(radix) && invalid_digit.is_none() {
+                        let offset = digits.len() - self.cursor.len() - c.len_utf8();
+                        invalid_digit = Some(&digits[offset..offset + c.len_utf8()]);
+                    }
+                }
+                if let Some(digit) = invalid_digit {
+                    return Some(Err(Error {
+                        span: self.span(digit),
+                        kind: ErrorKind::InvalidDigit,
+                    }));
+                }
+                if !has_digits {
+                    let len = self.cursor.as_ptr() as usize - root.as_ptr() as usize;
+                    return Some(Err(Error {
+                        span: self.span(&root[..len]),
+                        kind: ErrorKind::MissingDigits,
+                    }));
+                }
+                Lexigram::Number
+            }
             '0'..='9' => {
-                while self.next_if(|c| matches!(c, '0'..='9' | '.')).is_some() {}
+                while self.next_if(|c| matches!(c, '0'..='9' | '_')).is_some() {}
+                // Only consume a decimal point if it is followed by a digit,
+                // so that `1..2` and `x.0.1` are not mistaken for decimals.
+                if let Some(fraction) = self.cursor.strip_prefix('.')
+                    && fraction.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    self.next();
+                    while self.next_if(|c| matches!(c, '0'..='9' | '_')).is_some() {}
+                }
                 Lexigram::Number
             }
-            // String
-            '"' => {
+            // Multi-line String
+            '"' if self.cursor.starts_with("\"\"\n") || self.cursor.starts_with("\"\"\r\n") => {
+                self.cursor = &self.cursor[2..];
                 loop {
                     match self.next() {
                         Some('\\') => {
                             self.next();
                         }
-                        Some('"') => break,
+                        Some('"') if self.cursor.starts_with("\"\"") => {
+                            self.cursor = &self.cursor[2..];
+                            break;
+                        }
                         None => {
                             return Some(Err(Error {
-                                origin: root,
+                                span: self.span(root),
                                 kind: ErrorKind::UnterminatedString,
                             }));
                         }
@@ -314,6 +840,26 @@ impl<'source> Iterator for Lexer<'source> {
                 }
                 Lexigram::String
             }
+            // String
+            '"' => match self.string(root) {
+                Ok(true) => Lexigram::String,
+                Ok(false) => Lexigram::StringStart,
+                Err(e) => return Some(Err(e)),
+            },
+            '}' if self.interpolations != 0 => {
+                self.braces -= 1;
+                let interpolation = 1 << self.braces;
+                if self.interpolations & interpolation != 0 {
+                    self.interpolations &= !interpolation;
+                    match self.string(root) {
+                        Ok(true) => Lexigram::StringEnd,
+                        Ok(false) => Lexigram::StringMiddle,
+                        Err(e) => return Some(Err(e)),
+                    }
+                } else {
+                    Lexigram::CloseBrace
+                }
+            }
             // Raw Identifier
             '`' => {
                 loop {
@@ -324,7 +870,7 @@ impl<'source> Iterator for Lexer<'source> {
                         Some('`') => break,
                         None => {
                             return Some(Err(Error {
-                                origin: root,
+                                span: self.span(root),
                                 kind: ErrorKind::UnterminatedIdentifier,
                             }));
                         }
@@ -369,21 +915,28 @@ impl<'source> Iterator for Lexer<'source> {
             ')' => Lexigram::CloseParen,
             '[' => Lexigram::OpenSquare,
             ']' => Lexigram::CloseSquare,
+            '{' if self.interpolations != 0 => {
+                if self.braces == u64::BITS {
+                    return Some(Err(Error {
+                        span: self.span(&root[..1]),
+                        kind: ErrorKind::InterpolationTooDeep,
+                    }));
+                }
+                self.braces += 1;
+                Lexigram::OpenBrace
+            }
             '{' => Lexigram::OpenBrace,
             '}' => Lexigram::CloseBrace,
             ':' => Lexigram::Colon,
             ';' => Lexigram::Semicolon,
             _ => {
                 return Some(Err(Error {
-                    origin: &root[..1],
+                    span: self.span(&root[..root.len() - self.cursor.len()]),
                     kind: ErrorKind::UnexpectedCharacter,
                 }));
             }
         };
         let len = self.cursor.as_ptr() as usize - root.as_ptr() as usize;
-        Some(Ok(Token {
-            origin: &root[..len],
-            lexigram,
-        }))
+        Some(Ok(Token::new(self.span(&root[..len]), lexigram)))
     }
 }
@@ -11,6 +11,7 @@ pub type StackPointer = i32;
 pub type BlockId = u32;
 pub type StringId = u32;
 pub type StringSet = u32;
+pub type TypeId = u32;
 
 // TODO: Reorder these before release.
 pub mod instruction {
@@ -20,6 +21,7 @@ pub mod instruction {
     pub const COLLAPSE: u8 = 0x02;
     pub const JUMP: u8 = 0x03;
     pub const IF: u8 = 0x04;
+    pub const NO_MATCH: u8 = 0x05;
 
     // Push ops: 0x10-0x2F
     pub const PUSH_UNIT: u8 = 0x10;
@@ -29,6 +31,8 @@ pub mod instruction {
     pub const PUSH_STRING: u8 = 0x14;
     pub const PUSH_FUNCTION: u8 = 0x15;
     pub const PUSH_ENUM: u8 = 0x16;
+    pub const PUSH_F64: u8 = 0x17;
+    pub const PUSH_TYPE: u8 = 0x18;
 
     // Operations: 0x30..
     pub const ADD: u8 = 0x30;
@@ -56,6 +60,65 @@ pub mod instruction {
     pub const NEGATIVE: u8 = 0x45;
     pub const DEREF: u8 = 0x46;
     pub const SET: u8 = 0x47;
+    pub const MATCHES: u8 = 0x48;
+    pub const RANGE: u8 = 0x49;
+    pub const RANGE_INCLUSIVE: u8 = 0x4A;
+    pub const SLICE: u8 = 0x4B;
+    pub const REMOVE: u8 = 0x4C;
+    pub const NOT: u8 = 0x4D;
+    pub const CONCAT: u8 = 0x4E;
+    pub const IS_TYPE: u8 = 0x4F;
+    pub const TRY_INDEX: u8 = 0x50;
+}
+
+/// Kinds of metadata section, identified by a section's first byte.
+///
+/// Apart from [`TYPES`](metadata::TYPES), metadata has no effect on a program's behavior,
+/// and interpreters ignore sections of kinds they do not recognize.
+pub mod metadata {
+    /// Pairs of a function's block id and the string id of its doc comment.
+    pub const DOC_COMMENTS: u8 = 0x00;
+    /// The source code which produced each block's instructions.
+    ///
+    /// Each entry is a block id, a program counter,
+    /// the start and end of a source range as byte offsets,
+    /// and the line and column (counted from 1) of the range's start.
+    /// An entry applies to every instruction of its block from its program counter
+    /// up to that of the block's next entry.
+    pub const SOURCE_MAP: u8 = 0x01;
+    /// Types which were built at compile time, encoded one after another as described by
+    /// [`type_tags`](crate::type_tags).
+    ///
+    /// [`PUSH_TYPE`](crate::instruction::PUSH_TYPE) refers to these by their position.
+    /// Interpreters which don't recognize this section won't recognize that instruction either.
+    ///
+    /// Each execution of that instruction builds its type anew,
+    /// so an enum pushed twice defines two distinct enums.
+    pub const TYPES: u8 = 0x02;
+}
+
+/// The first byte of each type in a [`TYPES`](metadata::TYPES) section.
+///
+/// Counts and string ids are 32 bits, like those of instructions.
+pub mod type_tags {
+    pub const ANY: u8 = 0x00;
+    pub const UNIT: u8 = 0x01;
+    pub const I64: u8 = 0x02;
+    pub const F64: u8 = 0x03;
+    pub const STRING: u8 = 0x04;
+    pub const BOOL: u8 = 0x05;
+    /// Followed by the type of the option's contents.
+    pub const OPTION: u8 = 0x06;
+    /// Followed by the number of variants, and then the string id of each variant's name and its type.
+    pub const ENUM: u8 = 0x07;
+    /// Followed by the number of elements, and then the type of each.
+    ///
+    /// Tuples may only appear within other types.
+    pub const TUPLE: u8 = 0x08;
+    /// Followed by the number of elements, and then the string id of each element's name and its type.
+    ///
+    /// Tuples may only appear within other types.
+    pub const NAMED_TUPLE: u8 = 0x09;
 }
 
 // TODO: Reorder these before release.
@@ -67,6 +130,9 @@ pub mod builtins {
     pub const UNIT: StackPointer = -3;
     pub const I64: StackPointer = -4;
     pub const MUT: StackPointer = -5;
+    pub const F64: StackPointer = -6;
+    pub const STRING: StackPointer = -7;
+    pub const BOOL: StackPointer = -8;
 
     pub fn from_str(s: &str) -> Option<StackPointer> {
         match s {
@@ -75,6 +141,9 @@ pub mod builtins {
             "unit" => Some(UNIT),
             "i64" => Some(I64),
             "mut" => Some(MUT),
+            "f64" => Some(F64),
+            "string" => Some(STRING),
+            "bool" => Some(BOOL),
             _ => None,
         }
     }
@@ -40,6 +40,7 @@ pub enum Value<'host> {
     Borrow(&'host dyn Extern),
     Owned(Rc<dyn ExternOwned>),
     I64(i64),
+    F64(f64),
     Bool(bool),
     String(Rc<str>),
     Function(Rc<Function<'host>>),
@@ -49,6 +50,7 @@ pub enum Value<'host> {
         ty: Rc<ComplexType>,
     },
     Mut(Mut<'host>),
+    Range(Range),
 
     Type(Type),
 }
@@ -151,6 +153,39 @@ impl<'host> Value<'host> {
         }
     }
 
+    /// Returns the values of a tuple from `start` onward, or unit if there are none.
+    pub fn slice(self, start: i64) -> Result<Value<'host>, Error<'host>> {
+        match self {
+            Value::Unit => Ok(Value::Unit),
+            Value::Tuple(tuple) => {
+                let tuple = tuple.slice(start as usize);
+                Ok(if tuple.is_empty() {
+                    Value::Unit
+                } else {
+                    Value::Tuple(tuple)
+                })
+            }
+            _ => Err(Error::ExpectedTuple(self)),
+        }
+    }
+
+    /// Returns a named tuple without the field called `name`, or unit if no fields remain.
+    pub fn remove(self, name: &str) -> Result<Value<'host>, Error<'host>> {
+        match self {
+            Value::Tuple(tuple) => {
+                let Some(tuple) = tuple.remove(name) else {
+                    return Err(Error::ExpectedNamedTuple(Value::Tuple(tuple)));
+                };
+                Ok(if tuple.is_empty() {
+                    Value::Unit
+                } else {
+                    Value::Tuple(tuple)
+                })
+            }
+            _ => Err(Error::ExpectedNamedTuple(self)),
+        }
+    }
+
     pub fn index(&self, index: impl Into<Value<'host>>) -> Result<Value<'host>, Error<'host>> {
         match (self, index.into()) {
             (Value::Borrow(external), index) => external.index(index),
@@ -176,12 +211,14 @@ pub enum Type {
     /// This means that external values must be casted to trait objects to interact with them.
     Any,
     I64,
+    F64,
     Bool,
     String,
     Function(Rc<FunctionType>),
     Enum(Rc<EnumType>),
     Option(Rc<ComplexType>),
     Mut(Rc<ComplexType>),
+    Range,
 
     /// The type of types.
     Type,
@@ -259,12 +296,14 @@ impl std::fmt::Debug for Value<'_> {
                 write!(f, ")")
             }
             Value::I64(i) => write!(f, "I64({i:?})"),
+            Value::F64(i) => write!(f, "F64({i:?})"),
             Value::Bool(i) => write!(f, "Bool({i:?})"),
             Value::String(i) => write!(f, "String({i:?})"),
             Value::Function(function) => write!(f, "Function({function:?})"),
             Value::EnumVariant(enum_variant) => write!(f, "EnumVariant({enum_variant:?})"),
             Value::Option { contents, ty: _ } => write!(f, "{contents:?}"),
             Value::Mut(inner) => write!(f, "Mut({inner:?})"),
+            Value::Range(range) => write!(f, "Range({range:?})"),
             Value::Type(t) => write!(f, "{t:?}"),
         }
     }
@@ -289,7 +328,9 @@ impl<'host> Value<'host> {
                 Ok(true)
             }
             (Value::I64(l), Value::I64(r)) => Ok(l == r),
+            (Value::F64(l), Value::F64(r)) => Ok(l == r),
             (Value::Bool(l), Value::Bool(r)) => Ok(l == r),
+            (Value::String(l), Value::String(r)) => Ok(l == r),
             (Value::EnumVariant(l), Value::EnumVariant(r)) => Ok(l.variant == r.variant
                 && Rc::ptr_eq(&l.definition, &r.definition)
                 && Rc::try_unwrap(l)
@@ -326,11 +367,67 @@ impl<'host> Value<'host> {
                     ))
                 }
             }
+            (Value::Range(l), Value::Range(r)) => Ok(l == r),
             (Value::Type(l), Value::Type(r)) => Ok(l == r),
             (this, other) => Err(Error::IncomparableValues(this, other)),
         }
     }
 
+    /// Tests whether `case` accepts this value, as in a match expression.
+    ///
+    /// Enum variant and option constructors accept values of their variant,
+    /// producing the variant's contents.
+    /// Any other case is compared to the value for equality,
+    /// producing the value itself if they are equal.
+    /// Values which cannot be compared to the case (such as a string and an integer) never match.
+    pub fn matches(self, case: Self) -> Result<Option<Value<'host>>, Error<'host>> {
+        let constructor = match &case {
+            Value::Function(function) if matches!(function.argument, Value::Unit) => {
+                Some(&function.action)
+            }
+            _ => None,
+        };
+        match (constructor, self) {
+            (
+                Some(FunctionAction::Enum {
+                    variant,
+                    definition,
+                }),
+                value,
+            ) => Ok(match value {
+                Value::EnumVariant(value)
+                    if value.variant == *variant && Rc::ptr_eq(&value.definition, definition) =>
+                {
+                    Some(
+                        Rc::try_unwrap(value)
+                            .map(|value| value.contents)
+                            .unwrap_or_else(|value| value.contents.clone()),
+                    )
+                }
+                _ => None,
+            }),
+            (Some(FunctionAction::Some(ty)), value) => Ok(match value {
+                Value::Option {
+                    contents: Some(contents),
+                    ty: value_ty,
+                } if value_ty == *ty => Some(Rc::unwrap_or_clone(contents)),
+                _ => None,
+            }),
+            (Some(FunctionAction::None(ty)), value) => Ok(match value {
+                Value::Option {
+                    contents: None,
+                    ty: value_ty,
+                } if value_ty == *ty => Some(Value::Unit),
+                _ => None,
+            }),
+            (_, value) => match value.clone().eq(case) {
+                Ok(true) => Ok(Some(value)),
+                Ok(false) | Err(Error::IncomparableValues(..)) => Ok(None),
+                Err(error) => Err(error),
+            },
+        }
+    }
+
     /// # Errors
     ///
     /// Returns an error if a mutable reference is being mutably borrowed.
@@ -365,6 +462,7 @@ impl<'host> Value<'host> {
             }
             Value::Borrow(_) | Value::Owned(_) => Type::Any.into(),
             Value::I64(_) => Type::I64.into(),
+            Value::F64(_) => Type::F64.into(),
             Value::Bool(_) => Type::Bool.into(),
             Value::String(_) => Type::String.into(),
             Value::Function(function) => match &function.action {
@@ -388,6 +486,7 @@ impl<'host> Value<'host> {
                     .into(),
             )
             .into(),
+            Value::Range(_) => Type::Range.into(),
             Value::Type(_) => Type::Type.into(),
         })
     }
@@ -446,10 +545,68 @@ impl<'host> Value<'host> {
         self.try_into()
     }
 
+    pub fn into_f64(self) -> Result<f64, Error<'host>> {
+        self.try_into()
+    }
+
     pub fn into_str(self) -> Result<Rc<str>, Error<'host>> {
         self.try_into()
     }
 
+    /// Appends this value to `output` as an interpolated string would.
+    ///
+    /// Strings are written as-is,
+    /// unit, booleans, and numbers are written as they would appear in source code,
+    /// and tuples are written as their values (and names) separated by commas, in parentheses.
+    /// References are written as the value they refer to.
+    /// Any other value is an error.
+    pub fn interpolate(&self, output: &mut String) -> Result<(), Error<'host>> {
+        use std::fmt::Write;
+        match self {
+            Value::Unit => output.push_str("()"),
+            Value::Tuple(Tuple(TupleStorage::Numeric(items))) => {
+                output.push('(');
+                for (i, value) in items.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    value.interpolate(output)?;
+                }
+                output.push(')');
+            }
+            Value::Tuple(Tuple(TupleStorage::Named(items))) => {
+                output.push('(');
+                for (i, (name, value)) in items.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    output.push_str(name);
+                    output.push_str(": ");
+                    value.interpolate(output)?;
+                }
+                output.push(')');
+            }
+            Value::I64(i) => {
+                let _ = write!(output, "{i}");
+            }
+            // Debug formatting keeps the decimal point of whole numbers.
+            Value::F64(f) => {
+                let _ = write!(output, "{f:?}");
+            }
+            Value::Bool(b) => {
+                let _ = write!(output, "{b}");
+            }
+            Value::String(s) => output.push_str(s),
+            Value::Mut(inner) => inner
+                .upgrade()
+                .ok_or(Error::UpgradeError)?
+                .try_borrow()?
+                .interpolate(output)?,
+            _ => Err(Error::ExpectedPrintable(self.clone()))?,
+        }
+        Ok(())
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         if let Value::String(s) = &self {
             Some(s)
@@ -488,6 +645,10 @@ impl<'host> Value<'host> {
         }
     }
 
+    pub fn into_range(self) -> Result<Range, Error<'host>> {
+        self.try_into()
+    }
+
     pub fn into_complex_type(self) -> Result<ComplexType, Error<'host>> {
         self.try_into()
     }
@@ -542,6 +703,21 @@ impl From<Option<i64>> for Value<'_> {
     }
 }
 
+impl From<f64> for Value<'_> {
+    fn from(f: f64) -> Self {
+        Value::F64(f)
+    }
+}
+
+impl From<Option<f64>> for Value<'_> {
+    fn from(value: Option<f64>) -> Self {
+        Value::Option {
+            contents: value.map(|value| Rc::new(Value::F64(value))),
+            ty: Rc::new(Type::F64.into()),
+        }
+    }
+}
+
 impl From<Rc<str>> for Value<'_> {
     fn from(s: Rc<str>) -> Self {
         Value::String(s)
@@ -605,6 +781,30 @@ impl<'host> TryFrom<Value<'host>> for i64 {
     }
 }
 
+impl<'host> TryFrom<Value<'host>> for f64 {
+    type Error = Error<'host>;
+
+    fn try_from(value: Value<'host>) -> Result<Self, Self::Error> {
+        if let Value::F64(value) = value {
+            Ok(value)
+        } else {
+            Err(Error::type_error(value, Type::F64))
+        }
+    }
+}
+
+impl<'host> TryFrom<Value<'host>> for Range {
+    type Error = Error<'host>;
+
+    fn try_from(value: Value<'host>) -> Result<Self, Self::Error> {
+        if let Value::Range(value) = value {
+            Ok(value)
+        } else {
+            Err(Error::type_error(value, Type::Range))
+        }
+    }
+}
+
 impl<'host> TryFrom<Value<'host>> for Rc<str> {
     type Error = Error<'host>;
 
@@ -723,6 +923,14 @@ impl<T> Tuple<T> {
             .find_map(|(n, v)| if **n == *name { Some(v) } else { None })
     }
 
+    /// Returns the names of the tuple's values, or `None` if its values are unnamed.
+    pub fn names(&self) -> Option<impl Iterator<Item = &Rc<str>>> {
+        let TupleStorage::Named(items) = &self.0 else {
+            return None;
+        };
+        Some(items.iter().map(|(name, _)| name))
+    }
+
     pub fn values(&self) -> impl Iterator<Item = &T> {
         (0..self.len()).map(|i| match &self.0 {
             TupleStorage::Numeric(items) => &items[i],
@@ -731,6 +939,41 @@ impl<T> Tuple<T> {
     }
 }
 
+impl<T: Clone> Tuple<T> {
+    /// Returns the values of this tuple from `start` onward, preserving their names.
+    pub fn slice(&self, start: usize) -> Self {
+        match &self.0 {
+            TupleStorage::Numeric(items) => {
+                let items = items.get(start..).unwrap_or(&[]);
+                Tuple(TupleStorage::Numeric(rc_slice_from_iter(
+                    items.len(),
+                    items.iter().cloned(),
+                )))
+            }
+            TupleStorage::Named(items) => {
+                let items = items.get(start..).unwrap_or(&[]);
+                Tuple(TupleStorage::Named(rc_slice_from_iter(
+                    items.len(),
+                    items.iter().cloned(),
+                )))
+            }
+        }
+    }
+
+    /// Returns this tuple without the field called `name`,
+    /// or `None` if this is not a named tuple.
+    pub fn remove(&self, name: &str) -> Option<Self> {
+        let TupleStorage::Named(items) = &self.0 else {
+            return None;
+        };
+        let len = items.iter().filter(|(n, _)| **n != *name).count();
+        Some(Tuple(TupleStorage::Named(rc_slice_from_iter(
+            len,
+            items.iter().filter(|(n, _)| **n != *name).cloned(),
+        ))))
+    }
+}
+
 impl<'host> TryFrom<Tuple<Value<'host>>> for Tuple<ComplexType> {
     type Error = Error<'host>;
 
@@ -822,6 +1065,16 @@ impl<'host> Function<'host> {
         }
     }
 
+    /// Returns the doc comment of the `with` block this function was created from, if any.
+    pub fn doc_comment(&self) -> Option<&str> {
+        match &self.action {
+            FunctionAction::With {
+                program, block_id, ..
+            } => program.doc_comment(*block_id),
+            _ => None,
+        }
+    }
+
     pub fn eval(self) -> Result<Value<'host>, Error<'host>> {
         let result = match self.action {
             FunctionAction::With {
@@ -877,6 +1130,17 @@ impl<'host> Function<'host> {
                 self.argument.into_unit()?;
                 Value::Option { contents: None, ty }
             }
+            FunctionAction::RangeNext => {
+                let mut range = self.argument.into_range()?;
+                Value::Option {
+                    contents: range
+                        .next()
+                        .map(|i| Rc::new(Value::concat(Value::Range(range), i.into()))),
+                    ty: Rc::new(
+                        Tuple::from([ComplexType::from(Type::Range), Type::I64.into()]).into(),
+                    ),
+                }
+            }
             FunctionAction::Borrow(external) => external.call(self.argument)?,
             FunctionAction::Owned(external) => external.call(self.argument)?,
         };
@@ -913,6 +1177,7 @@ enum FunctionAction<'host> {
     Option,
     Some(Rc<ComplexType>),
     None(Rc<ComplexType>),
+    RangeNext,
     Borrow(&'host dyn ExternFn),
     Owned(Rc<dyn ExternFnOwned>),
 }
@@ -953,6 +1218,7 @@ impl std::fmt::Debug for FunctionAction<'_> {
             Self::Option => write!(f, "Option"),
             Self::Some(arg0) => f.debug_tuple("Some").field(arg0).finish(),
             Self::None(arg0) => f.debug_tuple("None").field(arg0).finish(),
+            Self::RangeNext => write!(f, "RangeNext"),
             Self::Borrow(arg0) => arg0.debug(f),
             Self::Owned(arg0) => arg0.debug(f),
         }
@@ -999,6 +1265,46 @@ impl<'host> Clone for Mut<'host> {
     }
 }
 
+/// A range of integers, produced by the `..` and `..=` operators.
+///
+/// Within espy, ranges are iterated by the function that accompanies them,
+/// which produces the range's next state and value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+impl Iterator for Range {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(self.start - 1)
+        } else if self.inclusive && self.start == self.end {
+            // Incrementing start would overflow if end is i64::MAX,
+            // so an exhausted inclusive range is represented as an empty exclusive range.
+            self.inclusive = false;
+            Some(self.start)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'host> Range {
+    /// Returns the range and the function which advances it,
+    /// as expected by espy's iterator functions.
+    pub fn into_iterator(self) -> Value<'host> {
+        Value::concat(
+            Value::Range(self),
+            Value::Function(Rc::new(FunctionAction::RangeNext.into())),
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FunctionType {
     pub input: ComplexType,
@@ -1053,6 +1359,13 @@ impl From<EnumType> for Type {
 #[derive(Debug)]
 pub enum Error<'host> {
     ExpectedNumbers(Value<'host>, Value<'host>),
+    /// The operand of `!` was neither a boolean nor an integer.
+    ExpectedBoolOrInteger(Value<'host>),
+    /// An arithmetic operation was given an i64 and an f64.
+    ///
+    /// espy does not promote integers to floats (or vice versa);
+    /// values must be converted explicitly.
+    MismatchedNumbers(Value<'host>, Value<'host>),
     ExpectedFunction(Value<'host>),
     ExpectedEnumVariant(Value<'host>),
     ExpectedOption(Value<'host>),
@@ -1061,8 +1374,14 @@ pub enum Error<'host> {
     ExpectedTuple(Value<'host>),
     ExpectedNamedTuple(Value<'host>),
     ExpectedReference(Value<'host>),
+    /// A value with no string representation was interpolated into a string.
+    ///
+    /// See [`Value::interpolate`] for the values which may be interpolated.
+    ExpectedPrintable(Value<'host>),
 
     IncomparableValues(Value<'host>, Value<'host>),
+    /// None of a match expression's cases accepted the value.
+    NoMatch(Value<'host>),
     TypeError {
         value: Value<'host>,
         ty: ComplexType,
@@ -1084,6 +1403,14 @@ pub enum Error<'host> {
     /// If this is emitted due to bytecode from the espy compiler,
     /// it should be considered a bug in either program.
     InvalidBytecode(InvalidBytecode),
+    /// An error along with the instruction which caused it.
+    ///
+    /// Errors which occur while evaluating a block are wrapped in this once,
+    /// by the innermost block.
+    Located {
+        location: Location,
+        error: Box<Error<'host>>,
+    },
 }
 
 impl<'host> Error<'host> {
@@ -1093,6 +1420,46 @@ impl<'host> Error<'host> {
             ty: ty.into(),
         }
     }
+
+    /// Returns where the error occurred, if it occurred while evaluating a block.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its location.
+    pub fn into_inner(self) -> Self {
+        match self {
+            Self::Located { error, .. } => *error,
+            error => error,
+        }
+    }
+}
+
+/// The instruction which caused an [`Error`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location {
+    pub block_id: usize,
+    /// The program counter of the instruction within its block.
+    pub pc: usize,
+    /// The source code which produced the instruction,
+    /// if the program was compiled with a source map.
+    pub source: Option<SourceRange>,
+}
+
+/// A range of source code, as recorded by a program's source map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceRange {
+    /// The byte offset of the start of the range.
+    pub start: usize,
+    /// The byte offset of the end of the range.
+    pub end: usize,
+    /// The line of the start of the range, counted from 1.
+    pub line: usize,
+    /// The column of the start of the range, counted from 1 in characters.
+    pub column: usize,
 }
 
 #[derive(Debug)]
@@ -1132,6 +1499,10 @@ pub enum InvalidBytecode {
     InvalidBuiltin,
     /// An instruction referred to a string id that did not exist.
     UnexpectedStringId,
+    /// An instruction referred to a type id that did not exist.
+    UnexpectedTypeId,
+    /// A type in the types section was truncated or had an unknown tag.
+    InvalidType,
     /// Occurs when the header is too short or
     /// describes a program which is longer than the provided slice.
     MalformedHeader,
@@ -1170,9 +1541,13 @@ fn string_count(bytes: &[u8]) -> Result<usize, InvalidBytecode> {
     read_header(bytes, size_of::<u32>())
 }
 
+fn metadata_count(bytes: &[u8]) -> Result<usize, InvalidBytecode> {
+    read_header(bytes, size_of::<u32>() * 2)
+}
+
 fn offsets(bytes: &[u8]) -> Result<&[u8], InvalidBytecode> {
-    let offset_count = block_count(bytes)? + string_count(bytes)?;
-    let first_offset = size_of::<u32>() * 2;
+    let offset_count = block_count(bytes)? + string_count(bytes)? + metadata_count(bytes)?;
+    let first_offset = size_of::<u32>() * 3;
     let last_offset = first_offset + size_of::<u32>() * offset_count;
     bytes
         .get(first_offset..last_offset)
@@ -1189,10 +1564,64 @@ fn block(bytes: &[u8], block_id: usize) -> Result<&[u8], InvalidBytecode> {
         .ok_or(InvalidBytecode::MalformedHeader)
 }
 
+/// Decodes a type from the start of `bytes`, and advances `bytes` past it.
+fn decode_type(bytes: &mut &[u8], strings: &[Rc<str>]) -> Result<ComplexType, InvalidBytecode> {
+    fn next4(bytes: &mut &[u8]) -> Result<usize, InvalidBytecode> {
+        let value = read_header(bytes, 0).map_err(|_| InvalidBytecode::InvalidType)?;
+        *bytes = &bytes[size_of::<u32>()..];
+        Ok(value)
+    }
+    fn name(bytes: &mut &[u8], strings: &[Rc<str>]) -> Result<Rc<str>, InvalidBytecode> {
+        strings
+            .get(next4(bytes)?)
+            .cloned()
+            .ok_or(InvalidBytecode::UnexpectedStringId)
+    }
+    let (&tag, rest) = bytes.split_first().ok_or(InvalidBytecode::InvalidType)?;
+    *bytes = rest;
+    Ok(match tag {
+        type_tags::ANY => Type::Any.into(),
+        type_tags::UNIT => Type::Unit.into(),
+        type_tags::I64 => Type::I64.into(),
+        type_tags::F64 => Type::F64.into(),
+        type_tags::STRING => Type::String.into(),
+        type_tags::BOOL => Type::Bool.into(),
+        type_tags::OPTION => Type::Option(Rc::new(decode_type(bytes, strings)?)).into(),
+        type_tags::ENUM => {
+            let variants = (0..next4(bytes)?)
+                .map(|_| Ok((name(bytes, strings)?, decode_type(bytes, strings)?)))
+                .collect::<Result<_, _>>()?;
+            Type::from(EnumType { variants }).into()
+        }
+        type_tags::TUPLE => Tuple::from(
+            (0..next4(bytes)?)
+                .map(|_| decode_type(bytes, strings))
+                .collect::<Result<Rc<[_]>, _>>()?,
+        )
+        .into(),
+        type_tags::NAMED_TUPLE => Tuple::from(
+            (0..next4(bytes)?)
+                .map(|_| Ok((name(bytes, strings)?, decode_type(bytes, strings)?)))
+                .collect::<Result<Rc<[_]>, _>>()?,
+        )
+        .into(),
+        _ => Err(InvalidBytecode::InvalidType)?,
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct Program {
     pub(crate) bytes: Rc<[u8]>,
     owned_strings: Rc<[Rc<str>]>,
+    /// Encoded types built at compile time, which `PUSH_TYPE` refers to.
+    ///
+    /// These are decoded each time they're pushed so that every evaluation of an `enum`
+    /// defines a distinct enum, as it would if it hadn't been folded.
+    types: Rc<[Rc<[u8]>]>,
+    /// Block ids of functions, and their doc comments' string ids.
+    doc_comments: Rc<[(usize, usize)]>,
+    /// Block ids and program counters, and the source code of the instructions which follow them.
+    source_map: Rc<[(usize, usize, SourceRange)]>,
 }
 
 impl TryFrom<Rc<[u8]>> for Program {
@@ -1212,15 +1641,91 @@ impl TryFrom<Rc<[u8]>> for Program {
                 let string = str::from_utf8(string_bytes).map_err(InvalidBytecode::Utf8Error)?;
                 Ok(Rc::from(string))
             })
-            .collect::<Result<_, Error>>()?;
+            .collect::<Result<Rc<[Rc<str>]>, Error>>()?;
+        let mut doc_comments = Vec::new();
+        let mut source_map = Vec::new();
+        let mut types = Vec::new();
+        for section in 0..metadata_count(&bytes)? {
+            let section = size_of::<u32>() * (section + block_count(&bytes)? + string_count);
+            let offsets = offsets(&bytes)?;
+            let start = read_header(offsets, section)?;
+            let end = read_header(offsets, size_of::<u32>() + section).unwrap_or(bytes.len());
+            let section = bytes
+                .get(start..end)
+                .ok_or(InvalidBytecode::MalformedHeader)?;
+            // Unrecognized kinds of metadata are ignored.
+            match section.split_first() {
+                Some((&metadata::DOC_COMMENTS, pairs)) => {
+                    for pair in pairs.chunks(size_of::<u32>() * 2) {
+                        let block_id = read_header(pair, 0)?;
+                        let string_id = read_header(pair, size_of::<u32>())?;
+                        if string_id >= owned_strings.len() {
+                            Err(InvalidBytecode::UnexpectedStringId)?;
+                        }
+                        doc_comments.push((block_id, string_id));
+                    }
+                }
+                Some((&metadata::SOURCE_MAP, entries)) => {
+                    for entry in entries.chunks(size_of::<u32>() * 6) {
+                        let field = |i| read_header(entry, size_of::<u32>() * i);
+                        source_map.push((
+                            field(0)?,
+                            field(1)?,
+                            SourceRange {
+                                start: field(2)?,
+                                end: field(3)?,
+                                line: field(4)?,
+                                column: field(5)?,
+                            },
+                        ));
+                    }
+                }
+                Some((&metadata::TYPES, mut encoded)) => {
+                    while !encoded.is_empty() {
+                        let rest = encoded;
+                        // Only the contents of other types may be tuples.
+                        let ComplexType::Simple(_) = decode_type(&mut encoded, &owned_strings)?
+                        else {
+                            Err(InvalidBytecode::InvalidType)?
+                        };
+                        types.push(Rc::from(&rest[..rest.len() - encoded.len()]));
+                    }
+                }
+                _ => {}
+            }
+        }
         Ok(Self {
             bytes,
             owned_strings,
+            types: types.into(),
+            doc_comments: doc_comments.into(),
+            source_map: source_map.into(),
         })
     }
 }
 
 impl Program {
+    /// Returns the doc comment of the function whose body is `block_id`,
+    /// if the program was compiled with doc comments.
+    pub fn doc_comment(&self, block_id: usize) -> Option<&str> {
+        self.doc_comments
+            .iter()
+            .find(|(function, _)| *function == block_id)
+            .map(|(_, string_id)| &*self.owned_strings[*string_id])
+    }
+
+    /// Returns the source code which produced the instruction at `pc` of `block_id`,
+    /// if the program was compiled with a source map.
+    pub fn source_range(&self, block_id: usize, pc: usize) -> Option<SourceRange> {
+        // Entries are in order of their program counters within each block.
+        self.source_map
+            .iter()
+            .filter(|(block, _, _)| *block == block_id)
+            .take_while(|(_, start, _)| *start <= pc)
+            .last()
+            .map(|(_, _, range)| *range)
+    }
+
     pub fn eval<'host>(
         &self,
         block_id: usize,
@@ -1229,6 +1734,8 @@ impl Program {
         struct Frame<'a> {
             bytecode: &'a [u8],
             pc: usize,
+            /// The program counter of the instruction being evaluated.
+            instruction: usize,
         }
 
         impl Frame<'_> {
@@ -1261,8 +1768,11 @@ impl Program {
                 ]))
             }
 
+            fn next_f64(&mut self) -> Result<f64, InvalidBytecode> {
+                Ok(f64::from_bits(self.next_i64()? as u64))
+            }
+
             fn pop<'host>(
-                // This doesn't use self yet, but i want to include pc in errors eventually.
                 &'_ self,
                 stack: &mut Vec<Value<'host>>,
             ) -> Result<Value<'host>, Error<'host>> {
@@ -1273,224 +1783,351 @@ impl Program {
         let mut program = Frame {
             bytecode: block(&self.bytes, block_id)?,
             pc: 0,
+            instruction: 0,
         };
 
-        // The program counter reaching the first (and only the first)
-        // out-of-bounds byte should be considered a return.
-        while program.pc != program.bytecode.len() {
-            macro_rules! bi_op {
-                (let $l:ident, $r:ident: $type:ident => $expr_type:ident: $expr:expr) => {{
-                    let $r = program.pop(stack)?;
-                    let $l = program.pop(stack)?;
-                    match (&$l, &$r) {
-                        (Value::$type($l), Value::$type($r)) => {
-                            stack.push(Value::$expr_type($expr))
-                        }
-                        _ => return Err(Error::ExpectedNumbers($l, $r)),
-                    }
-                }};
-            }
-            macro_rules! bi_num {
-                (let $l:ident, $r:ident => $expr:expr) => {
-                    bi_op!(let $l, $r: I64 => I64: $expr)
-                };
-            }
-            macro_rules! bi_cmp {
-                (let $l:ident, $r:ident => $expr:expr) => {
-                    bi_op!(let $l, $r: I64 => Bool: $expr)
-                };
-            }
-            let instruction = program.next()?;
-            match instruction {
-                instruction::CLONE => {
-                    let index = program.next4()? as i32;
-                    match index {
-                        0.. => {
-                            let value = stack
-                                .get(index as usize)
-                                .ok_or(InvalidBytecode::StackOutOfBounds)?;
-                            stack.push(value.clone());
-                        }
-                        builtins::ANY => {
-                            stack.push(Type::Any.into());
+        let mut run = || {
+            // The program counter reaching the first (and only the first)
+            // out-of-bounds byte should be considered a return.
+            while program.pc != program.bytecode.len() {
+                macro_rules! bi_op {
+                    (let $l:ident, $r:ident: $($type:ident => $expr_type:ident: $expr:expr),+) => {{
+                        let $r = program.pop(stack)?;
+                        let $l = program.pop(stack)?;
+                        match (&$l, &$r) {
+                            $(
+                                (Value::$type($l), Value::$type($r)) => {
+                                    stack.push(Value::$expr_type($expr))
+                                }
+                            )+
+                            (Value::I64(_), Value::F64(_)) | (Value::F64(_), Value::I64(_)) => {
+                                return Err(Error::MismatchedNumbers($l, $r))
+                            }
+                            _ => return Err(Error::ExpectedNumbers($l, $r)),
                         }
-                        builtins::UNIT => {
-                            stack.push(Type::Unit.into());
-                        }
-                        builtins::I64 => {
-                            stack.push(Type::I64.into());
+                    }};
+                }
+                macro_rules! bi_num {
+                    (let $l:ident, $r:ident => $expr:expr) => {
+                        bi_op!(let $l, $r: I64 => I64: $expr, F64 => F64: $expr)
+                    };
+                }
+                macro_rules! bi_int {
+                    (let $l:ident, $r:ident => $expr:expr) => {
+                        bi_op!(let $l, $r: I64 => I64: $expr)
+                    };
+                }
+                macro_rules! bi_cmp {
+                    (let $l:ident, $r:ident => $expr:expr) => {
+                        bi_op!(let $l, $r: I64 => Bool: $expr, F64 => Bool: $expr)
+                    };
+                }
+                program.instruction = program.pc;
+                let instruction = program.next()?;
+                match instruction {
+                    instruction::CLONE => {
+                        let index = program.next4()? as i32;
+                        match index {
+                            0.. => {
+                                let value = stack
+                                    .get(index as usize)
+                                    .ok_or(InvalidBytecode::StackOutOfBounds)?;
+                                stack.push(value.clone());
+                            }
+                            builtins::ANY => {
+                                stack.push(Type::Any.into());
+                            }
+                            builtins::UNIT => {
+                                stack.push(Type::Unit.into());
+                            }
+                            builtins::I64 => {
+                                stack.push(Type::I64.into());
+                            }
+                            builtins::F64 => {
+                                stack.push(Type::F64.into());
+                            }
+                            builtins::STRING => {
+                                stack.push(Type::String.into());
+                            }
+                            builtins::BOOL => {
+                                stack.push(Type::Bool.into());
+                            }
+                            builtins::OPTION => {
+                                stack.push(Value::Function(Rc::new(FunctionAction::Option.into())));
+                            }
+                            builtins::MUT => {
+                                stack.push(Value::Function(Rc::new(FunctionAction::Mut.into())));
+                            }
+                            _ => Err(InvalidBytecode::InvalidBuiltin)?,
                         }
-                        builtins::OPTION => {
-                            stack.push(Value::Function(Rc::new(FunctionAction::Option.into())));
+                    }
+                    instruction::POP => {
+                        program.pop(stack)?;
+                    }
+                    instruction::COLLAPSE => {
+                        let value = program.pop(stack)?;
+                        for _ in 0..(stack.len() - program.next4()?) {
+                            stack.pop();
                         }
-                        builtins::MUT => {
-                            stack.push(Value::Function(Rc::new(FunctionAction::Mut.into())));
+                        stack.push(value);
+                    }
+                    instruction::JUMP => {
+                        program.pc = program.next4()?;
+                    }
+                    instruction::IF => {
+                        let target = program.next4()?;
+                        if let Value::Bool(false) = program.pop(stack)? {
+                            program.pc = target;
                         }
-                        _ => Err(InvalidBytecode::InvalidBuiltin)?,
                     }
-                }
-                instruction::POP => {
-                    program.pop(stack)?;
-                }
-                instruction::COLLAPSE => {
-                    let value = program.pop(stack)?;
-                    for _ in 0..(stack.len() - program.next4()?) {
-                        stack.pop();
+                    instruction::NO_MATCH => {
+                        Err(Error::NoMatch(program.pop(stack)?))?;
                     }
-                    stack.push(value);
-                }
-                instruction::JUMP => {
-                    program.pc = program.next4()?;
-                }
-                instruction::IF => {
-                    let target = program.next4()?;
-                    if let Value::Bool(false) = program.pop(stack)? {
-                        program.pc = target;
+
+                    instruction::PUSH_UNIT => {
+                        stack.push(().into());
+                    }
+                    instruction::PUSH_TRUE => {
+                        stack.push(true.into());
+                    }
+                    instruction::PUSH_FALSE => {
+                        stack.push(false.into());
+                    }
+                    instruction::PUSH_I64 => {
+                        stack.push(program.next_i64()?.into());
+                    }
+                    instruction::PUSH_F64 => {
+                        stack.push(program.next_f64()?.into());
+                    }
+                    instruction::PUSH_STRING => {
+                        let string_id = program.next4()?;
+                        let string = self
+                            .owned_strings
+                            .get(string_id)
+                            .ok_or(InvalidBytecode::UnexpectedStringId)?
+                            .clone();
+                        stack.push(string.into());
+                    }
+                    instruction::PUSH_TYPE => {
+                        let type_id = program.next4()?;
+                        let mut encoded: &[u8] = self
+                            .types
+                            .get(type_id)
+                            .ok_or(InvalidBytecode::UnexpectedTypeId)?;
+                        let ComplexType::Simple(ty) =
+                            decode_type(&mut encoded, &self.owned_strings)?
+                        else {
+                            Err(InvalidBytecode::InvalidType)?
+                        };
+                        stack.push(ty.into());
+                    }
+                    instruction::PUSH_FUNCTION => {
+                        let captures = program.next4()?;
+                        let function = program.next4()?;
+                        let output = program.pop(stack)?;
+                        let input = program.pop(stack)?;
+                        let new_stack = stack.split_off(stack.len() - captures);
+                        stack.push(Value::Function(Rc::new(
+                            FunctionAction::With {
+                                program: self.clone(),
+                                signature: FunctionType {
+                                    input: input.try_into()?,
+                                    output: output.try_into()?,
+                                },
+                                block_id: function,
+                                captures: new_stack,
+                            }
+                            .into(),
+                        )));
+                    }
+                    instruction::PUSH_ENUM => {
+                        let variants = program.pop(stack)?;
+                        let Value::Tuple(Tuple(TupleStorage::Named(variants))) = variants else {
+                            Err(Error::ExpectedNamedTuple(variants))?
+                        };
+                        let variants = rc_slice_try_from_iter(
+                            variants.len(),
+                            variants.iter().map(|(name, value)| {
+                                value.clone().try_into().map(|value| (name.clone(), value))
+                            }),
+                        )?;
+                        stack.push(Type::from(EnumType { variants }).into());
                     }
-                }
 
-                instruction::PUSH_UNIT => {
-                    stack.push(().into());
-                }
-                instruction::PUSH_TRUE => {
-                    stack.push(true.into());
-                }
-                instruction::PUSH_FALSE => {
-                    stack.push(false.into());
-                }
-                instruction::PUSH_I64 => {
-                    stack.push(program.next_i64()?.into());
-                }
-                instruction::PUSH_STRING => {
-                    let string_id = program.next4()?;
-                    let string = self
-                        .owned_strings
-                        .get(string_id)
-                        .ok_or(InvalidBytecode::UnexpectedStringId)?
-                        .clone();
-                    stack.push(string.into());
-                }
-                instruction::PUSH_FUNCTION => {
-                    let captures = program.next4()?;
-                    let function = program.next4()?;
-                    let output = program.pop(stack)?;
-                    let input = program.pop(stack)?;
-                    let new_stack = stack.split_off(stack.len() - captures);
-                    stack.push(Value::Function(Rc::new(
-                        FunctionAction::With {
-                            program: self.clone(),
-                            signature: FunctionType {
-                                input: input.try_into()?,
-                                output: output.try_into()?,
-                            },
-                            block_id: function,
-                            captures: new_stack,
-                        }
-                        .into(),
-                    )));
-                }
-                instruction::PUSH_ENUM => {
-                    let variants = program.pop(stack)?;
-                    let Value::Tuple(Tuple(TupleStorage::Named(variants))) = variants else {
-                        Err(Error::ExpectedNamedTuple(variants))?
-                    };
-                    let variants = rc_slice_try_from_iter(
-                        variants.len(),
-                        variants.iter().map(|(name, value)| {
-                            value.clone().try_into().map(|value| (name.clone(), value))
-                        }),
-                    )?;
-                    stack.push(Type::from(EnumType { variants }).into());
-                }
+                    instruction::ADD => bi_num!(let l, r => l + r),
+                    instruction::SUB => bi_num!(let l, r => l - r),
+                    instruction::MUL => bi_num!(let l, r => l * r),
+                    instruction::DIV => bi_num!(let l, r => l / r),
+                    instruction::BITWISE_AND => bi_int!(let l, r => l & r),
+                    instruction::BITWISE_OR => bi_int!(let l, r => l | r),
+                    instruction::BITWISE_XOR => bi_int!(let l, r => l ^ r),
+                    instruction::GREATER => bi_cmp!(let l, r => l > r),
+                    instruction::GREATER_EQUAL => bi_cmp!(let l, r => l >= r),
+                    instruction::LESSER => bi_cmp!(let l, r => l < r),
+                    instruction::LESSER_EQUAL => bi_cmp!(let l, r => l <= r),
+                    instruction::EQUAL_TO => {
+                        let r = program.pop(stack)?;
+                        let l = program.pop(stack)?;
+                        stack.push(l.eq(r)?.into());
+                    }
+                    instruction::NOT_EQUAL_TO => {
+                        let r = program.pop(stack)?;
+                        let l = program.pop(stack)?;
+                        stack.push((!l.eq(r)?).into());
+                    }
+                    instruction::LOGICAL_AND => bi_op!(let l, r: Bool => Bool: *l && *r),
+                    instruction::LOGICAL_OR => bi_op!(let l, r: Bool => Bool: *l || *r),
+                    instruction::PIPE => {
+                        let mut function = Rc::<Function>::try_from(program.pop(stack)?)?;
+                        let argument = program.pop(stack)?;
+                        let function_mut = Rc::make_mut(&mut function);
+                        let mut arguments = ().into();
+                        mem::swap(&mut arguments, &mut function_mut.argument);
+                        arguments = Value::concat(arguments, argument);
+                        mem::swap(&mut arguments, &mut function_mut.argument);
+                        stack.push(Value::Function(function));
+                    }
 
-                instruction::ADD => bi_num!(let l, r => l + r),
-                instruction::SUB => bi_num!(let l, r => l - r),
-                instruction::MUL => bi_num!(let l, r => l * r),
-                instruction::DIV => bi_num!(let l, r => l / r),
-                instruction::BITWISE_AND => bi_num!(let l, r => l & r),
-                instruction::BITWISE_OR => bi_num!(let l, r => l | r),
-                instruction::BITWISE_XOR => bi_num!(let l, r => l ^ r),
-                instruction::GREATER => bi_cmp!(let l, r => l > r),
-                instruction::GREATER_EQUAL => bi_cmp!(let l, r => l >= r),
-                instruction::LESSER => bi_cmp!(let l, r => l < r),
-                instruction::LESSER_EQUAL => bi_cmp!(let l, r => l <= r),
-                instruction::EQUAL_TO => {
-                    let r = program.pop(stack)?;
-                    let l = program.pop(stack)?;
-                    stack.push(l.eq(r)?.into());
-                }
-                instruction::NOT_EQUAL_TO => {
-                    let r = program.pop(stack)?;
-                    let l = program.pop(stack)?;
-                    stack.push((!l.eq(r)?).into());
-                }
-                instruction::LOGICAL_AND => bi_op!(let l, r: Bool => Bool: *l && *r),
-                instruction::LOGICAL_OR => bi_op!(let l, r: Bool => Bool: *l || *r),
-                instruction::PIPE => {
-                    let mut function = Rc::<Function>::try_from(program.pop(stack)?)?;
-                    let argument = program.pop(stack)?;
-                    let function_mut = Rc::make_mut(&mut function);
-                    let mut arguments = ().into();
-                    mem::swap(&mut arguments, &mut function_mut.argument);
-                    arguments = Value::concat(arguments, argument);
-                    mem::swap(&mut arguments, &mut function_mut.argument);
-                    stack.push(Value::Function(function));
-                }
+                    instruction::CALL => {
+                        let argument = program.pop(stack)?;
+                        let function = program.pop(stack)?;
+                        let result = match function {
+                            Value::Function(function) => Rc::<Function>::try_unwrap(function)
+                                .unwrap_or_else(|function| (*function).clone())
+                                .piped(argument)
+                                .eval()?,
+                            function => Err(Error::ExpectedFunction(function))?,
+                        };
+                        stack.push(result);
+                    }
+                    instruction::TUPLE => {
+                        let r = program.pop(stack)?;
+                        let l = program.pop(stack)?;
+                        stack.push(Value::concat(l, r));
+                    }
+                    instruction::INDEX => {
+                        let index = program.pop(stack)?;
+                        let container = program.pop(stack)?;
+                        stack.push(container.index(index)?);
+                    }
+                    instruction::NAME => {
+                        let name_id = program.next4()?;
+                        let name = self
+                            .owned_strings
+                            .get(name_id)
+                            .ok_or(InvalidBytecode::UnexpectedStringId)?
+                            .clone();
+                        let value = program.pop(stack)?;
+                        stack.push(Value::Tuple(Tuple::from([(name, value)])))
+                    }
+                    instruction::NEST => {
+                        let value = program.pop(stack)?;
+                        stack.push(Value::Tuple(Tuple::from([value])))
+                    }
+                    instruction::NEGATIVE => match program.pop(stack)? {
+                        Value::F64(value) => stack.push((-value).into()),
+                        value => stack.push((-value.into_i64()?).into()),
+                    },
+                    instruction::NOT => match program.pop(stack)? {
+                        Value::Bool(value) => stack.push((!value).into()),
+                        Value::I64(value) => stack.push((!value).into()),
+                        value => Err(Error::ExpectedBoolOrInteger(value))?,
+                    },
+                    instruction::DEREF => {
+                        let value = program.pop(stack)?.into_refcell()?;
+                        stack.push(value.try_borrow()?.clone());
+                    }
+                    instruction::SET => {
+                        let value = program.pop(stack)?;
+                        let target = program.pop(stack)?.into_refcell()?;
+                        *target.borrow_mut() = value;
+                    }
+                    instruction::RANGE | instruction::RANGE_INCLUSIVE => {
+                        let end = program.pop(stack)?;
+                        let start = program.pop(stack)?;
+                        let (Value::I64(start), Value::I64(end)) = (&start, &end) else {
+                            return Err(Error::ExpectedNumbers(start, end));
+                        };
+                        let range = Range {
+                            start: *start,
+                            end: *end,
+                            inclusive: instruction == instruction::RANGE_INCLUSIVE,
+                        };
+                        stack.push(range.into_iterator());
+                    }
+                    instruction::SLICE => {
+                        let start = program.pop(stack)?.into_i64()?;
+                        let tuple = program.pop(stack)?;
+                        stack.push(tuple.slice(start)?);
+                    }
+                    instruction::REMOVE => {
+                        let name = program.pop(stack)?.into_str()?;
+                        let tuple = program.pop(stack)?;
+                        stack.push(tuple.remove(&name)?);
+                    }
+                    instruction::CONCAT => {
+                        let count = program.next4()?;
+                        let start = stack
+                            .len()
+                            .checked_sub(count)
+                            .ok_or(InvalidBytecode::StackUnderflow)?;
+                        let mut output = String::new();
+                        for value in stack.drain(start..) {
+                            value.interpolate(&mut output)?;
+                        }
+                        stack.push(Value::String(output.into()));
+                    }
+                    instruction::IS_TYPE => {
+                        let ty = ComplexType::try_from(program.pop(stack)?)?;
+                        let value = program.pop(stack)?;
+                        stack.push(value.type_of()?.compare(&ty).into());
+                    }
+                    instruction::TRY_INDEX => {
+                        let index = program.pop(stack)?;
+                        let container = program.pop(stack)?;
+                        let field = match (&container, index) {
+                            (Value::Tuple(tuple), Value::I64(index)) => usize::try_from(index)
+                                .ok()
+                                .and_then(|index| tuple.value(index)),
+                            (Value::Tuple(tuple), Value::String(name)) => tuple.find_value(&name),
+                            _ => None,
+                        };
+                        if let Some(field) = field {
+                            stack.push(field.clone());
+                            stack.push(true.into());
+                        } else {
+                            stack.push(().into());
+                            stack.push(false.into());
+                        }
+                    }
+                    instruction::MATCHES => {
+                        let case = program.pop(stack)?;
+                        let value = program.pop(stack)?;
+                        if let Some(value) = value.matches(case)? {
+                            stack.push(value);
+                            stack.push(true.into());
+                        } else {
+                            stack.push(().into());
+                            stack.push(false.into());
+                        }
+                    }
 
-                instruction::CALL => {
-                    let argument = program.pop(stack)?;
-                    let function = program.pop(stack)?;
-                    let result = match function {
-                        Value::Function(function) => Rc::<Function>::try_unwrap(function)
-                            .unwrap_or_else(|function| (*function).clone())
-                            .piped(argument)
-                            .eval()?,
-                        function => Err(Error::ExpectedFunction(function))?,
-                    };
-                    stack.push(result);
-                }
-                instruction::TUPLE => {
-                    let r = program.pop(stack)?;
-                    let l = program.pop(stack)?;
-                    stack.push(Value::concat(l, r));
-                }
-                instruction::INDEX => {
-                    let index = program.pop(stack)?;
-                    let container = program.pop(stack)?;
-                    stack.push(container.index(index)?);
-                }
-                instruction::NAME => {
-                    let name_id = program.next4()?;
-                    let name = self
-                        .owned_strings
-                        .get(name_id)
-                        .ok_or(InvalidBytecode::UnexpectedStringId)?
-                        .clone();
-                    let value = program.pop(stack)?;
-                    stack.push(Value::Tuple(Tuple::from([(name, value)])))
-                }
-                instruction::NEST => {
-                    let value = program.pop(stack)?;
-                    stack.push(Value::Tuple(Tuple::from([value])))
-                }
-                instruction::NEGATIVE => {
-                    let value = program.pop(stack)?.into_i64()?;
-                    stack.push((-value).into());
-                }
-                instruction::DEREF => {
-                    let value = program.pop(stack)?.into_refcell()?;
-                    stack.push(value.try_borrow()?.clone());
+                    _ => Err(InvalidBytecode::InvalidInstruction)?,
                 }
-                instruction::SET => {
-                    let value = program.pop(stack)?;
-                    let target = program.pop(stack)?.into_refcell()?;
-                    *target.borrow_mut() = value;
-                }
-
-                _ => Err(InvalidBytecode::InvalidInstruction)?,
             }
-        }
-        program.pop(stack)
+            program.pop(stack)
+        };
+        run().map_err(|error| match error {
+            Error::Located { .. } => error,
+            error => Error::Located {
+                location: Location {
+                    block_id,
+                    pc: program.instruction,
+                    source: self.source_range(block_id, program.instruction),
+                },
+                error: Box::new(error),
+            },
+        })
     }
 }
 
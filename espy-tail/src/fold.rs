@@ -0,0 +1,462 @@
+//! Evaluation of constant expressions at compile time.
+//!
+//! Folding must never change the behavior of a program,
+//! so any operation which would fail (or overflow) at runtime is left for the interpreter.
+
+use crate::{Error, Instruction, Program, Scope, parse_integer, try_validate};
+use espy_ears::{Expression, Node};
+use espy_eyes::{Lexigram, Token};
+use espy_heart::prelude::*;
+use espy_paws::{ComplexType, EnumType, Tuple, Type};
+use std::{borrow::Cow, rc::Rc};
+
+/// A value known at compile time.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Constant<'source> {
+    Unit,
+    Bool(bool),
+    I64(i64),
+    F64(f64),
+    String(Cow<'source, str>),
+    Tuple(Vec<Constant<'source>>),
+    NamedTuple(Vec<(Cow<'source, str>, Constant<'source>)>),
+    /// A builtin type, or an option or enum type built from constant types.
+    Type(Type),
+    /// The builtin `option` function.
+    Option,
+}
+
+impl<'source> Constant<'source> {
+    /// Mirrors the interpreter's tuple concatenation.
+    fn concat(self, r: Self) -> Self {
+        match (self, r) {
+            (Constant::Tuple(mut l), Constant::Tuple(r)) => {
+                l.extend(r);
+                Constant::Tuple(l)
+            }
+            (Constant::NamedTuple(mut l), Constant::NamedTuple(r)) => {
+                l.extend(r);
+                Constant::NamedTuple(l)
+            }
+            (l, Constant::Unit) => l,
+            (Constant::Unit, r) => r,
+            (Constant::Tuple(mut l), r) => {
+                l.push(r);
+                Constant::Tuple(l)
+            }
+            (l, Constant::Tuple(mut r)) => {
+                r.insert(0, l);
+                Constant::Tuple(r)
+            }
+            (l, r) => Constant::Tuple(vec![l, r]),
+        }
+    }
+
+    fn index(&self, index: Token<'source>, source: &'source str) -> Option<Self> {
+        match (self, index.lexigram) {
+            (Constant::Tuple(values), Lexigram::Number) => {
+                let index = usize::try_from(parse_integer(index, source).ok()?).ok()?;
+                values.get(index).cloned()
+            }
+            (Constant::NamedTuple(values), Lexigram::Number) => {
+                let index = usize::try_from(parse_integer(index, source).ok()?).ok()?;
+                values.get(index).map(|(_, value)| value.clone())
+            }
+            (Constant::NamedTuple(values), Lexigram::Ident) => {
+                let name = index.resolve(source).ok()?;
+                values
+                    .iter()
+                    .find_map(|(n, value)| (*n == name).then(|| value.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Mirrors the interpreter's conversion of values into types,
+    /// which accepts types and tuples of them.
+    fn complex_type(&self) -> Option<ComplexType> {
+        Some(match self {
+            Constant::Type(ty) => ty.clone().into(),
+            Constant::Tuple(values) => Tuple::from(
+                values
+                    .iter()
+                    .map(Constant::complex_type)
+                    .collect::<Option<Rc<[_]>>>()?,
+            )
+            .into(),
+            Constant::NamedTuple(values) => Tuple::from(
+                values
+                    .iter()
+                    .map(|(name, value)| Some((Rc::from(&**name), value.complex_type()?)))
+                    .collect::<Option<Rc<[_]>>>()?,
+            )
+            .into(),
+            _ => return None,
+        })
+    }
+
+    /// Mirrors the interpreter's construction of an enum from its variants,
+    /// which must be a named tuple of types.
+    fn enum_type(&self) -> Option<Self> {
+        let Constant::NamedTuple(values) = self else {
+            return None;
+        };
+        let variants = values
+            .iter()
+            .map(|(name, value)| Some((Rc::from(&**name), value.complex_type()?)))
+            .collect::<Option<_>>()?;
+        Some(Constant::Type(Type::from(EnumType { variants })))
+    }
+}
+
+/// The constant value of a builtin, if it has one.
+///
+/// `mut` is excluded because each of its results is a new mutable value.
+fn builtin(index: StackPointer) -> Option<Constant<'static>> {
+    Some(match index {
+        builtins::OPTION => Constant::Option,
+        builtins::ANY => Constant::Type(Type::Any),
+        builtins::UNIT => Constant::Type(Type::Unit),
+        builtins::I64 => Constant::Type(Type::I64),
+        builtins::F64 => Constant::Type(Type::F64),
+        builtins::STRING => Constant::Type(Type::String),
+        builtins::BOOL => Constant::Type(Type::Bool),
+        _ => return None,
+    })
+}
+
+/// Applies a unary operator to a constant, if it would succeed at runtime.
+fn unary<'source>(node: &Node<'source>, operand: &Constant<'source>) -> Option<Constant<'source>> {
+    Some(match (node, operand) {
+        (Node::Negative(_), Constant::I64(i)) => Constant::I64(i.checked_neg()?),
+        (Node::Negative(_), Constant::F64(f)) => Constant::F64(-f),
+        (Node::Not(_), Constant::Bool(b)) => Constant::Bool(!b),
+        (Node::Not(_), Constant::I64(i)) => Constant::I64(!i),
+        _ => return None,
+    })
+}
+
+/// Applies a binary operator to two constants, if it would succeed at runtime.
+fn binary<'source>(
+    node: &Node<'source>,
+    l: &Constant<'source>,
+    r: &Constant<'source>,
+) -> Option<Constant<'source>> {
+    use Constant::{Bool, F64, I64};
+    Some(match (node, l, r) {
+        (Node::Add(_), I64(l), I64(r)) => I64(l.checked_add(*r)?),
+        (Node::Sub(_), I64(l), I64(r)) => I64(l.checked_sub(*r)?),
+        (Node::Mul(_), I64(l), I64(r)) => I64(l.checked_mul(*r)?),
+        (Node::Div(_), I64(l), I64(r)) => I64(l.checked_div(*r)?),
+        (Node::Add(_), F64(l), F64(r)) => F64(l + r),
+        (Node::Sub(_), F64(l), F64(r)) => F64(l - r),
+        (Node::Mul(_), F64(l), F64(r)) => F64(l * r),
+        (Node::Div(_), F64(l), F64(r)) => F64(l / r),
+        (Node::BitwiseAnd(_), I64(l), I64(r)) => I64(l & r),
+        (Node::BitwiseOr(_), I64(l), I64(r)) => I64(l | r),
+        (Node::BitwiseXor(_), I64(l), I64(r)) => I64(l ^ r),
+        (Node::Greater(_), I64(l), I64(r)) => Bool(l > r),
+        (Node::GreaterEqual(_), I64(l), I64(r)) => Bool(l >= r),
+        (Node::Lesser(_), I64(l), I64(r)) => Bool(l < r),
+        (Node::LesserEqual(_), I64(l), I64(r)) => Bool(l <= r),
+        (Node::Greater(_), F64(l), F64(r)) => Bool(l > r),
+        (Node::GreaterEqual(_), F64(l), F64(r)) => Bool(l >= r),
+        (Node::Lesser(_), F64(l), F64(r)) => Bool(l < r),
+        (Node::LesserEqual(_), F64(l), F64(r)) => Bool(l <= r),
+        (Node::LogicalAnd(_), Bool(l), Bool(r)) => Bool(*l && *r),
+        (Node::LogicalOr(_), Bool(l), Bool(r)) => Bool(*l || *r),
+        (Node::EqualTo(_) | Node::NotEqualTo(_), l, r) => {
+            let equal = match (l, r) {
+                (Constant::Unit, Constant::Unit) => true,
+                (Bool(l), Bool(r)) => l == r,
+                (I64(l), I64(r)) => l == r,
+                (F64(l), F64(r)) => l == r,
+                (Constant::String(l), Constant::String(r)) => l == r,
+                _ => return None,
+            };
+            Bool(equal == matches!(node, Node::EqualTo(_)))
+        }
+        (Node::Tuple(_), l, r) => l.clone().concat(r.clone()),
+        (Node::Call(_), Constant::Option, r) => {
+            Constant::Type(Type::Option(Rc::new(r.complex_type()?)))
+        }
+        _ => return None,
+    })
+}
+
+/// A node of a postfix expression, or a constant which replaced a subexpression.
+#[derive(Debug)]
+pub(crate) enum Folded<'source> {
+    Node(Node<'source>),
+    Constant(Constant<'source>),
+    /// An enum whose variants could only be partially folded.
+    Enum {
+        enum_token: Token<'source>,
+        variants: Vec<Folded<'source>>,
+    },
+}
+
+/// Replaces every constant subexpression of a postfix expression with its value.
+///
+/// Variables are only folded if they refer to builtins within `scope`.
+pub(crate) fn fold<'source>(
+    nodes: impl IntoIterator<Item = Node<'source>>,
+    source: &'source str,
+    scope: &Scope<'_, 'source>,
+) -> Result<Vec<Folded<'source>>, Error<'source>> {
+    let mut output = Vec::new();
+    // The position in `output` where each unconsumed operand begins.
+    // An operand is constant if it consists of a single constant.
+    let mut operands: Vec<usize> = Vec::new();
+    let constant = |output: &[Folded<'source>], at: usize| match output.get(at..) {
+        Some([Folded::Constant(constant)]) => Some(constant.clone()),
+        _ => None,
+    };
+    for node in nodes {
+        let node = match node {
+            Node::Enum(enumeration) => {
+                try_validate(enumeration.diagnostics)?;
+                let variants = match enumeration.variants {
+                    Some(variants) => {
+                        let (_first_token, _last_token, diagnostics, nodes) =
+                            Expression::destroy(variants);
+                        try_validate(diagnostics)?;
+                        fold(nodes, source, scope)?
+                    }
+                    None => vec![Folded::Constant(Constant::Unit)],
+                };
+                operands.push(output.len());
+                output.push(match &variants[..] {
+                    [Folded::Constant(variants)] if let Some(ty) = variants.enum_type() => {
+                        Folded::Constant(ty)
+                    }
+                    _ => Folded::Enum {
+                        enum_token: enumeration.enum_token,
+                        variants,
+                    },
+                });
+                continue;
+            }
+            node => node,
+        };
+        let value = match &node {
+            Node::Unit(..) => Some(Constant::Unit),
+            Node::Bool(boolean, _) => Some(Constant::Bool(*boolean)),
+            Node::Number(token) if token.origin(source).contains('.') => Some(Constant::F64(
+                token
+                    .origin(source)
+                    .replace('_', "")
+                    .parse()
+                    .map_err(|e| Error::InvalidFloat(*token, e))?,
+            )),
+            Node::Number(token) => Some(Constant::I64(parse_integer(*token, source)?)),
+            Node::String(token) => Some(Constant::String(
+                token
+                    .resolve(source)
+                    .map_err(|e| Error::InvalidString(*token, e))?,
+            )),
+            Node::Variable(token) => token
+                .resolve(source)
+                .ok()
+                .and_then(|name| scope.get(&name))
+                .and_then(|value| builtin(value.index)),
+            Node::Interpolation(_)
+            | Node::Block { .. }
+            | Node::If(_)
+            | Node::Break(_)
+            | Node::Match(_)
+            | Node::Enum(_) => None,
+            Node::Positive(_)
+            | Node::Negative(_)
+            | Node::Not(_)
+            | Node::Deref(_)
+            | Node::Name { .. }
+            | Node::Field { .. }
+            | Node::Group { .. } => {
+                let start = *operands.last().expect("unary operators have an operand");
+                let Some(operand) = constant(&output, start) else {
+                    output.push(Folded::Node(node));
+                    continue;
+                };
+                let value = match &node {
+                    // These produce no instructions, so the constant may stand in for them.
+                    Node::Positive(_) | Node::Group { .. } => Some(operand),
+                    Node::Name { name, .. } if name.lexigram == Lexigram::Discard => {
+                        Some(Constant::Tuple(vec![operand]))
+                    }
+                    Node::Name { name, .. } => Some(Constant::NamedTuple(vec![(
+                        name.resolve(source)
+                            .map_err(|e| Error::InvalidIdentifier(*name, e))?,
+                        operand,
+                    )])),
+                    Node::Field { index, .. } => operand.index(*index, source),
+                    _ => unary(&node, &operand),
+                };
+                if let Some(value) = value {
+                    output.truncate(start);
+                    output.push(Folded::Constant(value));
+                } else {
+                    output.push(Folded::Node(node));
+                }
+                continue;
+            }
+            _ => {
+                let right = operands.pop().expect("binary operators have two operands");
+                let left = *operands.last().expect("binary operators have two operands");
+                let value = constant(&output[..right], left)
+                    .zip(constant(&output, right))
+                    .and_then(|(l, r)| binary(&node, &l, &r));
+                if let Some(value) = value {
+                    output.truncate(left);
+                    output.push(Folded::Constant(value));
+                } else {
+                    output.push(Folded::Node(node));
+                }
+                continue;
+            }
+        };
+        operands.push(output.len());
+        output.push(match value {
+            Some(value) => Folded::Constant(value),
+            None => Folded::Node(node),
+        });
+    }
+    Ok(output)
+}
+
+impl<'source> Program<'source> {
+    /// Emits the instructions which push `constant` onto the stack.
+    pub(crate) fn add_constant(
+        &mut self,
+        block_id: BlockId,
+        constant: Constant<'source>,
+    ) -> Result<(), Error<'source>> {
+        match constant {
+            Constant::Unit => self.blocks[block_id as usize].extend(Instruction::PushUnit),
+            Constant::Bool(true) => self.blocks[block_id as usize].extend(Instruction::PushTrue),
+            Constant::Bool(false) => self.blocks[block_id as usize].extend(Instruction::PushFalse),
+            Constant::I64(i) => self.blocks[block_id as usize].extend(Instruction::PushI64(i)),
+            Constant::F64(f) => self.blocks[block_id as usize].extend(Instruction::PushF64(f)),
+            Constant::String(s) => {
+                let s = self.create_string(s)?;
+                self.blocks[block_id as usize].extend(Instruction::PushString(s));
+            }
+            Constant::Tuple(values) => {
+                let single = values.len() == 1;
+                for (i, value) in values.into_iter().enumerate() {
+                    // Concatenation would merge these values with their neighbors,
+                    // so they are nested in a tuple of their own first.
+                    let nest = single
+                        || matches!(value, Constant::Unit | Constant::Tuple(_))
+                        || (i == 0 && matches!(value, Constant::NamedTuple(_)));
+                    self.add_constant(block_id, value)?;
+                    if nest {
+                        self.blocks[block_id as usize].extend(Instruction::Nest);
+                    }
+                    if i > 0 {
+                        self.blocks[block_id as usize].extend(Instruction::Tuple);
+                    }
+                }
+            }
+            Constant::NamedTuple(values) => {
+                for (i, (name, value)) in values.into_iter().enumerate() {
+                    self.add_constant(block_id, value)?;
+                    let name = self.create_string(name)?;
+                    self.blocks[block_id as usize].extend(Instruction::Name(name));
+                    if i > 0 {
+                        self.blocks[block_id as usize].extend(Instruction::Tuple);
+                    }
+                }
+            }
+            Constant::Type(ty) => {
+                let instruction = match ty {
+                    Type::Any => Instruction::Clone(builtins::ANY),
+                    Type::Unit => Instruction::Clone(builtins::UNIT),
+                    Type::I64 => Instruction::Clone(builtins::I64),
+                    Type::F64 => Instruction::Clone(builtins::F64),
+                    Type::String => Instruction::Clone(builtins::STRING),
+                    Type::Bool => Instruction::Clone(builtins::BOOL),
+                    ty => {
+                        let mut encoded = Vec::new();
+                        self.encode_type(&ty.into(), &mut encoded)?;
+                        Instruction::PushType(self.create_type(encoded)?)
+                    }
+                };
+                self.blocks[block_id as usize].extend(instruction);
+            }
+            Constant::Option => {
+                self.blocks[block_id as usize].extend(Instruction::Clone(builtins::OPTION))
+            }
+        }
+        Ok(())
+    }
+
+    fn create_type(&mut self, encoded: Vec<u8>) -> Result<TypeId, Error<'source>> {
+        if let Some(i) = self.types.iter().position(|x| *x == encoded) {
+            i
+        } else {
+            self.types.push(encoded);
+            self.types.len() - 1
+        }
+        .try_into()
+        .map_err(|_| Error::ProgramLimitExceeded)
+    }
+
+    /// Appends `ty` to `output` as described by [`type_tags`].
+    fn encode_type(
+        &mut self,
+        ty: &ComplexType,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error<'source>> {
+        let count = |len: usize| {
+            u32::try_from(len)
+                .map(u32::to_le_bytes)
+                .map_err(|_| Error::ProgramLimitExceeded)
+        };
+        match ty {
+            ComplexType::Simple(ty) => match ty {
+                Type::Any => output.push(type_tags::ANY),
+                Type::Unit => output.push(type_tags::UNIT),
+                Type::I64 => output.push(type_tags::I64),
+                Type::F64 => output.push(type_tags::F64),
+                Type::String => output.push(type_tags::STRING),
+                Type::Bool => output.push(type_tags::BOOL),
+                Type::Option(contents) => {
+                    output.push(type_tags::OPTION);
+                    self.encode_type(contents, output)?;
+                }
+                Type::Enum(enum_type) => {
+                    output.push(type_tags::ENUM);
+                    output.extend(count(enum_type.variants.len())?);
+                    for (name, variant) in enum_type.variants.iter() {
+                        let name = self.create_string(name.to_string())?;
+                        output.extend(name.to_le_bytes());
+                        self.encode_type(variant, output)?;
+                    }
+                }
+                Type::Function(_) | Type::Mut(_) | Type::Range | Type::Type => {
+                    unreachable!("only builtin types, options, and enums are folded")
+                }
+            },
+            ComplexType::Complex(tuple) => match tuple.names() {
+                Some(names) => {
+                    output.push(type_tags::NAMED_TUPLE);
+                    output.extend(count(tuple.len())?);
+                    for (name, value) in names.zip(tuple.values()) {
+                        let name = self.create_string(name.to_string())?;
+                        output.extend(name.to_le_bytes());
+                        self.encode_type(value, output)?;
+                    }
+                }
+                None => {
+                    output.push(type_tags::TUPLE);
+                    output.extend(count(tuple.len())?);
+                    for value in tuple.values() {
+                        self.encode_type(value, output)?;
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+}
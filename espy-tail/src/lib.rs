@@ -5,22 +5,33 @@
 //! use espy_ears::Block;
 //! use espy_tail::Program;
 //!
-//! let mut lexer = Lexer::from("1 + 2").peekable();
-//! let block = Block::new(&mut lexer);
-//! let program = Program::try_from(block).unwrap();
+//! let source = "1 + 2";
+//! let mut lexer = Lexer::from(source).peekable();
+//! let block = Block::program(&mut lexer);
+//! let program = Program::new(source, block).unwrap();
 //! let bytecode = program.compile();
 //! ```
 
 use espy_ears::{
-    Binding, BindingMethod, Block, BlockResult, Diagnostics, Evaluation, Expression, Node, Set,
-    Statement,
+    Binding, BindingMethod, Block, BlockResult, Diagnostics, Evaluation, Expression, Interpolation,
+    LetElse, Match, Node, Set, Statement, VariantBinding,
 };
-use espy_eyes::{Lexigram, Token};
+use espy_eyes::{DocComments, Lexigram, SourceFile, Span, Token};
 use espy_heart::prelude::*;
-use std::{borrow::Cow, iter, mem, num::ParseIntError};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    iter, mem,
+    num::{ParseFloatError, ParseIntError},
+};
 
+mod fold;
 #[cfg(test)]
 mod tests;
+pub mod types;
+
+use fold::Folded;
 
 #[derive(Debug)]
 pub enum Error<'source> {
@@ -28,8 +39,12 @@ pub enum Error<'source> {
     ProgramLimitExceeded,
     /// Attempted to break out of a scope, but no parent scope accepted unlabeled breaks.
     InvalidBreak(Token<'source>),
+    /// Attempted to break out of a labeled block, but no parent scope had that label.
+    UndefinedLabel(Token<'source>),
     /// The AST contained an integer that did not fit into the expected type.
     InvalidInteger(Token<'source>, ParseIntError),
+    /// The AST contained a floating point number that could not be parsed.
+    InvalidFloat(Token<'source>, ParseFloatError),
     /// The AST contained a string with an invalid escape sequence.
     InvalidString(Token<'source>, espy_eyes::EscapeError),
     /// The AST contained an identifier with an invalid escape sequence.
@@ -44,6 +59,119 @@ pub enum Error<'source> {
     InvalidAst(espy_ears::Error<'source>),
 }
 
+/// For retroactively filling in the destinations of jump instructions.
+///
+/// `at` should point to the program counter operand of the jump,
+/// which will be set to the current end of the block.
+fn fill(block: &mut [u8], at: usize) {
+    let pc = block.len() as ProgramCounter;
+    block[at..(at + size_of::<ProgramCounter>())].copy_from_slice(&pc.to_le_bytes());
+}
+
+/// Finds the right operands of short-circuiting operators.
+///
+/// For each node of a (postfix) expression, returns the instruction of the logical operator
+/// whose right operand begins at that node, if any.
+fn short_circuits(nodes: &[Folded]) -> Vec<Option<Instruction>> {
+    let mut short_circuits: Vec<Option<Instruction>> = iter::repeat_n(None, nodes.len()).collect();
+    // The index of the first node of each operand that has not yet been consumed by an operator.
+    let mut starts = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let node = match node {
+            Folded::Node(node) => node,
+            Folded::Constant(_) | Folded::Enum { .. } => {
+                starts.push(i);
+                continue;
+            }
+        };
+        match node {
+            Node::Unit(..)
+            | Node::Bool(..)
+            | Node::Number(_)
+            | Node::String(_)
+            | Node::Interpolation(_)
+            | Node::Variable(_)
+            | Node::Block { .. }
+            | Node::If(_)
+            | Node::Break(_)
+            | Node::Match(_)
+            | Node::Enum(_) => starts.push(i),
+            Node::Positive(_)
+            | Node::Negative(_)
+            | Node::Not(_)
+            | Node::Deref(_)
+            | Node::Name { .. }
+            | Node::Field { .. }
+            | Node::Group { .. } => {}
+            _ => {
+                let right = starts.pop().expect("binary operators have two operands");
+                match node {
+                    Node::LogicalAnd(_) => short_circuits[right] = Some(Instruction::LogicalAnd),
+                    Node::LogicalOr(_) => short_circuits[right] = Some(Instruction::LogicalOr),
+                    _ => {}
+                }
+            }
+        }
+    }
+    short_circuits
+}
+
+/// Returns the token which best describes where a node's instructions came from,
+/// or `None` if the node produces no instructions of its own.
+fn node_token<'source>(node: &Node<'source>) -> Option<Token<'source>> {
+    match node {
+        Node::Positive(_) | Node::Group { .. } => None,
+        Node::Unit(token, _)
+        | Node::Bool(_, token)
+        | Node::Number(token)
+        | Node::String(token)
+        | Node::Variable(token)
+        | Node::Pipe(token)
+        | Node::Call(token)
+        | Node::Negative(token)
+        | Node::Not(token)
+        | Node::Deref(token)
+        | Node::Mul(token)
+        | Node::Div(token)
+        | Node::Add(token)
+        | Node::Sub(token)
+        | Node::BitwiseAnd(token)
+        | Node::BitwiseOr(token)
+        | Node::BitwiseXor(token)
+        | Node::EqualTo(token)
+        | Node::NotEqualTo(token)
+        | Node::Greater(token)
+        | Node::GreaterEqual(token)
+        | Node::Lesser(token)
+        | Node::LesserEqual(token)
+        | Node::LogicalAnd(token)
+        | Node::LogicalOr(token)
+        | Node::Range(token)
+        | Node::RangeInclusive(token)
+        | Node::Tuple(token)
+        | Node::Name { name: token, .. }
+        | Node::Field { index: token, .. } => Some(*token),
+        Node::Interpolation(interpolation) => Some(interpolation.start),
+        Node::Block { open_brace, .. } => Some(*open_brace),
+        Node::If(if_block) => Some(if_block.if_token),
+        Node::Break(break_node) => Some(break_node.break_token),
+        Node::Match(match_block) => Some(match_block.match_token),
+        Node::Enum(enumeration) => Some(enumeration.enum_token),
+    }
+}
+
+/// Parses an integer literal, respecting radix prefixes and `_` separators.
+fn parse_integer<'source>(token: Token<'source>, source: &str) -> Result<i64, Error<'source>> {
+    let digits = token.origin(source).replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, &*digits),
+    };
+    i64::from_str_radix(digits, radix).map_err(|e| Error::InvalidInteger(token, e))
+}
+
 fn try_validate(diagnostics: Diagnostics) -> Result<(), Error> {
     if let Some(error) = diagnostics.errors.into_iter().next() {
         Err(Error::InvalidAst(error))
@@ -55,7 +183,7 @@ fn try_validate(diagnostics: Diagnostics) -> Result<(), Error> {
 // These are practically used as functions which return iterators over bytes at this point.
 // There isn't a good reason for this other than that they used to be stored for a little while,
 // so if true functions make more sense for any reason this type can be removed.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Instruction {
     /// Copy a value from the given position and put it on the top of the stack.
     Clone(StackPointer),
@@ -69,9 +197,12 @@ pub enum Instruction {
     /// Pop a boolean value off the stack.
     /// If it is *false*, set the program counter.
     If(ProgramCounter),
+    /// Pop a value off the stack and fail because no match case accepted it.
+    NoMatch,
 
     PushUnit,
     PushI64(i64),
+    PushF64(f64),
     /// Pop the top value off the stack and use it as the return type of the function.
     ///
     /// Pop the next value off the stack and use it as the input type of the function.
@@ -91,6 +222,8 @@ pub enum Instruction {
     /// Push the resulting enum type to the stack.
     PushEnum,
     PushString(StringId),
+    /// Push a type which was built at compile time, by its position in the types metadata section.
+    PushType(TypeId),
 
     Add,
     Sub,
@@ -114,6 +247,9 @@ pub enum Instruction {
     /// Pop a value off the stack and turn it into a numeric tuple with a single value.
     Nest,
     Negative,
+    /// Pop a value off the stack and push its logical negation if it is a boolean,
+    /// or its bitwise complement if it is an integer.
+    Not,
     Pipe,
     BitwiseAnd,
     BitwiseOr,
@@ -128,6 +264,41 @@ pub enum Instruction {
     LogicalOr,
     Deref,
     Set,
+    /// Pop the first value off the stack (the case), then pop the second (the value being matched).
+    ///
+    /// Push the value to be bound by the case,
+    /// followed by a boolean which is *true* if the case accepted the value.
+    /// Enum variant constructors accept their variant and bind its contents;
+    /// all other cases are compared for equality and bind the value itself.
+    Matches,
+    /// Pop two integers off the stack and push an iterator over the range between them,
+    /// excluding the topmost value.
+    ///
+    /// The iterator is a tuple of the range and a function which advances it.
+    Range,
+    /// Identical to [`Instruction::Range`], but includes the topmost value.
+    RangeInclusive,
+    /// Pop an integer off the stack, then pop a tuple and push its values from that index onward.
+    ///
+    /// Names are preserved. An empty result is unit.
+    Slice,
+    /// Pop a string off the stack, then pop a named tuple and push it without the field of that name.
+    ///
+    /// An empty result is unit.
+    Remove,
+    /// Pop the given number of values off the stack and push a string of them concatenated,
+    /// with the topmost value last.
+    ///
+    /// Values other than strings are converted to strings first.
+    Concat(u32),
+    /// Pop a type off the stack, then pop a value and push *true* if it is of that type.
+    IsType,
+    /// Identical to [`Instruction::Index`], but only indexes tuples,
+    /// and pushes a boolean after the result which is *false* (in place of failing)
+    /// if the tuple has no such field.
+    ///
+    /// The result is unit if the boolean is *false*.
+    TryIndex,
 }
 
 pub struct InstructionIter {
@@ -156,9 +327,11 @@ impl Iterator for InstructionIter {
             Instruction::Collapse(to) => decompose!(instruction::COLLAPSE, to as 1..=4),
             Instruction::Jump(pc) => decompose!(instruction::JUMP, pc as 1..=4),
             Instruction::If(pc) => decompose!(instruction::IF, pc as 1..=4),
+            Instruction::NoMatch => decompose!(instruction::NO_MATCH,),
 
             Instruction::PushUnit => decompose!(instruction::PUSH_UNIT,),
             Instruction::PushI64(literal) => decompose!(instruction::PUSH_I64, literal as 1..=8),
+            Instruction::PushF64(literal) => decompose!(instruction::PUSH_F64, literal as 1..=8),
             Instruction::PushFunction { captures, function } => {
                 decompose!(instruction::PUSH_FUNCTION, captures as 1..=4, function as 5..=8)
             }
@@ -166,6 +339,7 @@ impl Iterator for InstructionIter {
             Instruction::PushFalse => decompose!(instruction::PUSH_FALSE,),
             Instruction::PushEnum => decompose!(instruction::PUSH_ENUM,),
             Instruction::PushString(s) => decompose!(instruction::PUSH_STRING, s as 1..=4),
+            Instruction::PushType(t) => decompose!(instruction::PUSH_TYPE, t as 1..=4),
 
             Instruction::Add => decompose!(instruction::ADD,),
             Instruction::Sub => decompose!(instruction::SUB,),
@@ -177,6 +351,7 @@ impl Iterator for InstructionIter {
             Instruction::Name(name) => decompose!(instruction::NAME, name as 1..=4),
             Instruction::Nest => decompose!(instruction::NEST,),
             Instruction::Negative => decompose!(instruction::NEGATIVE,),
+            Instruction::Not => decompose!(instruction::NOT,),
             Instruction::Pipe => decompose!(instruction::PIPE,),
             Instruction::BitwiseAnd => decompose!(instruction::BITWISE_AND,),
             Instruction::BitwiseOr => decompose!(instruction::BITWISE_OR,),
@@ -191,6 +366,14 @@ impl Iterator for InstructionIter {
             Instruction::LogicalOr => decompose!(instruction::LOGICAL_OR,),
             Instruction::Deref => decompose!(instruction::DEREF,),
             Instruction::Set => decompose!(instruction::SET,),
+            Instruction::Matches => decompose!(instruction::MATCHES,),
+            Instruction::Range => decompose!(instruction::RANGE,),
+            Instruction::RangeInclusive => decompose!(instruction::RANGE_INCLUSIVE,),
+            Instruction::Slice => decompose!(instruction::SLICE,),
+            Instruction::Remove => decompose!(instruction::REMOVE,),
+            Instruction::Concat(count) => decompose!(instruction::CONCAT, count as 1..=4),
+            Instruction::IsType => decompose!(instruction::IS_TYPE,),
+            Instruction::TryIndex => decompose!(instruction::TRY_INDEX,),
         };
         self.index += 1;
         Some(byte)
@@ -210,15 +393,125 @@ impl IntoIterator for Instruction {
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Program<'source> {
+    /// The source code that the program's tokens refer to.
+    source: &'source str,
+    options: Options,
     blocks: Vec<Vec<u8>>,
     strings: Vec<Cow<'source, str>>,
+    /// Types built by constant folding, each encoded for the types metadata section.
+    types: Vec<Vec<u8>>,
+    doc_comments: Vec<(BlockId, StringId)>,
+    /// The doc comments of the source, which are only found if they are to be included.
+    docs: DocComments,
+    source_map: Vec<(BlockId, ProgramCounter, Span)>,
+    /// The positions of named fields which the type checker has proven, by the span of their name.
+    field_positions: HashMap<Span, usize>,
+}
+
+/// Settings which affect the output of the compiler but not the behavior of the program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+    /// Include the doc comments of functions in a metadata section.
+    ///
+    /// A function's doc comment is the one preceding its `with`,
+    /// or the `let` which binds it if the function has none.
+    pub doc_comments: bool,
+    /// Include a metadata section which maps instructions to the source code that produced them.
+    ///
+    /// Interpreters use this to report where runtime errors occurred.
+    pub source_map: bool,
+    /// Evaluate expressions made up only of literals, operators, and builtin types at compile time,
+    /// such as `60 * 60 * 24` or `option i64`, and emit their results in place of the operations.
+    ///
+    /// Types built this way (such as options and enums) are stored in a metadata section.
+    ///
+    /// Enabled by default.
+    pub fold_constants: bool,
+    /// Index the fields of named tuples (and the variants of enums and options) by position
+    /// wherever the [type checker](types) can prove the position of a name,
+    /// rather than searching for the name at runtime.
+    ///
+    /// This requires checking the program's types before compiling it.
+    pub index_fields: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            doc_comments: false,
+            source_map: false,
+            fold_constants: true,
+            index_fields: false,
+        }
+    }
 }
 
 impl<'source> Program<'source> {
+    /// Compiles a block parsed from `source` into a program.
+    pub fn new(source: &'source str, block: Box<Block<'source>>) -> Result<Self, Error<'source>> {
+        Self::with_options(source, block, Options::default())
+    }
+
+    /// Compiles a block parsed from `source` into a program, according to `options`.
+    pub fn with_options(
+        source: &'source str,
+        block: Box<Block<'source>>,
+        options: Options,
+    ) -> Result<Self, Error<'source>> {
+        let mut this = Self {
+            source,
+            options,
+            ..Default::default()
+        };
+        if options.doc_comments {
+            this.docs = DocComments::new(source);
+        }
+        if options.index_fields {
+            this.field_positions = types::check(source, &block)
+                .fields
+                .into_iter()
+                .map(|(token, position)| (token.span, position))
+                .collect();
+        }
+        let block_id = this.create_block()?;
+        this.add_block(block_id, block, Scope::default())?;
+        Ok(this)
+    }
+
     pub fn compile(self) -> Vec<u8> {
+        let mut metadata = Vec::new();
+        if !self.types.is_empty() {
+            let mut section = vec![metadata::TYPES];
+            section.extend(self.types.concat());
+            metadata.push(section);
+        }
+        if !self.doc_comments.is_empty() {
+            let mut section = vec![metadata::DOC_COMMENTS];
+            for (block_id, string_id) in self.doc_comments {
+                section.extend(block_id.to_le_bytes());
+                section.extend(string_id.to_le_bytes());
+            }
+            metadata.push(section);
+        }
+        if !self.source_map.is_empty() {
+            let source = SourceFile::new(self.source);
+            let mut section = vec![metadata::SOURCE_MAP];
+            for (block_id, pc, span) in self.source_map {
+                let (line, column) = source.location(span.start);
+                section.extend(block_id.to_le_bytes());
+                section.extend(pc.to_le_bytes());
+                section.extend(span.start.to_le_bytes());
+                section.extend(span.end().to_le_bytes());
+                section.extend((line as u32).to_le_bytes());
+                section.extend((column as u32).to_le_bytes());
+            }
+            metadata.push(section);
+        }
+
         let mut output = Vec::new();
         output.extend((self.blocks.len() as u32).to_le_bytes());
         output.extend((self.strings.len() as u32).to_le_bytes());
+        output.extend((metadata.len() as u32).to_le_bytes());
         // Reserve space for vector offsets.
         // Blocks, strings, and string sets are only referred to by index,
         // so this is the only program-wide retroactive filling required.
@@ -227,6 +520,9 @@ impl<'source> Program<'source> {
         // same for strings.
         let string_offsets = output.len();
         output.extend(iter::repeat_n(0, self.strings.len() * size_of::<u32>()));
+        // and metadata sections.
+        let metadata_offsets = output.len();
+        output.extend(iter::repeat_n(0, metadata.len() * size_of::<u32>()));
 
         // Fill in offsets.
         for (block_id, block) in self.blocks.into_iter().enumerate() {
@@ -241,6 +537,12 @@ impl<'source> Program<'source> {
             output[dest..(dest + size_of::<u32>())].copy_from_slice(&src.to_le_bytes());
             output.extend(string.bytes());
         }
+        for (section_id, section) in metadata.into_iter().enumerate() {
+            let src = output.len() as u32;
+            let dest = metadata_offsets + section_id * size_of::<u32>();
+            output[dest..(dest + size_of::<u32>())].copy_from_slice(&src.to_le_bytes());
+            output.extend(section);
+        }
         output
     }
 
@@ -266,6 +568,14 @@ impl<'source> Program<'source> {
         .map_err(|_| Error::ProgramLimitExceeded)
     }
 
+    /// Records that the instructions about to be added to a block were produced by `span`.
+    fn locate(&mut self, block_id: BlockId, span: Span) {
+        if self.options.source_map {
+            let pc = self.blocks[block_id as usize].len() as ProgramCounter;
+            self.source_map.push((block_id, pc, span));
+        }
+    }
+
     fn add_block(
         &mut self,
         block_id: BlockId,
@@ -292,7 +602,7 @@ impl<'source> Program<'source> {
         // We have to check this here because BlockResult::Function is about to move the scope,
         // but the instruction is not emitted until after the result is pushed to the stack.
         // This is fine because block results must push one and only one value.
-        let collapse_point = scope
+        let mut collapse_point = scope
             .parent
             .filter(|parent| parent.stack_pointer < scope.stack_pointer)
             .map(|parent| parent.stack_pointer);
@@ -319,10 +629,19 @@ impl<'source> Program<'source> {
                 let mut scope = scope.promote();
                 let captures = scope.stack_pointer;
                 let function_id = self.create_block()?;
+                if let Some(doc_comment) = self.docs.text(function.with_token, self.source) {
+                    let string_id = self.create_string(doc_comment)?;
+                    self.doc_comments.push((function_id, string_id));
+                }
                 // to be filled in by the argument (which is about to be bound)
                 scope.stack_pointer += 1;
                 if let Some(argument) = function.argument {
-                    self.add_binding(function_id, argument, &mut scope)?;
+                    let refuted = if argument.is_refutable() {
+                        Some(self.add_refutation(function_id, None, &mut scope)?)
+                    } else {
+                        None
+                    };
+                    self.add_binding(function_id, argument, &mut scope, refuted)?;
                 }
                 self.add_block(function_id, function.block, scope)?;
                 self.blocks[block_id as usize].extend(Instruction::PushFunction {
@@ -331,17 +650,112 @@ impl<'source> Program<'source> {
                 })
             }
         }
+        // Early exits arrive with their result on top of the stack,
+        // and possibly more values than the result above the collapse point.
+        let exits = mem::take(scope.exits.get_mut());
+        if !exits.is_empty() {
+            collapse_point = scope.parent.map(|parent| parent.stack_pointer);
+        }
+        for exit in exits {
+            fill(&mut self.blocks[block_id as usize], exit);
+        }
         if let Some(collapse_point) = collapse_point {
             self.blocks[block_id as usize].extend(Instruction::Collapse(collapse_point));
         }
         Ok(())
     }
 
+    /// Emits the code which runs when the binding of the value on top of the stack is refuted,
+    /// and returns its address.
+    ///
+    /// The code is skipped over until a refutable binding jumps to it.
+    /// It either evaluates `else_branch` and exits the block,
+    /// or fails with the value which was being bound.
+    fn add_refutation(
+        &mut self,
+        block_id: BlockId,
+        else_branch: Option<Box<LetElse<'source>>>,
+        scope: &mut Scope<'_, 'source>,
+    ) -> Result<ProgramCounter, Error<'source>> {
+        macro_rules! block {
+            () => {
+                self.blocks[block_id as usize]
+            };
+        }
+        let subject = scope.stack_pointer - 1;
+        block!().extend(Instruction::Jump(0));
+        let skip_destination = block!().len() - size_of::<ProgramCounter>();
+        let refuted = block!().len() as ProgramCounter;
+        // Matches leaves a unit in place of the refuted value,
+        // above anything that was bound before the refutation.
+        block!().extend(Instruction::Collapse(subject + 1));
+        block!().extend(Instruction::Pop);
+        if let Some(else_branch) = else_branch {
+            self.add_block(block_id, else_branch.block, scope.child())?;
+            block!().extend(Instruction::Jump(0));
+            scope
+                .exits
+                .get_mut()
+                .push(block!().len() - size_of::<ProgramCounter>());
+        } else {
+            block!().extend(Instruction::NoMatch);
+        }
+        fill(&mut block!(), skip_destination);
+        Ok(refuted)
+    }
+
+    /// Matches a clone of the value at `root` against the value of `case`,
+    /// jumping to `refuted` if they do not match.
+    ///
+    /// If they do, the matched value (such as the contents of an enum variant)
+    /// is left on top of the stack.
+    fn add_case(
+        &mut self,
+        block_id: BlockId,
+        root: StackPointer,
+        case: Vec<Node<'source>>,
+        refuted: Option<ProgramCounter>,
+        scope: &mut Scope<'_, 'source>,
+    ) -> Result<(), Error<'source>> {
+        let refuted =
+            refuted.expect("refutable bindings must be given a destination for when they fail");
+        self.blocks[block_id as usize].extend(Instruction::Clone(root));
+        scope.stack_pointer += 1;
+        let case = Expression::build(None, None, Diagnostics::default(), case);
+        self.add_expression(block_id, Some(case), scope)?;
+        // Matches replaces the value and case with a value and a boolean,
+        // and If pops the boolean.
+        self.blocks[block_id as usize].extend(Instruction::Matches);
+        self.blocks[block_id as usize].extend(Instruction::If(refuted));
+        scope.stack_pointer -= 1;
+        Ok(())
+    }
+
+    /// Indexes the container beneath the index on top of the stack.
+    ///
+    /// If the binding is refutable, a container without the field jumps to `refuted`
+    /// rather than failing.
+    fn add_field(&mut self, block_id: BlockId, refuted: Option<ProgramCounter>) {
+        let block = &mut self.blocks[block_id as usize];
+        if let Some(refuted) = refuted {
+            // TryIndex leaves a unit in place of a missing field, like Matches.
+            block.extend(Instruction::TryIndex);
+            block.extend(Instruction::If(refuted));
+        } else {
+            block.extend(Instruction::Index);
+        }
+    }
+
+    /// Binds the value on top of the stack.
+    ///
+    /// `refuted` must be provided if the binding is refutable,
+    /// and is jumped to if the value does not match it.
     fn add_binding(
         &mut self,
         block_id: BlockId,
         binding: Binding<'source>,
         scope: &mut Scope<'_, 'source>,
+        refuted: Option<ProgramCounter>,
     ) -> Result<(), Error<'source>> {
         macro_rules! block {
             () => {
@@ -354,44 +768,94 @@ impl<'source> Program<'source> {
             BindingMethod::Single(token) => match token.lexigram {
                 Lexigram::Ident => scope.insert(
                     token
-                        .resolve()
+                        .resolve(self.source)
                         .map_err(|e| Error::InvalidIdentifier(token, e))?,
                 ),
                 Lexigram::Discard => {}
                 _ => unreachable!("only idents and discards are valid bindings"),
             },
-            BindingMethod::Numeric { bindings, .. } => {
+            BindingMethod::Literal(token) => {
+                let case = match token.lexigram {
+                    Lexigram::Number => Node::Number(token),
+                    Lexigram::String => Node::String(token),
+                    Lexigram::True => Node::Bool(true, token),
+                    Lexigram::False => Node::Bool(false, token),
+                    _ => unreachable!("only numbers, strings, and booleans are valid literals"),
+                };
+                self.add_case(block_id, root, vec![case], refuted, scope)?;
+                // The value is already bound to root, so the matched copy is not needed.
+                block!().extend(Instruction::Pop);
+                scope.stack_pointer -= 1;
+            }
+            BindingMethod::Variant(variant) => {
+                let VariantBinding {
+                    constructor,
+                    fields,
+                    binding,
+                } = *variant;
+                let case = iter::once(Node::Variable(constructor))
+                    .chain(fields.into_iter().map(|field| Node::Field {
+                        dot_token: field.dot_token,
+                        index: field.field,
+                    }))
+                    .collect();
+                self.add_case(block_id, root, case, refuted, scope)?;
+                // The variant's contents are left unbound if there is no binding.
+                if let Some(binding) = binding {
+                    self.add_binding(block_id, binding, scope, refuted)?;
+                }
+            }
+            BindingMethod::Numeric { bindings, rest, .. } => {
+                let count = bindings.len();
                 for (i, binding) in bindings.into_iter().enumerate() {
                     block!().extend(Instruction::Clone(root));
                     block!().extend(Instruction::PushI64(i as i64));
-                    block!().extend(Instruction::Index);
+                    self.add_field(block_id, refuted);
                     scope.stack_pointer += 1;
-                    self.add_binding(block_id, binding.binding, scope)?;
+                    self.add_binding(block_id, binding.binding, scope, refuted)?;
+                }
+                if let Some(rest) = rest {
+                    block!().extend(Instruction::Clone(root));
+                    block!().extend(Instruction::PushI64(count as i64));
+                    block!().extend(Instruction::Slice);
+                    scope.stack_pointer += 1;
+                    self.add_binding(block_id, rest.binding, scope, refuted)?;
                 }
             }
-            BindingMethod::Named { bindings, .. } => {
+            BindingMethod::Named { bindings, rest, .. } => {
+                let mut fields = Vec::with_capacity(bindings.len());
                 for binding in bindings {
                     block!().extend(Instruction::Clone(root));
                     let s = self.create_string(
                         binding
                             .field
-                            .resolve()
+                            .resolve(self.source)
                             .map_err(|e| Error::InvalidIdentifier(binding.field, e))?,
                     )?;
+                    fields.push(s);
                     block!().extend(Instruction::PushString(s));
-                    block!().extend(Instruction::Index);
+                    self.add_field(block_id, refuted);
                     scope.stack_pointer += 1;
                     if let Some(sub_binding) = binding.binding {
-                        self.add_binding(block_id, sub_binding.binding, scope)?;
+                        self.add_binding(block_id, sub_binding.binding, scope, refuted)?;
                     } else {
                         scope.insert(
                             binding
                                 .field
-                                .resolve()
+                                .resolve(self.source)
                                 .map_err(|e| Error::InvalidIdentifier(binding.field, e))?,
                         );
                     }
                 }
+                if let Some(rest) = rest {
+                    block!().extend(Instruction::Clone(root));
+                    for s in fields {
+                        block!().extend(Instruction::PushString(s));
+                        block!().extend(Instruction::Remove);
+                    }
+                    scope.stack_pointer += 1;
+                    self.add_binding(block_id, rest.binding, scope, refuted)?;
+                }
             }
         }
         Ok(())
@@ -416,10 +880,22 @@ impl<'source> Program<'source> {
             Statement::Evaluation(Evaluation {
                 binding,
                 expression,
+                else_branch,
                 diagnostics,
                 ..
             }) => {
                 try_validate(diagnostics)?;
+                // A function bound by `let` inherits the binding's doc comment.
+                if let Some(doc_comment) = binding
+                    .as_ref()
+                    .and_then(|binding| binding.doc_comment(&self.docs))
+                    && let Some(expression) = &expression
+                    && let [Node::Block { block, .. }] = &expression.contents
+                    && let BlockResult::Function(function) = &block.result
+                    && function.doc_comment(&self.docs).is_none()
+                {
+                    self.docs.insert(function.with_token, doc_comment);
+                }
                 if let Some(expression) = expression {
                     self.add_expression(block_id, expression, scope)?;
                 } else {
@@ -431,7 +907,13 @@ impl<'source> Program<'source> {
                     let binding = binding
                         .binding
                         .expect("valid statement structures always have bindings");
-                    self.add_binding(block_id, binding, scope)?;
+                    // An else branch is never taken if the binding cannot be refuted.
+                    let refuted = if binding.is_refutable() {
+                        Some(self.add_refutation(block_id, else_branch, scope)?)
+                    } else {
+                        None
+                    };
+                    self.add_binding(block_id, binding, scope, refuted)?;
                 } else {
                     block!().extend(Instruction::Pop);
                     scope.stack_pointer -= 1;
@@ -458,6 +940,28 @@ impl<'source> Program<'source> {
         block_id: BlockId,
         expression: impl Into<Option<Box<Expression<'source>>>>,
         scope: &mut Scope<'_, 'source>,
+    ) -> Result<(), Error<'source>> {
+        let Some(expression) = expression.into() else {
+            scope.stack_pointer += 1;
+            self.blocks[block_id as usize].extend(Instruction::PushUnit);
+            return Ok(());
+        };
+        let (_first_token, _last_token, diagnostics, nodes) = Expression::destroy(expression);
+        try_validate(diagnostics)?;
+        let nodes = if self.options.fold_constants {
+            fold::fold(nodes, self.source, scope)?
+        } else {
+            nodes.map(Folded::Node).collect()
+        };
+        self.add_folded(block_id, nodes, scope)
+    }
+
+    /// Emits the instructions of a (possibly folded) postfix expression.
+    fn add_folded(
+        &mut self,
+        block_id: BlockId,
+        nodes: Vec<Folded<'source>>,
+        scope: &mut Scope<'_, 'source>,
     ) -> Result<(), Error<'source>> {
         // Shortcut for re-indexing self.blocks.
         // This is necessary because add_block etc take &mut self.
@@ -472,24 +976,77 @@ impl<'source> Program<'source> {
                 block!().extend($instruction)
             }};
         }
-        let Some(expression) = expression.into() else {
-            scope.stack_pointer += 1;
-            block!().extend(Instruction::PushUnit);
-            return Ok(());
-        };
-        let (_first_token, _last_token, diagnostics, nodes) = Expression::destroy(expression);
-        try_validate(diagnostics)?;
-        for node in nodes {
+        let mut short_circuits = short_circuits(&nodes);
+        // Jumps which skip the right operand of a logical operator,
+        // to be filled in once the operator is reached.
+        let mut short_circuit_destinations = Vec::new();
+        for (i, node) in nodes.into_iter().enumerate() {
+            // The left operand of `and` or `or` is on top of the stack.
+            // Skip the right operand if it cannot change the result,
+            // leaving the left operand as the result.
+            // Non-boolean left operands always reach the right operand,
+            // so that the operator reports both of them.
+            match short_circuits[i].take() {
+                Some(Instruction::LogicalAnd) => {
+                    block!().extend(Instruction::Clone(scope.stack_pointer - 1));
+                    block!().extend(Instruction::If(0));
+                    short_circuit_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                }
+                Some(Instruction::LogicalOr) => {
+                    // `or` must skip its right operand only when the left is *true*,
+                    // so non-boolean left operands are sent to the right operand first.
+                    block!().extend(Instruction::Clone(scope.stack_pointer - 1));
+                    block!().extend(Instruction::Clone(builtins::BOOL));
+                    block!().extend(Instruction::IsType);
+                    block!().extend(Instruction::If(0));
+                    let not_bool_destination = block!().len() - size_of::<ProgramCounter>();
+                    block!().extend(Instruction::Clone(scope.stack_pointer - 1));
+                    block!().extend(Instruction::If(0));
+                    let false_destination = block!().len() - size_of::<ProgramCounter>();
+                    block!().extend(Instruction::Jump(0));
+                    short_circuit_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                    fill(&mut block!(), not_bool_destination);
+                    fill(&mut block!(), false_destination);
+                }
+                _ => {}
+            }
+            let node = match node {
+                Folded::Node(node) => node,
+                Folded::Constant(constant) => {
+                    scope.stack_pointer += 1;
+                    self.add_constant(block_id, constant)?;
+                    continue;
+                }
+                Folded::Enum {
+                    enum_token,
+                    variants,
+                } => {
+                    // The variants are replaced by the resulting enum.
+                    self.add_folded(block_id, variants, scope)?;
+                    self.locate(block_id, enum_token.span);
+                    block!().extend(Instruction::PushEnum);
+                    continue;
+                }
+            };
+            if let Some(token) = node_token(&node) {
+                self.locate(block_id, token.span);
+            }
             match node {
                 Node::Unit(_, _) => {
                     scope.stack_pointer += 1;
                     block!().extend(Instruction::PushUnit)
                 }
-                Node::Number(token) => {
-                    let integer = token
-                        .origin
+                Node::Number(token) if token.origin(self.source).contains('.') => {
+                    let float = token
+                        .origin(self.source)
+                        .replace('_', "")
                         .parse()
-                        .map_err(|e| Error::InvalidInteger(token, e))?;
+                        .map_err(|e| Error::InvalidFloat(token, e))?;
+                    scope.stack_pointer += 1;
+                    block!().extend(Instruction::PushF64(float))
+                }
+                Node::Number(token) => {
+                    let integer = parse_integer(token, self.source)?;
                     scope.stack_pointer += 1;
                     block!().extend(Instruction::PushI64(integer))
                 }
@@ -498,17 +1055,52 @@ impl<'source> Program<'source> {
                     // adjust this if additional string formats are added.
                     let string = self.create_string(
                         string
-                            .resolve()
+                            .resolve(self.source)
                             .map_err(|e| Error::InvalidString(string, e))?,
                     )?;
                     scope.stack_pointer += 1;
                     block!().extend(Instruction::PushString(string))
                 }
+                Node::Interpolation(interpolation) => {
+                    let Interpolation {
+                        start,
+                        segments,
+                        diagnostics,
+                    } = *interpolation;
+                    try_validate(diagnostics)?;
+                    // Every string and expression is pushed to the stack and then concatenated,
+                    // except for empty strings, which would have no effect.
+                    let mut count = 0;
+                    let mut string = Some(start);
+                    let mut segments = segments.into_iter();
+                    loop {
+                        let string_token =
+                            string.expect("interpolations without errors have every string");
+                        let resolved = string_token
+                            .resolve(self.source)
+                            .map_err(|e| Error::InvalidString(string_token, e))?;
+                        if !resolved.is_empty() {
+                            let resolved = self.create_string(resolved)?;
+                            scope.stack_pointer += 1;
+                            count += 1;
+                            block!().extend(Instruction::PushString(resolved));
+                        }
+                        let Some(segment) = segments.next() else {
+                            break;
+                        };
+                        self.add_expression(block_id, segment.expression, scope)?;
+                        count += 1;
+                        string = segment.string;
+                    }
+                    scope.stack_pointer -= count as StackPointer - 1;
+                    self.locate(block_id, start.span);
+                    block!().extend(Instruction::Concat(count));
+                }
                 Node::Variable(token) => {
                     let value = scope
                         .get(
                             &token
-                                .resolve()
+                                .resolve(self.source)
                                 .map_err(|e| Error::InvalidIdentifier(token, e))?,
                         )
                         .ok_or(Error::UndefinedSymbol(token))?;
@@ -531,8 +1123,22 @@ impl<'source> Program<'source> {
                 Node::GreaterEqual(_) => binop!(Instruction::GreaterEqual),
                 Node::Lesser(_) => binop!(Instruction::Lesser),
                 Node::LesserEqual(_) => binop!(Instruction::LesserEqual),
-                Node::LogicalAnd(_) => binop!(Instruction::LogicalAnd),
-                Node::LogicalOr(_) => binop!(Instruction::LogicalOr),
+                Node::LogicalAnd(_) => {
+                    binop!(Instruction::LogicalAnd);
+                    let destination = short_circuit_destinations
+                        .pop()
+                        .expect("logical operators always have a short circuit");
+                    fill(&mut block!(), destination);
+                }
+                Node::LogicalOr(_) => {
+                    binop!(Instruction::LogicalOr);
+                    let destination = short_circuit_destinations
+                        .pop()
+                        .expect("logical operators always have a short circuit");
+                    fill(&mut block!(), destination);
+                }
+                Node::Range(_) => binop!(Instruction::Range),
+                Node::RangeInclusive(_) => binop!(Instruction::RangeInclusive),
                 // Unaries
                 // These do not move the stack,
                 // and say "+= 0" to make this clear.
@@ -544,6 +1150,14 @@ impl<'source> Program<'source> {
                     scope.stack_pointer += 0;
                     block!().extend(Instruction::Negative);
                 }
+                Node::Not(_) => {
+                    scope.stack_pointer += 0;
+                    block!().extend(Instruction::Not);
+                }
+                Node::Group { .. } => {
+                    scope.stack_pointer += 0;
+                    // parentheses only affect parsing
+                }
                 Node::Deref(_) => {
                     scope.stack_pointer += 0;
                     block!().extend(Instruction::Deref);
@@ -557,17 +1171,46 @@ impl<'source> Program<'source> {
                         block!().extend(Instruction::Nest);
                     } else {
                         let s = self.create_string(
-                            name.resolve()
+                            name.resolve(self.source)
                                 .map_err(|e| Error::InvalidIdentifier(name, e))?,
                         )?;
                         block!().extend(Instruction::Name(s));
                     }
                 }
 
-                Node::Block(block) => {
-                    self.add_block(block_id, block, scope.child())?;
+                Node::Block { label, block, .. } => {
+                    let mut child = scope.child();
+                    child.breakable = true;
+                    child.label = label.map(|label| label.label.origin(self.source));
+                    self.add_block(block_id, block, child)?;
                     scope.stack_pointer += 1;
                 }
+                Node::Break(break_node) => {
+                    if let Some(expression) = break_node.expression {
+                        self.add_expression(block_id, expression, scope)?;
+                    } else {
+                        scope.stack_pointer += 1;
+                        block!().extend(Instruction::PushUnit);
+                    }
+                    let target = if break_node.break_token.lexigram == Lexigram::Return {
+                        scope.root()
+                    } else if let Some(label) = break_node.label {
+                        scope
+                            .break_target(Some(label.origin(self.source)))
+                            .ok_or(Error::UndefinedLabel(label))?
+                    } else {
+                        scope
+                            .break_target(None)
+                            .ok_or(Error::InvalidBreak(break_node.break_token))?
+                    };
+                    // The value remains counted on the stack,
+                    // as though the break were an expression which produced it.
+                    block!().extend(Instruction::Jump(0));
+                    target
+                        .exits
+                        .borrow_mut()
+                        .push(block!().len() - size_of::<ProgramCounter>());
+                }
                 Node::Bool(boolean, _) => {
                     scope.stack_pointer += 1;
                     block!().extend(if boolean {
@@ -577,13 +1220,6 @@ impl<'source> Program<'source> {
                     })
                 }
                 Node::If(if_block) => {
-                    // For retroactively filling in the jump instructions.
-                    fn fill(block: &mut [u8], at: usize) {
-                        let pc = block.len() as ProgramCounter;
-                        block[at..(at + size_of::<ProgramCounter>())]
-                            .copy_from_slice(&pc.to_le_bytes());
-                    }
-
                     try_validate(if_block.diagnostics)?;
                     self.add_expression(block_id, if_block.condition, scope)?;
                     block!().extend(Instruction::If(0));
@@ -611,24 +1247,29 @@ impl<'source> Program<'source> {
                     index,
                 } => {
                     match index {
+                        token @ Token {
+                            lexigram: Lexigram::Ident,
+                            ..
+                        } if self.field_positions.contains_key(&token.span) => {
+                            let position = self.field_positions[&token.span];
+                            block!().extend(Instruction::PushI64(position as i64));
+                        }
                         token @ Token {
                             lexigram: Lexigram::Ident,
                             ..
                         } => {
                             let s = self.create_string(
                                 token
-                                    .resolve()
+                                    .resolve(self.source)
                                     .map_err(|e| Error::InvalidIdentifier(token, e))?,
                             )?;
                             block!().extend(Instruction::PushString(s));
                         }
                         token @ Token {
                             lexigram: Lexigram::Number,
-                            origin,
+                            ..
                         } => {
-                            let integer = origin
-                                .parse()
-                                .map_err(|e| Error::InvalidInteger(token, e))?;
+                            let integer = parse_integer(token, self.source)?;
                             block!().extend(Instruction::PushI64(integer));
                         }
                         _ => {
@@ -647,25 +1288,93 @@ impl<'source> Program<'source> {
                     // however only the variants (bottom of the stack) are accounted for in our scope,
                     // and the resulting enum will replace it.
                     scope.stack_pointer += 0;
+                    self.locate(block_id, enumeration.enum_token.span);
                     self.blocks[block_id as usize].extend(Instruction::PushEnum);
                 }
-                Node::Match(_) => todo!(),
+                Node::Match(match_block) => {
+                    let (match_token, expression, _then_token, _end_token, diagnostics, cases) =
+                        Match::destroy(match_block);
+                    try_validate(diagnostics)?;
+                    self.add_expression(block_id, expression, scope)?;
+                    // The value being matched stays on the stack until a case accepts it,
+                    // at which point the case's result is collapsed into its position.
+                    let subject = scope.stack_pointer - 1;
+                    let mut exit_destinations = Vec::new();
+                    for case in cases {
+                        let mut case_scope = scope.child();
+                        block!().extend(Instruction::Clone(subject));
+                        case_scope.stack_pointer += 1;
+                        // Each test which may reject the case jumps past it,
+                        // leaving a single (unused) value on the stack.
+                        // A let binding without any tests always matches,
+                        // so it does not need a conditional jump.
+                        let mut case_destinations = Vec::new();
+                        if let Some(case_expression) = case.case {
+                            self.add_expression(block_id, case_expression, &mut case_scope)?;
+                            // Matches replaces the subject and case with a value and a boolean,
+                            // and If pops the boolean.
+                            block!().extend(Instruction::Matches);
+                            block!().extend(Instruction::If(0));
+                            case_scope.stack_pointer -= 1;
+                            case_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                        }
+                        if let Some(ty) = case.ty {
+                            block!().extend(Instruction::Clone(case_scope.stack_pointer - 1));
+                            case_scope.stack_pointer += 1;
+                            self.add_expression(block_id, ty, &mut case_scope)?;
+                            // IsType replaces the value and type with a boolean,
+                            // and If pops the boolean.
+                            block!().extend(Instruction::IsType);
+                            block!().extend(Instruction::If(0));
+                            case_scope.stack_pointer -= 2;
+                            case_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                        }
+                        // Discards and equality cases leave the value unbound.
+                        if let Some(
+                            token @ Token {
+                                lexigram: Lexigram::Ident,
+                                ..
+                            },
+                        ) = case.binding
+                        {
+                            case_scope.insert(
+                                token
+                                    .resolve(self.source)
+                                    .map_err(|e| Error::InvalidIdentifier(token, e))?,
+                            );
+                        }
+                        if let Some(guard) = case.guard {
+                            self.add_expression(block_id, guard, &mut case_scope)?;
+                            block!().extend(Instruction::If(0));
+                            case_scope.stack_pointer -= 1;
+                            case_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                        }
+                        self.add_expression(block_id, case.expression, &mut case_scope)?;
+                        block!().extend(Instruction::Collapse(subject));
+                        block!().extend(Instruction::Jump(0));
+                        exit_destinations.push(block!().len() - size_of::<ProgramCounter>());
+                        if !case_destinations.is_empty() {
+                            for case_destination in case_destinations {
+                                fill(&mut block!(), case_destination);
+                            }
+                            block!().extend(Instruction::Pop);
+                        }
+                    }
+                    // The subject is still on top of the stack if no case accepted it.
+                    self.locate(block_id, match_token.span);
+                    block!().extend(Instruction::NoMatch);
+                    for exit_destination in exit_destinations {
+                        fill(&mut block!(), exit_destination);
+                    }
+                    // The subject has been replaced by the result of the match.
+                    scope.stack_pointer += 0;
+                }
             };
         }
         Ok(())
     }
 }
 
-impl<'source> TryFrom<Box<Block<'source>>> for Program<'source> {
-    type Error = Error<'source>;
-    fn try_from(block: Box<Block<'source>>) -> Result<Self, Self::Error> {
-        let mut this = Self::default();
-        let block_id = this.create_block()?;
-        this.add_block(block_id, block, Scope::default())?;
-        Ok(this)
-    }
-}
-
 #[derive(Clone, Copy, Debug)]
 pub struct Value {
     index: StackPointer,
@@ -677,6 +1386,14 @@ pub struct Scope<'parent, 'source> {
 
     stack_pointer: StackPointer,
     bindings: Vec<(Cow<'source, str>, Value)>,
+    /// Jumps to the end of the scope's block, to be filled in once it is reached.
+    ///
+    /// Breaks add to this from within child scopes, which only have a shared reference.
+    exits: RefCell<Vec<usize>>,
+    /// Whether an unlabeled `break` exits this scope's block.
+    breakable: bool,
+    /// The label which a `break` may use to exit this scope's block.
+    label: Option<&'source str>,
 }
 
 impl<'parent, 'source> Scope<'parent, 'source> {
@@ -707,6 +1424,24 @@ impl<'parent, 'source> Scope<'parent, 'source> {
         ));
     }
 
+    /// Finds the scope which a `break` with the given label exits.
+    fn break_target(&self, label: Option<&str>) -> Option<&Self> {
+        let accepted = match label {
+            Some(label) => self.label == Some(label),
+            None => self.breakable,
+        };
+        if accepted {
+            Some(self)
+        } else {
+            self.parent?.break_target(label)
+        }
+    }
+
+    /// Finds the scope of the function (or program) body, which `return` exits.
+    fn root(&self) -> &Self {
+        self.parent.map_or(self, |parent| parent.root())
+    }
+
     fn child(&'parent self) -> Self {
         Self {
             parent: Some(self),
@@ -728,6 +1463,7 @@ impl<'parent, 'source> Scope<'parent, 'source> {
             parent: None,
             stack_pointer: self.stack_pointer - lost_size,
             bindings: new_bindings,
+            ..Default::default()
         }
     }
 }
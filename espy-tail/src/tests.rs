@@ -1,6 +1,8 @@
 use super::*;
 use Instruction::*;
-use espy_eyes::Lexer;
+use espy_eyes::{Lexer, Span};
+use espy_paws::{ComplexType, EnumType, FunctionType, Tuple, Type};
+use std::rc::Rc;
 
 // espy assembly parser :3
 macro_rules! program {
@@ -11,11 +13,15 @@ macro_rules! program {
         $(fn $block:ident {
             $($i:expr,)*
         })*
+        $(metadata $kind:path {
+            $($m:expr,)*
+        })*
     ] => {
         {
             let mut program = Vec::new();
             program.extend([0; 4]);
             program.extend([0; 4]);
+            program.extend([0; 4]);
             let block_count = program.len();
             let mut i: BlockId = 0;
             $(
@@ -36,6 +42,17 @@ macro_rules! program {
             )*
             program[4..8].copy_from_slice(&i.to_le_bytes());
 
+            #[allow(unused)]
+            let metadata_count = program.len();
+            #[allow(unused)]
+            let mut i: u32 = 0;
+            $(
+                let _ = $kind;
+                program.extend([0; 4]);
+                i += 1;
+            )*
+            program[8..12].copy_from_slice(&i.to_le_bytes());
+
             let mut i = 0;
             $(
                 let offset = program.len() as u32;
@@ -55,16 +72,36 @@ macro_rules! program {
                 #[allow(unused_assignments)]
                 { i += 1; }
             )*
+            #[allow(unused)]
+            let mut i = 0;
+            $(
+                let offset = program.len() as u32;
+                program.push($kind);
+                $(program.extend($m);)*
+                program[(metadata_count + i * size_of::<u32>())..(metadata_count + (i + 1) * size_of::<u32>())]
+                    .copy_from_slice(&offset.to_le_bytes());
+                #[allow(unused_assignments)]
+                { i += 1; }
+            )*
             program
         }
     }
 }
 
+/// For tests of how operators are compiled, which would otherwise be folded away.
+const UNFOLDED: Options = Options {
+    doc_comments: false,
+    source_map: false,
+    fold_constants: false,
+    index_fields: false,
+};
+
 #[test]
 fn variables_and_arithmetic() {
-    let mut lexer = Lexer::from("let x = 1 + 2; let y = x * 3; x - y").peekable();
+    let source = "let x = 1 + 2; let y = x * 3; x - y";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::with_options(source, block, UNFOLDED).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -84,9 +121,10 @@ fn variables_and_arithmetic() {
 
 #[test]
 fn simple_blocks() {
-    let mut lexer = Lexer::from("let x = 2; 1 + { let y = 3; x * y }").peekable();
+    let source = "let x = 2; 1 + { let y = 3; x * y }";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -105,9 +143,10 @@ fn simple_blocks() {
 
 #[test]
 fn function_creation() {
-    let mut lexer = Lexer::from("let x = 2; with y; x * y").peekable();
+    let source = "let x = 2; with y; x * y";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -130,9 +169,10 @@ fn function_creation() {
 
 #[test]
 fn function_usage() {
-    let mut lexer = Lexer::from("let f = {with x; x * x}; f 2").peekable();
+    let source = "let f = {with x; x * x}; f 2";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -157,9 +197,10 @@ fn function_usage() {
 
 #[test]
 fn if_expression() {
-    let mut lexer = Lexer::from("if true then 1 else then 2 end").peekable();
+    let source = "if true then 1 else then 2 end";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -175,9 +216,10 @@ fn if_expression() {
 
 #[test]
 fn option_enum() {
-    let mut lexer = Lexer::from("let Option = enum Some: any, None: () end;").peekable();
+    let source = "let Option = enum Some: any, None: () end;";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         let some = "Some";
@@ -197,9 +239,10 @@ fn option_enum() {
 
 #[test]
 fn tuple_indexing() {
-    let mut lexer = Lexer::from("let x = 1, 2; x.1").peekable();
+    let source = "let x = 1, 2; x.1";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         fn _main {
@@ -216,9 +259,10 @@ fn tuple_indexing() {
 
 #[test]
 fn named_tuple_indexing() {
-    let mut lexer = Lexer::from("let x = first: 1, second: 2; x.second").peekable();
+    let source = "let x = first: 1, second: 2; x.second";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program![
         let first = "first";
@@ -239,9 +283,10 @@ fn named_tuple_indexing() {
 
 #[test]
 fn builtins() {
-    let mut lexer = Lexer::from("let OptionI64 = option i64; OptionI64.Some 1").peekable();
+    let source = "let OptionI64 = option i64; OptionI64.Some 1";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::with_options(source, block, UNFOLDED).unwrap();
     let actual = program.compile();
     let expected = program! {
         let some = "Some";
@@ -261,9 +306,10 @@ fn builtins() {
 
 #[test]
 fn string() {
-    let mut lexer = Lexer::from("\"string\"").peekable();
+    let source = "\"string\"";
+    let mut lexer = Lexer::from(source).peekable();
     let block = Block::new(&mut lexer);
-    let program = Program::try_from(block).unwrap();
+    let program = Program::new(source, block).unwrap();
     let actual = program.compile();
     let expected = program! {
         let string = "string";
@@ -273,3 +319,640 @@ fn string() {
     };
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn raw_and_multi_line_strings() {
+    let source = r####"let raw = r#"C:\path\"{quoted}""#;
+let empty = r"";
+let multi_line = """
+    SELECT *
+      FROM table\t
+
+    WHERE x = "{y}"
+    """;
+raw, multi_line"####;
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program! {
+        let raw = r#"C:\path\"{quoted}""#;
+        let empty = "";
+        let multi_line = "SELECT *\n  FROM table\t\n\nWHERE x = \"{y}\"";
+        fn _main {
+            PushString(raw),
+            PushString(empty),
+            PushString(multi_line),
+            Clone(0),
+            Clone(2),
+            Tuple,
+        }
+    };
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn interpolated_string() {
+    let source = "let name = \"world\"; \"Hello, {name}!\"";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program! {
+        let world = "world";
+        let hello = "Hello, ";
+        let bang = "!";
+        fn _main {
+            PushString(world),
+            PushString(hello),
+            Clone(0),
+            PushString(bang),
+            Concat(3),
+        }
+    };
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn match_expression() {
+    let source = "match 2 then 1 => 10; let x => x; end";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(2),
+            Clone(0),
+            PushI64(1),
+            Matches,
+            If(48),
+            PushI64(10),
+            Collapse(0),
+            Jump(70),
+            Pop,
+            Clone(0),
+            Clone(1),
+            Collapse(0),
+            Jump(70),
+            NoMatch,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn float() {
+    let source = "1.5 * -0.25";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::with_options(source, block, UNFOLDED).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushF64(1.5),
+            PushF64(0.25),
+            Negative,
+            Mul,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn integer_literals() {
+    let source = "0xFF00, 0b1010_0000, 0o17, 1_000_000, 2.5_0";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(0xFF00),
+            PushI64(0b1010_0000),
+            Tuple,
+            PushI64(0o17),
+            Tuple,
+            PushI64(1_000_000),
+            Tuple,
+            PushF64(2.5),
+            Tuple,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn ranges() {
+    let source = "0..10, 1..=10";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(0),
+            PushI64(10),
+            Range,
+            PushI64(1),
+            PushI64(10),
+            RangeInclusive,
+            Tuple,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn rest_bindings() {
+    let source = "let (a, ...b) = 1, 2, 3; let {x, ...y} = x: 1, y: 2;";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        let x = "x";
+        let y = "y";
+        fn _main {
+            PushI64(1),
+            PushI64(2),
+            Tuple,
+            PushI64(3),
+            Tuple,
+            Clone(0),
+            PushI64(0),
+            Index,
+            Clone(0),
+            PushI64(1),
+            Slice,
+            PushI64(1),
+            Name(x),
+            PushI64(2),
+            Name(y),
+            Tuple,
+            Clone(3),
+            PushString(x),
+            Index,
+            Clone(3),
+            PushString(x),
+            Remove,
+            PushUnit,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn short_circuit() {
+    let source = "true and false or true";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::with_options(source, block, UNFOLDED).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushTrue,
+            Clone(0),
+            If(13),
+            PushFalse,
+            LogicalAnd,
+            Clone(0),
+            Clone(builtins::BOOL),
+            IsType,
+            If(44),
+            Clone(0),
+            If(44),
+            Jump(46),
+            PushTrue,
+            LogicalOr,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn not() {
+    let source = "!true, !0";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::with_options(source, block, UNFOLDED).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushTrue,
+            Not,
+            PushI64(0),
+            Not,
+            Tuple,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn invalid_integer() {
+    let source = "1 + 0x8000_0000_0000_0000";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let Err(Error::InvalidInteger(token, _)) = Program::new(source, block) else {
+        panic!("expected an invalid integer error");
+    };
+    assert_eq!(token.span, Span::new(4, 21));
+    assert_eq!(token.origin(source), "0x8000_0000_0000_0000");
+}
+
+#[test]
+fn trailing_input() {
+    let source = "1 + 2 end";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::program(&mut lexer);
+    let Err(Error::InvalidAst(espy_ears::Error::TrailingInput(token))) =
+        Program::new(source, block)
+    else {
+        panic!("expected a trailing input error");
+    };
+    assert_eq!(token.origin(source), "end");
+}
+
+#[test]
+fn doc_comments() {
+    let source = "## Squares a number.
+let f = {with x; x * x};
+let g = {
+    ## Doubles a number.
+    with y;
+    y + y
+};
+f 2";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let options = Options {
+        doc_comments: true,
+        ..Default::default()
+    };
+    let program = Program::with_options(source, block, options).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        let squares = "Squares a number.";
+        let doubles = "Doubles a number.";
+        fn _main {
+            Clone(builtins::ANY),
+            Clone(builtins::ANY),
+            PushFunction {
+                captures: 0,
+                function: f,
+            },
+            Clone(builtins::ANY),
+            Clone(builtins::ANY),
+            PushFunction {
+                captures: 0,
+                function: g,
+            },
+            Clone(0),
+            PushI64(2),
+            Call,
+        }
+        fn f {
+            Clone(0),
+            Clone(0),
+            Mul,
+        }
+        fn g {
+            Clone(0),
+            Clone(0),
+            Add,
+        }
+        metadata metadata::DOC_COMMENTS {
+            f.to_le_bytes(),
+            squares.to_le_bytes(),
+            g.to_le_bytes(),
+            doubles.to_le_bytes(),
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn source_map() {
+    let source = "let x = 2;\nx * 3";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let options = Options {
+        source_map: true,
+        ..UNFOLDED
+    };
+    let program = Program::with_options(source, block, options).unwrap();
+    let actual = program.compile();
+    // block id, pc, start, end, line, column
+    let entry = |entry: [u32; 6]| entry.map(u32::to_le_bytes).concat();
+    let expected = program![
+        fn _main {
+            PushI64(2),
+            Clone(0),
+            PushI64(3),
+            Mul,
+        }
+        metadata metadata::SOURCE_MAP {
+            entry([0, 0, 8, 9, 1, 9]),
+            entry([0, 9, 11, 12, 2, 1]),
+            entry([0, 14, 15, 16, 2, 5]),
+            entry([0, 23, 13, 14, 2, 3]),
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn constant_folding() {
+    let source = "let day = 60 * 60 * 24;
+let b = (a: 1 + 1, b: 2.5).b;
+let nested = _: (1, 2), 3;
+let OptionI64 = option i64;
+let Option = enum Some: any, None: unit end;
+9223372036854775807 + 1, -day";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        let some = "Some";
+        let none = "None";
+        fn _main {
+            PushI64(86400),
+            PushF64(2.5),
+            PushI64(1),
+            PushI64(2),
+            Tuple,
+            Nest,
+            PushI64(3),
+            Tuple,
+            PushType(0),
+            PushType(1),
+            // Overflow is left for the interpreter to report.
+            PushI64(i64::MAX),
+            PushI64(1),
+            Add,
+            Clone(0),
+            Negative,
+            Tuple,
+        }
+        metadata metadata::TYPES {
+            [type_tags::OPTION, type_tags::I64],
+            [type_tags::ENUM],
+            2u32.to_le_bytes(),
+            some.to_le_bytes(),
+            [type_tags::ANY],
+            none.to_le_bytes(),
+            [type_tags::UNIT],
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn refutable_binding() {
+    let source = "let (1, x) = 1, 2; x";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(1),
+            PushI64(2),
+            Tuple,
+            Jump(31),
+            // refuted
+            Collapse(1),
+            Pop,
+            NoMatch,
+            Clone(0),
+            PushI64(0),
+            TryIndex,
+            If(24),
+            Clone(1),
+            PushI64(1),
+            Matches,
+            If(24),
+            Pop,
+            Clone(0),
+            PushI64(1),
+            TryIndex,
+            If(24),
+            Clone(2),
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn match_guards_and_types() {
+    let source = "match 1 then let n: i64 if n > 0 => n; end";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(1),
+            Clone(0),
+            Clone(1),
+            Clone(builtins::I64),
+            IsType,
+            If(65),
+            Clone(1),
+            PushI64(0),
+            Greater,
+            If(65),
+            Clone(1),
+            Collapse(0),
+            Jump(67),
+            Pop,
+            NoMatch,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn labeled_break() {
+    let source = "'a: { let x = 1; { break 'a x } }";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let program = Program::new(source, block).unwrap();
+    let actual = program.compile();
+    let expected = program![
+        fn _main {
+            PushI64(1),
+            Clone(0),
+            Jump(19),
+            // exit of 'a
+            Collapse(0),
+        }
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn type_inference() {
+    let source = "let Shape = enum Circle: f64, Rect: (f64, f64) end;
+let point = x: 1, y: 2.0;
+let {y, ...rest} = point;
+let circle = Shape.Circle 1.5;
+let area = {
+    let Shape = Shape;
+    with shape: Shape -> f64;
+    match shape then
+        let r = Shape.Circle => 3.14 * r * r;
+        let size = Shape.Rect => size.0 * size.1;
+    end
+};
+area circle";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let actual = types::check(source, &block);
+    let shape = Type::from(Rc::new(EnumType {
+        variants: Rc::new([
+            (Rc::from("Circle"), Type::F64.into()),
+            (
+                Rc::from("Rect"),
+                Tuple::from([ComplexType::from(Type::F64), Type::F64.into()]).into(),
+            ),
+        ]),
+    }));
+    let bindings = actual
+        .bindings
+        .iter()
+        .map(|(token, ty)| (token.origin(source), ty.clone()))
+        .collect::<Vec<_>>();
+    let expected: &[(&str, ComplexType)] = &[
+        ("Shape", Type::Type.into()),
+        (
+            "point",
+            Tuple::from([
+                (Rc::from("x"), Type::I64.into()),
+                (Rc::from("y"), Type::F64.into()),
+            ])
+            .into(),
+        ),
+        ("y", Type::F64.into()),
+        (
+            "rest",
+            Tuple::from([(Rc::from("x"), Type::I64.into())]).into(),
+        ),
+        ("circle", shape.clone().into()),
+        ("Shape", Type::Type.into()),
+        ("shape", shape.clone().into()),
+        ("r", Type::F64.into()),
+        (
+            "size",
+            Tuple::from([ComplexType::from(Type::F64), Type::F64.into()]).into(),
+        ),
+        (
+            "area",
+            Type::Function(Rc::new(FunctionType {
+                input: shape.into(),
+                output: Type::F64.into(),
+            }))
+            .into(),
+        ),
+    ];
+    assert_eq!(bindings, expected);
+    assert_eq!(actual.diagnostics, []);
+}
+
+#[test]
+fn type_errors() {
+    let diagnostics = |source| {
+        let mut lexer = Lexer::from(source).peekable();
+        let block = Block::new(&mut lexer);
+        types::check(source, &block)
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.token().origin(source))
+            .collect::<Vec<_>>()
+    };
+    // Function arguments and results.
+    assert_eq!(
+        diagnostics("let f = { with x: i64 -> string; x }; f 1.5"),
+        ["string", "1.5"]
+    );
+    // Tuple indexing.
+    assert_eq!(
+        diagnostics("let t = a: 1, b: 2; t.c, t.2, t.b.0"),
+        ["c", "2", "0"]
+    );
+    // Enum construction.
+    assert_eq!(
+        diagnostics("let E = enum A: i64, B: unit end; E.A \"a\"; E.C ()"),
+        ["\"a\"", "C"]
+    );
+    // Operators and calls.
+    assert_eq!(
+        diagnostics("let n = 1; n + 1.0; -\"s\"; n (); n |> n"),
+        ["+", "-", "(", "|>"]
+    );
+    // Signatures must be types, and unknown values are never reported.
+    assert_eq!(diagnostics("with x: 1; x.y + x 2"), ["1"]);
+    // Annotations ignore names and extra fields, so declared tuples may have any field.
+    assert!(
+        diagnostics("let f = {with q: (x: i64); q.y}; f (y: 1); {with p: (i64, i64); p.2}")
+            .is_empty()
+    );
+}
+
+#[test]
+fn field_indexing() {
+    let source = "let p = x: 1, y: 2;
+let f = {
+    with q: (x: i64, y: i64);
+    q.y
+};
+f p;
+p.y";
+    let mut lexer = Lexer::from(source).peekable();
+    let block = Block::new(&mut lexer);
+    let options = Options {
+        index_fields: true,
+        ..UNFOLDED
+    };
+    let actual = Program::with_options(source, block, options)
+        .unwrap()
+        .compile();
+    let expected = program![
+        let x = "x";
+        let y = "y";
+        fn _main {
+            PushI64(1),
+            Name(x),
+            PushI64(2),
+            Name(y),
+            Tuple,
+            Clone(builtins::I64),
+            Name(x),
+            Clone(builtins::I64),
+            Name(y),
+            Tuple,
+            Clone(builtins::ANY),
+            PushFunction {
+                captures: 0,
+                function: f,
+            },
+            Clone(1),
+            Clone(0),
+            Call,
+            Pop,
+            // The position of a literal's field is known.
+            Clone(0),
+            PushI64(1),
+            Index,
+        }
+        fn f {
+            // An argument is only checked by `compare`, which ignores names.
+            Clone(0),
+            PushString(y),
+            Index,
+        }
+    ];
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,1011 @@
+//! Static type inference and checking.
+//!
+//! Types are inferred using the interpreter's own [`Type`] vocabulary,
+//! with [`Type::Any`] standing in for anything that cannot be known before runtime.
+//! Checking is entirely separate from compilation and has no effect on a program's behavior;
+//! it only reports operations which are certain to fail once they are evaluated.
+//!
+//! ```
+//! use espy_eyes::Lexer;
+//! use espy_ears::Block;
+//! use espy_tail::types::{self, Diagnostic};
+//!
+//! let source = "let square = { with x: i64 -> i64; x * x }; square 1.5";
+//! let block = Block::program(&mut Lexer::from(source).peekable());
+//! let types = types::check(source, &block);
+//! assert!(matches!(types.diagnostics[..], [Diagnostic::Mismatch { .. }]));
+//! ```
+
+use crate::parse_integer;
+use espy_ears::{
+    Binding, BindingMethod, Block, BlockResult, Evaluation, Expression, Function, If, Match, Node,
+    Set, Statement, VariantBinding,
+};
+use espy_eyes::{Lexigram, Token};
+use espy_heart::prelude::*;
+use espy_paws::{ComplexType, EnumType, FunctionType, Tuple, Type};
+use std::{borrow::Cow, cell::RefCell, mem, rc::Rc};
+
+/// An operation which the type checker has proven will fail at runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic<'source> {
+    /// A value's type did not match the type it was required to have,
+    /// such as a function's argument or result.
+    Mismatch {
+        token: Token<'source>,
+        expected: ComplexType,
+        actual: ComplexType,
+    },
+    /// An arithmetic, bitwise, comparison, or range operator was given operands it does not accept.
+    InvalidOperands {
+        token: Token<'source>,
+        left: ComplexType,
+        right: ComplexType,
+    },
+    /// `-` or `!` was given an operand it does not accept.
+    InvalidOperand {
+        token: Token<'source>,
+        operand: ComplexType,
+    },
+    /// A value was indexed by a field it does not have.
+    MissingField {
+        token: Token<'source>,
+        container: ComplexType,
+    },
+    /// A value which is not a function was called or piped into.
+    NotCallable {
+        token: Token<'source>,
+        actual: ComplexType,
+    },
+    /// A value which is not a type was used as one.
+    ExpectedType {
+        token: Token<'source>,
+        actual: ComplexType,
+    },
+}
+
+impl<'source> Diagnostic<'source> {
+    /// The token which the diagnostic refers to.
+    pub fn token(&self) -> Token<'source> {
+        match self {
+            Diagnostic::Mismatch { token, .. }
+            | Diagnostic::InvalidOperands { token, .. }
+            | Diagnostic::InvalidOperand { token, .. }
+            | Diagnostic::MissingField { token, .. }
+            | Diagnostic::NotCallable { token, .. }
+            | Diagnostic::ExpectedType { token, .. } => *token,
+        }
+    }
+}
+
+/// The result of checking a program.
+#[derive(Debug, Default)]
+pub struct Types<'source> {
+    /// The type of each variable the program binds, in the order they are bound.
+    pub bindings: Vec<(Token<'source>, ComplexType)>,
+    /// The position of each field accessed by name whose position is certain,
+    /// by the token of the field's name.
+    ///
+    /// Named tuples (and enum and option types) may also be indexed by position,
+    /// so the compiler may use these to skip searching for the name at runtime.
+    pub fields: Vec<(Token<'source>, usize)>,
+    pub diagnostics: Vec<Diagnostic<'source>>,
+}
+
+/// Infers the types of a program's variables and reports any operations which cannot succeed.
+pub fn check<'source>(source: &'source str, block: &Block<'source>) -> Types<'source> {
+    let mut checker = Checker {
+        source,
+        types: Types::default(),
+    };
+    checker.block(block, Scope::default());
+    checker.types
+}
+
+/// What is known about a value before runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Inferred {
+    /// A value of the given type, which may be (or contain) [`Type::Any`] if it is not known.
+    Of(ComplexType, Certainty),
+    /// A type (or tuple of types), which is itself a value of type `type`.
+    Type(ComplexType),
+    /// The builtin `option` function.
+    Option,
+    /// The builtin `mut` function.
+    Mut,
+}
+
+/// How closely an inferred type is guaranteed to match the value's type at runtime.
+///
+/// The interpreter checks annotations with [`ComplexType::compare`],
+/// which ignores the names (and lengths) of tuples,
+/// so a value is only known exactly if it was built from literals
+/// or checked by an enum variant's constructor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Certainty {
+    /// The value is only known to be compatible with its type.
+    Declared,
+    /// The value's own fields are known exactly, but not their contents.
+    Outer,
+    /// The value's type is known exactly.
+    Exact,
+}
+
+impl Inferred {
+    fn any() -> Self {
+        Inferred::Of(Type::Any.into(), Certainty::Exact)
+    }
+
+    fn unit() -> Self {
+        Inferred::Of(Type::Unit.into(), Certainty::Exact)
+    }
+
+    fn exact(ty: impl Into<ComplexType>) -> Self {
+        Inferred::Of(ty.into(), Certainty::Exact)
+    }
+
+    /// The type which the interpreter would report for this value.
+    fn type_of(&self) -> ComplexType {
+        match self {
+            Inferred::Of(ty, _) => ty.clone(),
+            Inferred::Type(_) => Type::Type.into(),
+            Inferred::Option | Inferred::Mut => function(Type::Any.into(), Type::Any.into()),
+        }
+    }
+
+    fn certainty(&self) -> Certainty {
+        match self {
+            Inferred::Of(_, certainty) => *certainty,
+            Inferred::Type(_) | Inferred::Option | Inferred::Mut => Certainty::Exact,
+        }
+    }
+
+    /// The type this value denotes when it is used as one.
+    ///
+    /// Returns the value's own type if it cannot be a type.
+    fn into_type(self) -> Result<ComplexType, ComplexType> {
+        match self {
+            Inferred::Type(ty) => Ok(ty),
+            Inferred::Of(ComplexType::Simple(Type::Any | Type::Type), _) => Ok(Type::Any.into()),
+            value => Err(value.type_of()),
+        }
+    }
+
+    /// The value's shape under tuple concatenation, if it is known.
+    fn shape(&self) -> Option<Shape> {
+        Some(match self {
+            Inferred::Of(ComplexType::Simple(Type::Any | Type::Type), _) => return None,
+            Inferred::Of(ComplexType::Simple(Type::Unit), _) => Shape::Unit,
+            Inferred::Of(ComplexType::Complex(tuple), _) => Shape::of(tuple, Clone::clone),
+            Inferred::Type(ComplexType::Complex(tuple)) => Shape::of(tuple, |_| Type::Type.into()),
+            value => Shape::Single(value.type_of()),
+        })
+    }
+
+    /// Whether the value's shape is certain to be the one returned by [`Inferred::shape`].
+    ///
+    /// External values are compatible with any declared type,
+    /// so a declared tuple or unit may actually be a single value.
+    fn shape_is_certain(&self) -> bool {
+        match self {
+            Inferred::Of(ComplexType::Simple(Type::Unit) | ComplexType::Complex(_), certainty) => {
+                *certainty >= Certainty::Outer
+            }
+            _ => true,
+        }
+    }
+
+    /// Mirrors the interpreter's tuple concatenation.
+    fn concat(self, r: Self) -> Self {
+        match (self, r) {
+            // A tuple of types is still a type, so the types it denotes are tracked instead.
+            (Inferred::Type(l), Inferred::Type(r)) => {
+                let shape = |ty| match ty {
+                    ComplexType::Complex(tuple) => Shape::of(&tuple, Clone::clone),
+                    ty => Shape::Single(ty),
+                };
+                Inferred::Type(shape(l).concat(shape(r)).into_type())
+            }
+            (l, r) => match l.shape().zip(r.shape()) {
+                Some((ls, rs)) => {
+                    let certainty = if l.shape_is_certain() && r.shape_is_certain() {
+                        l.certainty().min(r.certainty()).max(Certainty::Outer)
+                    } else {
+                        Certainty::Declared
+                    };
+                    Inferred::Of(ls.concat(rs).into_type(), certainty)
+                }
+                None => Inferred::any(),
+            },
+        }
+    }
+
+    /// Mirrors the interpreter's `:` operator.
+    fn name(self, name: Option<Rc<str>>) -> Self {
+        let tuple = |ty| match name {
+            Some(name) => Tuple::from([(name, ty)]),
+            None => Tuple::from([ty]),
+        };
+        match self {
+            Inferred::Type(ty) => Inferred::Type(tuple(ty).into()),
+            // A tuple of types would be a type, so nothing more is known about it.
+            Inferred::Of(ComplexType::Simple(Type::Type), _) => self,
+            value => Inferred::Of(
+                tuple(value.type_of()).into(),
+                value.certainty().max(Certainty::Outer),
+            ),
+        }
+    }
+
+    /// Wraps the type of one of this value's fields.
+    fn with_field(&self, ty: ComplexType) -> Self {
+        match self {
+            Inferred::Type(_) => Inferred::Type(ty),
+            Inferred::Of(_, Certainty::Exact) => Inferred::exact(ty),
+            _ => Inferred::Of(ty, Certainty::Declared),
+        }
+    }
+
+    /// Returns the tuple which this value is (or denotes), if it is known.
+    fn tuple(&self) -> Option<&Tuple<ComplexType>> {
+        match self {
+            Inferred::Of(ComplexType::Complex(tuple), _)
+            | Inferred::Type(ComplexType::Complex(tuple)) => Some(tuple),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of a tuple's field, if it is known.
+    fn element(&self, index: usize) -> Self {
+        self.tuple()
+            .and_then(|tuple| tuple.value(index))
+            .map_or_else(Inferred::any, |ty| self.with_field(ty.clone()))
+    }
+
+    /// Returns the type of a named tuple's field, if it is known.
+    fn named_element(&self, name: &str) -> Self {
+        self.tuple()
+            .and_then(|tuple| tuple.find_value(name))
+            .map_or_else(Inferred::any, |ty| self.with_field(ty.clone()))
+    }
+
+    /// Applies `f` to the type of a tuple, or the types it denotes.
+    fn map_tuple(&self, f: impl FnOnce(&Tuple<ComplexType>) -> Option<Tuple<ComplexType>>) -> Self {
+        match self.tuple().and_then(f) {
+            // The interpreter produces unit rather than empty tuples.
+            Some(tuple) if tuple.is_empty() => Inferred::unit(),
+            Some(tuple) => match self {
+                Inferred::Type(_) => Inferred::Type(tuple.into()),
+                _ => Inferred::Of(tuple.into(), self.certainty()),
+            },
+            None => Inferred::any(),
+        }
+    }
+
+    /// Joins the results of two branches.
+    fn join(self, r: Self) -> Self {
+        match (self, r) {
+            (Inferred::Of(l, lc), Inferred::Of(r, rc)) if l == r => Inferred::Of(l, lc.min(rc)),
+            (l, r) if l == r => l,
+            _ => Inferred::any(),
+        }
+    }
+}
+
+fn function(input: ComplexType, output: ComplexType) -> ComplexType {
+    Type::Function(Rc::new(FunctionType { input, output })).into()
+}
+
+/// A value's structure, as seen by tuple concatenation.
+enum Shape {
+    Unit,
+    Numeric(Vec<ComplexType>),
+    Named(Vec<(Rc<str>, ComplexType)>),
+    Single(ComplexType),
+}
+
+impl Shape {
+    fn of(tuple: &Tuple<ComplexType>, element: impl Fn(&ComplexType) -> ComplexType) -> Self {
+        match tuple.names() {
+            Some(names) => Shape::Named(names.cloned().zip(tuple.values().map(element)).collect()),
+            None => Shape::Numeric(tuple.values().map(element).collect()),
+        }
+    }
+
+    fn concat(self, r: Self) -> Self {
+        match (self, r) {
+            (Shape::Numeric(mut l), Shape::Numeric(r)) => {
+                l.extend(r);
+                Shape::Numeric(l)
+            }
+            (Shape::Named(mut l), Shape::Named(r)) => {
+                l.extend(r);
+                Shape::Named(l)
+            }
+            (l, Shape::Unit) => l,
+            (Shape::Unit, r) => r,
+            (Shape::Numeric(mut l), r) => {
+                l.push(r.into_type());
+                Shape::Numeric(l)
+            }
+            (l, Shape::Numeric(mut r)) => {
+                r.insert(0, l.into_type());
+                Shape::Numeric(r)
+            }
+            (l, r) => Shape::Numeric(vec![l.into_type(), r.into_type()]),
+        }
+    }
+
+    fn into_type(self) -> ComplexType {
+        match self {
+            Shape::Unit => Type::Unit.into(),
+            Shape::Numeric(values) => Tuple::from(Rc::<[_]>::from(values)).into(),
+            Shape::Named(values) => Tuple::from(Rc::<[_]>::from(values)).into(),
+            Shape::Single(ty) => ty,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Scope<'parent, 'source> {
+    parent: Option<&'parent Scope<'parent, 'source>>,
+    bindings: Vec<(Cow<'source, str>, Inferred)>,
+    /// Values which leave this scope early, through `break`, `return`, or a `let ... else` branch.
+    exits: RefCell<Vec<Inferred>>,
+    breakable: bool,
+    label: Option<&'source str>,
+}
+
+impl<'parent, 'source> Scope<'parent, 'source> {
+    fn get(&self, k: &str) -> Option<Inferred> {
+        self.bindings
+            .iter()
+            // Use most recent binding
+            .rev()
+            .find(|(x, _)| *x == k)
+            .map(|(_, x)| x.clone())
+            .or_else(|| {
+                self.parent.and_then(|parent| parent.get(k)).or_else(|| {
+                    builtins::from_str(k).map(|builtin| match builtin {
+                        builtins::OPTION => Inferred::Option,
+                        builtins::MUT => Inferred::Mut,
+                        builtins::ANY => Inferred::Type(Type::Any.into()),
+                        builtins::UNIT => Inferred::Type(Type::Unit.into()),
+                        builtins::I64 => Inferred::Type(Type::I64.into()),
+                        builtins::F64 => Inferred::Type(Type::F64.into()),
+                        builtins::STRING => Inferred::Type(Type::String.into()),
+                        builtins::BOOL => Inferred::Type(Type::Bool.into()),
+                        _ => Inferred::any(),
+                    })
+                })
+            })
+    }
+
+    fn child(&'parent self) -> Self {
+        Self {
+            parent: Some(self),
+            bindings: Vec::new(),
+            exits: RefCell::default(),
+            breakable: false,
+            label: None,
+        }
+    }
+
+    /// Finds the scope of the function (or program) body, which `return` exits.
+    fn root(&self) -> &Self {
+        self.parent.map_or(self, |parent| parent.root())
+    }
+
+    fn break_target(&self, label: Option<&str>) -> Option<&Self> {
+        let accepted = match label {
+            Some(label) => self.label == Some(label),
+            None => self.breakable,
+        };
+        if accepted {
+            Some(self)
+        } else {
+            self.parent?.break_target(label)
+        }
+    }
+
+    /// Mirrors the compiler: a function only captures the bindings of the scope it's declared in.
+    fn promote(&mut self) -> Self {
+        Self {
+            parent: None,
+            bindings: mem::take(&mut self.bindings),
+            exits: RefCell::default(),
+            breakable: false,
+            label: None,
+        }
+    }
+}
+
+struct Checker<'source> {
+    source: &'source str,
+    types: Types<'source>,
+}
+
+impl<'source> Checker<'source> {
+    fn report(&mut self, diagnostic: Diagnostic<'source>) {
+        self.types.diagnostics.push(diagnostic);
+    }
+
+    fn block(&mut self, block: &Block<'source>, mut scope: Scope<'_, 'source>) -> Inferred {
+        for statement in &block.statements {
+            self.statement(statement, &mut scope);
+        }
+        let result = match &block.result {
+            BlockResult::Expression(Some(expression)) => self.expression(expression, &scope),
+            BlockResult::Expression(None) => Inferred::unit(),
+            BlockResult::Function(function) => self.function(function, &mut scope),
+        };
+        // The block's value may also come from any early exit which targets it.
+        scope.exits.take().into_iter().fold(result, Inferred::join)
+    }
+
+    fn statement(&mut self, statement: &Statement<'source>, scope: &mut Scope<'_, 'source>) {
+        match statement {
+            Statement::Evaluation(Evaluation {
+                binding,
+                expression,
+                else_branch,
+                ..
+            }) => {
+                let value = expression
+                    .as_ref()
+                    .map_or_else(Inferred::unit, |expression| {
+                        self.expression(expression, scope)
+                    });
+                if let Some(else_branch) = else_branch {
+                    let exit = self.block(&else_branch.block, scope.child());
+                    scope.exits.get_mut().push(exit);
+                }
+                if let Some(binding) = binding.as_ref().and_then(|x| x.binding.as_ref()) {
+                    self.bind(binding, value, scope);
+                }
+            }
+            Statement::Set(Set {
+                target, expression, ..
+            }) => {
+                if let Some(target) = target {
+                    let expected = Type::Mut(Rc::new(Type::Any.into())).into();
+                    let actual = self.expression(target, scope).type_of();
+                    if !actual.compare(&expected)
+                        && let Some(token) = target.first_token
+                    {
+                        self.report(Diagnostic::Mismatch {
+                            token,
+                            expected,
+                            actual,
+                        });
+                    }
+                }
+                if let Some(expression) = expression {
+                    self.expression(expression, scope);
+                }
+            }
+        }
+    }
+
+    fn function(
+        &mut self,
+        function: &Function<'source>,
+        scope: &mut Scope<'_, 'source>,
+    ) -> Inferred {
+        let input = function
+            .input
+            .as_ref()
+            .map_or(Type::Any.into(), |input| self.ty(input, scope));
+        let output = function
+            .output
+            .as_ref()
+            .map_or(Type::Any.into(), |output| self.ty(output, scope));
+        let mut scope = scope.promote();
+        if let Some(argument) = &function.argument {
+            self.bind(
+                argument,
+                Inferred::Of(input.clone(), Certainty::Declared),
+                &mut scope,
+            );
+        }
+        let actual = self.block(&function.block, scope).type_of();
+        if !actual.compare(&output)
+            && let Some(token) = function.output.as_ref().and_then(|x| x.first_token)
+        {
+            self.report(Diagnostic::Mismatch {
+                token,
+                expected: output.clone(),
+                actual,
+            });
+        }
+        Inferred::exact(self::function(input, output))
+    }
+
+    /// Evaluates an expression which is used as a type.
+    fn ty(&mut self, expression: &Expression<'source>, scope: &Scope<'_, 'source>) -> ComplexType {
+        match self.expression(expression, scope).into_type() {
+            Ok(ty) => ty,
+            Err(actual) => {
+                if let Some(token) = expression.first_token {
+                    self.report(Diagnostic::ExpectedType { token, actual });
+                }
+                Type::Any.into()
+            }
+        }
+    }
+
+    fn bind(
+        &mut self,
+        binding: &Binding<'source>,
+        value: Inferred,
+        scope: &mut Scope<'_, 'source>,
+    ) {
+        match &binding.method {
+            BindingMethod::Single(token) => self.bind_name(*token, value, scope),
+            BindingMethod::Literal(_) => {}
+            BindingMethod::Variant(variant) => {
+                let contents = self.variant_contents(variant, scope);
+                if let Some(binding) = &variant.binding {
+                    self.bind(binding, contents, scope);
+                }
+            }
+            BindingMethod::Numeric { bindings, rest, .. } => {
+                for (i, numeric) in bindings.iter().enumerate() {
+                    self.bind(&numeric.binding, value.element(i), scope);
+                }
+                if let Some(rest) = rest {
+                    let remaining = value.map_tuple(|tuple| Some(tuple.slice(bindings.len())));
+                    self.bind(&rest.binding, remaining, scope);
+                }
+            }
+            BindingMethod::Named { bindings, rest, .. } => {
+                let mut names = Vec::new();
+                for named in bindings {
+                    let Ok(name) = named.field.resolve(self.source) else {
+                        continue;
+                    };
+                    let field = value.named_element(&name);
+                    names.push(name);
+                    match &named.binding {
+                        Some(sub) => self.bind(&sub.binding, field, scope),
+                        None => self.bind_name(named.field, field, scope),
+                    }
+                }
+                if let Some(rest) = rest {
+                    let remaining = value.map_tuple(|tuple| {
+                        names
+                            .iter()
+                            .try_fold(tuple.clone(), |tuple, name| tuple.remove(name))
+                    });
+                    self.bind(&rest.binding, remaining, scope);
+                }
+            }
+        }
+    }
+
+    fn bind_name(
+        &mut self,
+        token: Token<'source>,
+        value: Inferred,
+        scope: &mut Scope<'_, 'source>,
+    ) {
+        if token.lexigram == Lexigram::Discard {
+            return;
+        }
+        let Ok(name) = token.resolve(self.source) else {
+            return;
+        };
+        self.types.bindings.push((token, value.type_of()));
+        scope.bindings.push((name, value));
+    }
+
+    /// Returns the contents of the variant matched by a binding like `Shape.Circle r`.
+    fn variant_contents(
+        &mut self,
+        variant: &VariantBinding<'source>,
+        scope: &Scope<'_, 'source>,
+    ) -> Inferred {
+        let Some(mut constructor) = variant
+            .constructor
+            .resolve(self.source)
+            .ok()
+            .and_then(|name| scope.get(&name))
+        else {
+            return Inferred::any();
+        };
+        for field in &variant.fields {
+            constructor = self.field(constructor, field.field);
+        }
+        contents(&constructor)
+    }
+
+    fn expression(
+        &mut self,
+        expression: &Expression<'source>,
+        scope: &Scope<'_, 'source>,
+    ) -> Inferred {
+        let mut stack: Vec<Inferred> = Vec::new();
+        for node in &expression.contents {
+            let value = match node {
+                Node::Unit(..) => Inferred::unit(),
+                Node::Bool(..) => Inferred::exact(Type::Bool),
+                Node::Number(token) if token.origin(self.source).contains('.') => {
+                    Inferred::exact(Type::F64)
+                }
+                Node::Number(_) => Inferred::exact(Type::I64),
+                Node::String(_) => Inferred::exact(Type::String),
+                Node::Interpolation(interpolation) => {
+                    for segment in &interpolation.segments {
+                        if let Some(expression) = &segment.expression {
+                            self.expression(expression, scope);
+                        }
+                    }
+                    Inferred::exact(Type::String)
+                }
+                Node::Variable(token) => token
+                    .resolve(self.source)
+                    .ok()
+                    .and_then(|name| scope.get(&name))
+                    // Undefined variables are reported by the compiler.
+                    .unwrap_or_else(Inferred::any),
+                Node::Block { label, block, .. } => {
+                    let mut child = scope.child();
+                    child.breakable = true;
+                    child.label = label.as_ref().map(|label| label.label.origin(self.source));
+                    self.block(block, child)
+                }
+                Node::If(if_block) => self.if_block(if_block, scope),
+                Node::Break(break_node) => {
+                    let value = break_node
+                        .expression
+                        .as_ref()
+                        .map_or_else(Inferred::unit, |expression| {
+                            self.expression(expression, scope)
+                        });
+                    let target = if break_node.break_token.lexigram == Lexigram::Return {
+                        Some(scope.root())
+                    } else {
+                        scope.break_target(break_node.label.map(|label| label.origin(self.source)))
+                    };
+                    // Invalid breaks are reported by the compiler.
+                    if let Some(target) = target {
+                        target.exits.borrow_mut().push(value);
+                    }
+                    Inferred::any()
+                }
+                Node::Match(match_block) => self.match_block(match_block, scope),
+                Node::Enum(enum_block) => {
+                    let variants = enum_block
+                        .variants
+                        .as_ref()
+                        .map_or_else(Inferred::unit, |x| self.expression(x, scope));
+                    let ty = match variants {
+                        Inferred::Type(ComplexType::Complex(tuple)) => {
+                            tuple.names().map(|names| EnumType {
+                                variants: names.cloned().zip(tuple.values().cloned()).collect(),
+                            })
+                        }
+                        _ => None,
+                    };
+                    Inferred::Type(ty.map_or(Type::Any, |ty| Type::Enum(Rc::new(ty))).into())
+                }
+                Node::Positive(_) | Node::Group { .. } => continue,
+                Node::Negative(token) | Node::Not(token) => {
+                    let operand = stack.pop().expect("unary operators have an operand");
+                    let ty = operand.type_of();
+                    let valid = matches!(
+                        (node, &ty),
+                        (_, ComplexType::Simple(Type::Any | Type::I64))
+                            | (Node::Negative(_), ComplexType::Simple(Type::F64))
+                            | (Node::Not(_), ComplexType::Simple(Type::Bool))
+                    );
+                    if !valid {
+                        self.report(Diagnostic::InvalidOperand {
+                            token: *token,
+                            operand: ty.clone(),
+                        });
+                    }
+                    if valid { operand } else { Inferred::any() }
+                }
+                Node::Deref(token) => {
+                    let operand = stack.pop().expect("unary operators have an operand");
+                    match operand.type_of() {
+                        ComplexType::Simple(Type::Mut(ty)) => {
+                            Inferred::Of((*ty).clone(), Certainty::Declared)
+                        }
+                        ComplexType::Simple(Type::Any) => Inferred::any(),
+                        actual => {
+                            self.report(Diagnostic::Mismatch {
+                                token: *token,
+                                expected: Type::Mut(Rc::new(Type::Any.into())).into(),
+                                actual,
+                            });
+                            Inferred::any()
+                        }
+                    }
+                }
+                Node::Name { name, .. } => {
+                    let operand = stack.pop().expect("unary operators have an operand");
+                    if name.lexigram == Lexigram::Discard {
+                        operand.name(None)
+                    } else {
+                        match name.resolve(self.source) {
+                            Ok(name) => operand.name(Some(Rc::from(name))),
+                            Err(_) => Inferred::any(),
+                        }
+                    }
+                }
+                Node::Field { index, .. } => {
+                    let operand = stack.pop().expect("unary operators have an operand");
+                    self.field(operand, *index)
+                }
+                _ => {
+                    let r = stack.pop().expect("binary operators have two operands");
+                    let l = stack.pop().expect("binary operators have two operands");
+                    self.binary(node, l, r)
+                }
+            };
+            stack.push(value);
+        }
+        stack.pop().unwrap_or_else(Inferred::unit)
+    }
+
+    fn if_block(&mut self, if_block: &If<'source>, scope: &Scope<'_, 'source>) -> Inferred {
+        if let Some(condition) = &if_block.condition {
+            self.expression(condition, scope);
+        }
+        let first = self.block(&if_block.first, scope.child());
+        let second = self.block(&if_block.second, scope.child());
+        first.join(second)
+    }
+
+    fn match_block(
+        &mut self,
+        match_block: &Match<'source>,
+        scope: &Scope<'_, 'source>,
+    ) -> Inferred {
+        let value = match_block
+            .expression
+            .as_ref()
+            .map_or_else(Inferred::any, |x| self.expression(x, scope));
+        let mut result: Option<Inferred> = None;
+        for case in &match_block.cases {
+            let mut scope = scope.child();
+            let ty = case.ty.as_ref().map(|ty| self.ty(ty, &scope));
+            let constructor = case.case.as_ref().map(|x| self.expression(x, &scope));
+            if let Some(binding) = case.binding {
+                let bound = match (ty, &constructor) {
+                    (Some(ty), _) => Inferred::Of(ty, Certainty::Declared),
+                    (None, Some(constructor)) if is_constructor(constructor) => {
+                        contents(constructor)
+                    }
+                    (None, _) => value.clone(),
+                };
+                self.bind_name(binding, bound, &mut scope);
+            }
+            if let Some(guard) = &case.guard {
+                self.expression(guard, &scope);
+            }
+            let arm = case
+                .expression
+                .as_ref()
+                .map_or_else(Inferred::unit, |x| self.expression(x, &scope));
+            result = Some(match result {
+                Some(result) => result.join(arm),
+                None => arm,
+            });
+        }
+        result.unwrap_or_else(Inferred::any)
+    }
+
+    /// Mirrors the interpreter's indexing of tuples, enum types, and option types.
+    fn field(&mut self, container: Inferred, index: Token<'source>) -> Inferred {
+        enum Index<'source> {
+            Number(usize),
+            Name(Cow<'source, str>),
+        }
+        let index_value = match index.lexigram {
+            Lexigram::Number => parse_integer(index, self.source)
+                .ok()
+                .and_then(|i| usize::try_from(i).ok())
+                .map(Index::Number),
+            _ => index.resolve(self.source).ok().map(Index::Name),
+        };
+        let Some(index_value) = index_value else {
+            return Inferred::any();
+        };
+        /// Finds the field which `index` refers to among `len` fields with the given names.
+        fn position<'a>(
+            index: &Index,
+            len: usize,
+            mut names: impl Iterator<Item = &'a str>,
+        ) -> Option<usize> {
+            match index {
+                Index::Number(i) => (*i < len).then_some(*i),
+                Index::Name(name) => names.position(|n| n == name),
+            }
+        }
+        let (position, field) = match &container {
+            Inferred::Of(ComplexType::Simple(Type::Any | Type::Type), _)
+            | Inferred::Type(ComplexType::Simple(Type::Any)) => (None, Some(Inferred::any())),
+            Inferred::Of(ComplexType::Complex(tuple), _)
+            | Inferred::Type(ComplexType::Complex(tuple)) => {
+                let names = tuple.names().into_iter().flatten().map(|name| &**name);
+                let position = position(&index_value, tuple.len(), names);
+                let field = position
+                    .and_then(|i| tuple.value(i))
+                    .map(|field| container.with_field(field.clone()));
+                (position, field)
+            }
+            Inferred::Type(ComplexType::Simple(Type::Enum(definition))) => {
+                let names = definition.variants.iter().map(|(name, _)| &**name);
+                let position = position(&index_value, definition.variants.len(), names);
+                let field = position.map(|i| {
+                    let input = definition.variants[i].1.clone();
+                    Inferred::exact(function(input, Type::Enum(definition.clone()).into()))
+                });
+                (position, field)
+            }
+            Inferred::Type(ComplexType::Simple(Type::Option(ty))) => {
+                let position = position(&index_value, 2, ["Some", "None"].into_iter());
+                let field = position.map(|i| {
+                    let input = if i == 0 {
+                        (**ty).clone()
+                    } else {
+                        Type::Unit.into()
+                    };
+                    Inferred::exact(function(input, Type::Option(ty.clone()).into()))
+                });
+                (position, field)
+            }
+            _ => (None, None),
+        };
+        if let (Some(position), Index::Name(_)) = (position, &index_value)
+            && container.certainty() >= Certainty::Outer
+        {
+            self.types.fields.push((index, position));
+        }
+        field.unwrap_or_else(|| {
+            // A value which was only declared may have fields its declaration doesn't,
+            // since annotations ignore names and extra fields.
+            if container.certainty() < Certainty::Outer {
+                return Inferred::any();
+            }
+            self.report(Diagnostic::MissingField {
+                token: index,
+                container: container.type_of(),
+            });
+            Inferred::any()
+        })
+    }
+
+    fn binary(&mut self, node: &Node<'source>, l: Inferred, r: Inferred) -> Inferred {
+        let token = match node {
+            Node::Call(token) => return self.call(*token, l, r),
+            Node::Pipe(token) => return self.pipe(*token, r),
+            Node::Tuple(_) => return l.concat(r),
+            Node::EqualTo(_) | Node::NotEqualTo(_) => return Inferred::exact(Type::Bool),
+            // The right operand is not evaluated if the left operand decides the result.
+            Node::LogicalAnd(_) | Node::LogicalOr(_) => return Inferred::exact(Type::Bool),
+            Node::Add(token)
+            | Node::Sub(token)
+            | Node::Mul(token)
+            | Node::Div(token)
+            | Node::BitwiseAnd(token)
+            | Node::BitwiseOr(token)
+            | Node::BitwiseXor(token)
+            | Node::Greater(token)
+            | Node::GreaterEqual(token)
+            | Node::Lesser(token)
+            | Node::LesserEqual(token)
+            | Node::Range(token)
+            | Node::RangeInclusive(token) => *token,
+            _ => unreachable!("all other nodes are operands or unary operators"),
+        };
+        let integers_only = matches!(
+            node,
+            Node::BitwiseAnd(_)
+                | Node::BitwiseOr(_)
+                | Node::BitwiseXor(_)
+                | Node::Range(_)
+                | Node::RangeInclusive(_)
+        );
+        let (left, right) = (l.type_of(), r.type_of());
+        let number = |ty: &ComplexType| match ty {
+            ComplexType::Simple(Type::Any) => Some(None),
+            ComplexType::Simple(Type::I64) => Some(Some(Type::I64)),
+            ComplexType::Simple(Type::F64) if !integers_only => Some(Some(Type::F64)),
+            _ => None,
+        };
+        let result = match (number(&left), number(&right)) {
+            (Some(Some(l)), Some(Some(r))) if l != r => None,
+            (Some(l), Some(r)) => Some(l.or(r).unwrap_or(Type::Any)),
+            _ => None,
+        };
+        let Some(result) = result else {
+            self.report(Diagnostic::InvalidOperands { token, left, right });
+            return Inferred::any();
+        };
+        match node {
+            Node::Greater(_) | Node::GreaterEqual(_) | Node::Lesser(_) | Node::LesserEqual(_) => {
+                Inferred::exact(Type::Bool)
+            }
+            // Ranges produce iterators, whose state is opaque.
+            Node::Range(_) | Node::RangeInclusive(_) => Inferred::any(),
+            _ => Inferred::exact(result),
+        }
+    }
+
+    fn call(&mut self, token: Token<'source>, function: Inferred, argument: Inferred) -> Inferred {
+        match function {
+            Inferred::Option => match argument.into_type() {
+                Ok(ty) => Inferred::Type(Type::Option(Rc::new(ty)).into()),
+                Err(actual) => {
+                    self.report(Diagnostic::ExpectedType { token, actual });
+                    Inferred::any()
+                }
+            },
+            Inferred::Mut => match argument {
+                Inferred::Type(ty) => Inferred::Type(Type::Mut(Rc::new(ty)).into()),
+                // This may be a type or a value.
+                Inferred::Of(ComplexType::Simple(Type::Any | Type::Type), _) => Inferred::any(),
+                argument => Inferred::exact(Type::Mut(Rc::new(argument.type_of()))),
+            },
+            Inferred::Of(ComplexType::Simple(Type::Function(signature)), _) => {
+                let actual = argument.type_of();
+                if !actual.compare(&signature.input) {
+                    self.report(Diagnostic::Mismatch {
+                        token,
+                        expected: signature.input.clone(),
+                        actual,
+                    });
+                }
+                Inferred::Of(signature.output.clone(), Certainty::Declared)
+            }
+            Inferred::Of(ComplexType::Simple(Type::Any), _) => Inferred::any(),
+            function => {
+                self.report(Diagnostic::NotCallable {
+                    token,
+                    actual: function.type_of(),
+                });
+                Inferred::any()
+            }
+        }
+    }
+
+    fn pipe(&mut self, token: Token<'source>, function: Inferred) -> Inferred {
+        match function.type_of() {
+            // The argument is only complete once the pipeline is called,
+            // so only the result of the function is still known.
+            ComplexType::Simple(Type::Function(signature)) => {
+                Inferred::exact(self::function(Type::Any.into(), signature.output.clone()))
+            }
+            ComplexType::Simple(Type::Any) => Inferred::any(),
+            actual => {
+                self.report(Diagnostic::NotCallable { token, actual });
+                Inferred::any()
+            }
+        }
+    }
+}
+
+/// Whether a value is the constructor of an enum variant or option.
+fn is_constructor(value: &Inferred) -> bool {
+    matches!(
+        value,
+        Inferred::Of(ComplexType::Simple(Type::Function(signature)), _)
+            if matches!(signature.output, ComplexType::Simple(Type::Enum(_) | Type::Option(_)))
+    )
+}
+
+/// The contents of a variant created by `constructor`, if it is known.
+fn contents(constructor: &Inferred) -> Inferred {
+    match constructor {
+        Inferred::Of(ComplexType::Simple(Type::Function(signature)), _)
+            if is_constructor(constructor) =>
+        {
+            Inferred::exact(signature.input.clone())
+        }
+        _ => Inferred::any(),
+    }
+}
@@ -0,0 +1,51 @@
+//! Compares searching for named fields at runtime against indexing them by position.
+//!
+//! Run with `cargo bench -p espy`.
+
+#![feature(test)]
+
+extern crate test;
+
+use espy::{compiler, interpreter, lexer, parser};
+use std::{fmt::Write, rc::Rc};
+use test::Bencher;
+
+/// A script which builds a record with many fields and then reads them repeatedly.
+fn field_heavy_script() -> String {
+    let mut source = String::from("let record = ");
+    for i in 0..16 {
+        if i > 0 {
+            source.push_str(", ");
+        }
+        write!(source, "field{i}: {i}").unwrap();
+    }
+    source.push_str(";\n0");
+    for i in 0..1000 {
+        write!(source, " + record.field{}", 15 - i % 8).unwrap();
+    }
+    source
+}
+
+fn compile(source: &str, index_fields: bool) -> interpreter::Program {
+    let block = parser::Block::program(&mut lexer::Lexer::from(source).peekable());
+    let options = compiler::Options {
+        index_fields,
+        ..Default::default()
+    };
+    let bytecode = compiler::Program::with_options(source, block, options)
+        .unwrap()
+        .compile();
+    interpreter::Program::try_from(Rc::from(bytecode)).unwrap()
+}
+
+#[bench]
+fn named_fields(b: &mut Bencher) {
+    let program = compile(&field_heavy_script(), false);
+    b.iter(|| program.eval(0, &mut Vec::new()).unwrap());
+}
+
+#[bench]
+fn indexed_fields(b: &mut Bencher) {
+    let program = compile(&field_heavy_script(), true);
+    b.iter(|| program.eval(0, &mut Vec::new()).unwrap());
+}
@@ -11,6 +11,27 @@ pub use interpreter::{Error, Extern, ExternFn, ExternFnOwned, ExternOwned, Funct
 pub struct Program(interpreter::Program);
 
 impl Program {
+    /// Compiles `source` with the given compiler options.
+    ///
+    /// [`Program::try_from`] uses the default options,
+    /// which leave out doc comments and the source map.
+    pub fn with_options(
+        source: &str,
+        options: compiler::Options,
+    ) -> Result<Self, compiler::Error<'_>> {
+        compiler::Program::with_options(
+            source,
+            parser::Block::program(&mut lexer::Lexer::from(source).peekable()),
+            options,
+        )
+        .map(|program| {
+            Program(
+                interpreter::Program::try_from(Rc::from(program.compile()))
+                    .expect("textual programs may not produce invalid bytecode"),
+            )
+        })
+    }
+
     pub fn eval<'host>(&self) -> Result<Value<'host>, Error<'host>> {
         self.0.eval(0, &mut Vec::new())
     }
@@ -20,14 +41,7 @@ impl<'source> TryFrom<&'source str> for Program {
     type Error = compiler::Error<'source>;
 
     fn try_from(s: &'source str) -> Result<Self, Self::Error> {
-        compiler::Program::try_from(parser::Block::new(&mut lexer::Lexer::from(s).peekable())).map(
-            |program| {
-                Program(
-                    interpreter::Program::try_from(Rc::from(program.compile()))
-                        .expect("textual programs may not produce invalid bytecode"),
-                )
-            },
-        )
+        Program::with_options(s, compiler::Options::default())
     }
 }
 
@@ -42,6 +56,23 @@ mod tests {
         assert!(actual.eval().unwrap().eq(12.into()).unwrap())
     }
 
+    #[test]
+    fn floats() {
+        let actual = Program::try_from("let half = 0.5; (half + 1.0) * -2.0").unwrap();
+        println!("{actual:?}");
+        assert!(actual.eval().unwrap().eq((-3.0).into()).unwrap())
+    }
+
+    #[test]
+    fn mixed_numbers() {
+        let actual = Program::try_from("1 + 1.0").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::MismatchedNumbers(Value::I64(1), Value::F64(1.0)))
+        ))
+    }
+
     #[test]
     fn tuples() {
         let actual =
@@ -95,6 +126,256 @@ mod tests {
         assert!(value.eq(1.into()).unwrap());
     }
 
+    #[test]
+    fn doc_comments() {
+        let actual = Program::with_options(
+            "## Squares a number.
+            let square = {with x: i64 -> i64; x * x};
+            square",
+            compiler::Options {
+                doc_comments: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let Value::Function(function) = actual.eval().unwrap() else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.doc_comment(), Some("Squares a number."));
+    }
+
+    #[test]
+    fn refutable_bindings() {
+        let source = "let Result = enum Ok: i64, Err: unit end;
+            let unwrap_or = {
+                let Result = Result;
+                with (result, default);
+                let Result.Ok value = result else then default end;
+                value
+            };
+            let (1, x) = 1, 2;
+            let {y: Result.Err _} = y: (Result.Err ());
+            (unwrap_or ((Result.Ok 3), 0)), (unwrap_or ((Result.Err ()), x))";
+        assert!(
+            Program::try_from(source)
+                .unwrap()
+                .eval()
+                .unwrap()
+                .eq(Value::concat(3.into(), 2.into()))
+                .unwrap()
+        );
+        // The else branch ends the innermost block, discarding any values it has bound.
+        let source = "let f = {
+                with n;
+                let r = {
+                    let a = n;
+                    let 1 = a else then a * 10 end;
+                    a + 1
+                };
+                r * 2
+            };
+            (f 1), (f 2)";
+        assert!(
+            Program::try_from(source)
+                .unwrap()
+                .eval()
+                .unwrap()
+                .eq(Value::concat(4.into(), 40.into()))
+                .unwrap()
+        );
+        assert!(matches!(
+            Program::try_from("let (1, x) = 2, 3; x")
+                .unwrap()
+                .eval()
+                .map_err(Error::into_inner),
+            Err(Error::NoMatch(_))
+        ));
+        // Values without the bound fields, or which can't be compared to a literal, are refuted too.
+        for source in [
+            "let (1, x) = 7 else then 0 end; x",
+            "let (1, x) = (\"a\", 2) else then 0 end; x",
+            "let {y: 1, z} = (y: 1) else then 0 end; z",
+        ] {
+            let actual = Program::try_from(source).unwrap().eval().unwrap();
+            assert!(actual.eq(0.into()).unwrap(), "{source}");
+        }
+        assert!(matches!(
+            Program::try_from("let (1, x) = 7; x")
+                .unwrap()
+                .eval()
+                .map_err(Error::into_inner),
+            Err(Error::NoMatch(Value::I64(7)))
+        ));
+    }
+
+    #[test]
+    fn match_guards_and_types() {
+        let actual = Program::try_from(
+            "let describe = {
+                with x;
+                match x then
+                    let n: i64 if n > 0 => \"positive\";
+                    let _: i64 => \"not positive\";
+                    let _: (string, bool) => \"pair\";
+                    0.5 if false => \"unreachable\";
+                    let _ => \"other\";
+                end
+            };
+            \"{describe 1} {describe (-1)} {describe (\"s\", true)} {describe 0.5}\"",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::String("positive not positive pair other".into()))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn early_exits() {
+        let actual = Program::try_from(
+            "let validate = {
+                with x;
+                if x < 0 then return \"negative\" end;
+                let doubled = {
+                    let y = x * 2;
+                    if y > 10 then break \"large\" end;
+                    y
+                };
+                let nested = 'outer: {
+                    let a = 1;
+                    let b = {
+                        let c = 2;
+                        if x == 3 then break 'outer \"three\" end;
+                        c
+                    };
+                    a + b
+                };
+                \"{doubled} {nested}\"
+            };
+            \"{validate (-1)}, {validate 1}, {validate 3}, {validate 6}\"",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::String("negative, 2 3, 6 three, large 3".into()))
+                .unwrap()
+        );
+        assert!(matches!(
+            Program::try_from("break 1"),
+            Err(compiler::Error::InvalidBreak(_))
+        ));
+        assert!(matches!(
+            Program::try_from("{ break 'missing 1 }"),
+            Err(compiler::Error::UndefinedLabel(_))
+        ));
+    }
+
+    #[test]
+    fn constant_folding() {
+        let compile = |source, fold_constants| {
+            let options = compiler::Options {
+                fold_constants,
+                ..Default::default()
+            };
+            Program::with_options(source, options).unwrap()
+        };
+        for source in [
+            "60 * 60 * 24",
+            "let x = 2; (x: 1 + 1, y: 2.5 / 2.0).y, x * (3 - 1)",
+            "_: (1, 2), (), 3, (a: 1, b: 2)",
+            "(a: 1), (b: 2), c: 3",
+            "(a: 1, b: 2).0 == 1, !5, -(3), 7 ^ 2 | 8 & 12, !true",
+            "\"a\" == \"a\", () == (), 1 < 2, 2.0 >= 3.0, true or false, 1 != 1",
+            "if 1 + 1 == 2 then \"{60 * 60}\" else then \"no\" end",
+            "let f = {with x; x * (2 + 2)}; f 3",
+            "let O = option (a: i64, b: string); let s = O.Some (a: 1, b: \"s\"); let n = O.None (); s, n",
+            "let E = enum Some: any, None: unit, Pair: (i64, (option f64)) end;
+            let n = (option f64).None ();
+            E.Pair (1, n)",
+            "let i64 = string; option i64",
+            "let f = {with x: option i64; x}; f ((option i64).Some 1)",
+            // Each evaluation of an enum defines a distinct enum, even once folded.
+            "let X = enum A: i64 end; let Y = enum A: i64 end; let X.A v = Y.A 1 else then 0 end; v",
+            "let mk = {with x; enum A: i64 end}; let X = mk (); let Y = mk ();
+            let X.A v = Y.A 1 else then 0 end;
+            v",
+        ] {
+            let folded = format!("{:?}", compile(source, true).eval().unwrap());
+            let unfolded = format!("{:?}", compile(source, false).eval().unwrap());
+            assert_eq!(folded, unfolded, "{source}");
+        }
+    }
+
+    #[test]
+    fn field_indexing() {
+        let compile = |source, index_fields| {
+            let options = compiler::Options {
+                index_fields,
+                ..Default::default()
+            };
+            Program::with_options(source, options).unwrap()
+        };
+        for source in [
+            "let p = x: 1, y: 2; p.y, p.x",
+            "let t = (a: 1), (b: 2), c: 3; t.c, (t, d: 4).d",
+            // Arguments with differently named fields pass the annotation's check.
+            "let f = {with q: (x: i64, y: i64); q.x}; f (y: 1, x: 2)",
+            "let E = enum A: i64, B: (x: i64, y: i64) end;
+            match E.B (x: 1, y: 2) then
+                let a = E.A => a;
+                let b = E.B => b.y;
+            end",
+            "let O = option (a: i64, b: string);
+            let O.Some {b, ...rest} = O.Some (a: 1, b: \"s\");
+            b, rest.a",
+            // Early exits may give a block a value with differently ordered fields.
+            "let t = { if true then break (b: 10, a: 20) else then () end; (a: 1, b: 2) }; t.a",
+            "let t = { let 1 = 2 else then (b: 10, a: 20) end; (a: 1, b: 2) }; t.a",
+            "let f = {with x; if x then return (b: 10, a: 20) else then () end; (a: 1, b: 2)}; (f true).a",
+        ] {
+            let indexed = format!("{:?}", compile(source, true).eval().unwrap());
+            let searched = format!("{:?}", compile(source, false).eval().unwrap());
+            assert_eq!(indexed, searched, "{source}");
+        }
+    }
+
+    #[test]
+    fn error_locations() {
+        let options = || compiler::Options {
+            source_map: true,
+            ..Default::default()
+        };
+        let actual =
+            Program::with_options("let f = {\n  with x;\n  x + 1\n};\nf true", options()).unwrap();
+        let error = actual.eval().unwrap_err();
+        let location = *error.location().unwrap();
+        assert_eq!(location.block_id, 1);
+        let source = location.source.unwrap();
+        assert_eq!((source.line, source.column), (3, 5));
+        assert!(matches!(
+            error.into_inner(),
+            Error::ExpectedNumbers(Value::Bool(true), Value::I64(1))
+        ));
+
+        let actual =
+            Program::with_options("let x = 3;\nmatch x then 1 => 2; end", options()).unwrap();
+        let source = actual
+            .eval()
+            .unwrap_err()
+            .location()
+            .unwrap()
+            .source
+            .unwrap();
+        assert_eq!((source.line, source.column), (2, 1));
+    }
+
     #[test]
     fn options() {
         let actual = Program::try_from(
@@ -111,6 +392,233 @@ mod tests {
         )
     }
 
+    #[test]
+    fn rest_bindings() {
+        assert_eq!(
+            Program::try_from(
+                "let (head, ...tail) = 1, 2, 3;
+                let (second, ...empty) = tail;
+                let {name, ...rest} = name: \"espy\", x: 4, y: 5;
+                head + second + tail.1 + rest.x + rest.y"
+            )
+            .unwrap()
+            .eval()
+            .unwrap()
+            .into_i64()
+            .unwrap(),
+            15
+        );
+    }
+
+    #[test]
+    fn match_equality() {
+        let actual = Program::try_from(
+            "let x = 2; match x then 1 => \"one\"; 2 => \"two\"; let _ => \"many\"; end",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::String("two".into()))
+                .unwrap()
+        );
+        // Cases which can't be compared to the value don't accept it.
+        let actual = Program::try_from("match \"s\" then 4 => 1; let _ => 2; end").unwrap();
+        assert!(actual.eval().unwrap().eq(2.into()).unwrap());
+    }
+
+    #[test]
+    fn string_interpolation() {
+        let actual = Program::try_from(
+            "let name = \"world\"; let n = 1.0, 2; \"Hello, {name}! {n} {true} {\"{-3}\"}{()}\"",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::String("Hello, world! (1.0, 2) true -3()".into()))
+                .unwrap()
+        );
+        let actual = Program::try_from("\"\\{ {i64} \\}\"").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedPrintable(Value::Type(_)))
+        ));
+        assert!(matches!(
+            Program::try_from("\"1 + {1 +} = 2\""),
+            Err(compiler::Error::InvalidAst(
+                parser::Error::IncompleteExpression
+            ))
+        ));
+    }
+
+    #[test]
+    fn raw_strings() {
+        let actual = Program::try_from(r##"r#"\{"}"#"##).unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::String(r#"\{"}"#.into()))
+                .unwrap()
+        );
+        assert!(matches!(
+            Program::try_from(r##"r#"unterminated""##),
+            Err(compiler::Error::InvalidAst(parser::Error::Lexer(
+                lexer::Error {
+                    kind: lexer::ErrorKind::UnterminatedRawString,
+                    ..
+                }
+            )))
+        ));
+    }
+
+    #[test]
+    fn match_binding() {
+        let actual =
+            Program::try_from("match 3 then let x = 2 => x; let y => y * 10; end").unwrap();
+        println!("{actual:?}");
+        assert!(actual.eval().unwrap().eq(30.into()).unwrap())
+    }
+
+    #[test]
+    fn match_enum() {
+        let actual = Program::try_from(
+            "let Shape = enum Circle: i64, Square: i64 end;
+            let area = {
+                let Shape = Shape;
+                with shape;
+                match shape then
+                    let r = Shape.Circle => 3 * r * r;
+                    let s = Shape.Square => s * s;
+                end
+            };
+            (area (Shape.Circle 2)), (area (Shape.Square 5))",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::concat(12.into(), 25.into()))
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn match_option() {
+        let actual = Program::try_from(
+            "let OptionI64 = option i64;
+            let unwrap_or_zero = {
+                let OptionI64 = OptionI64;
+                with x;
+                match x then
+                    let x = OptionI64.Some => x;
+                    OptionI64.None => 0;
+                end
+            };
+            (unwrap_or_zero (OptionI64.Some 4)), (unwrap_or_zero (OptionI64.None ()))",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::concat(4.into(), 0.into()))
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn match_failure() {
+        let actual = Program::try_from("match 3 then 1 => 1; 2 => 2; end").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::NoMatch(Value::I64(3)))
+        ))
+    }
+
+    #[test]
+    fn short_circuit() {
+        let actual = Program::try_from(
+            "let x = false and (1 + true);
+            let y = true or (1 + true);
+            let z = true and false or true;
+            x, y, z",
+        )
+        .unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::concat(
+                    Value::concat(false.into(), true.into()),
+                    true.into()
+                ))
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn logical_type_errors() {
+        let actual = Program::try_from("1 and true").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedNumbers(Value::I64(1), Value::Bool(true)))
+        ));
+        let actual = Program::try_from("true and 2").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedNumbers(Value::Bool(true), Value::I64(2)))
+        ));
+        let actual = Program::try_from("false or 2").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedNumbers(Value::Bool(false), Value::I64(2)))
+        ));
+        let actual = Program::try_from("1 or true").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedNumbers(Value::I64(1), Value::Bool(true)))
+        ));
+    }
+
+    #[test]
+    fn not() {
+        let actual = Program::try_from("!false, !!true, !0, !0x0F").unwrap();
+        println!("{actual:?}");
+        assert!(
+            actual
+                .eval()
+                .unwrap()
+                .eq(Value::concat(
+                    Value::concat(Value::concat(true.into(), true.into()), (-1).into()),
+                    (-16).into()
+                ))
+                .unwrap()
+        );
+        let actual = Program::try_from("!\"string\"").unwrap();
+        println!("{actual:?}");
+        assert!(matches!(
+            actual.eval().map_err(Error::into_inner),
+            Err(Error::ExpectedBoolOrInteger(Value::String(_)))
+        ));
+    }
+
     #[test]
     fn rust_function() {
         struct F;
@@ -351,4 +859,62 @@ mod tests {
             55
         );
     }
+
+    #[test]
+    fn ranges() {
+        #[derive(Default)]
+        struct Sum;
+
+        impl ExternFn for Sum {
+            fn call<'host>(&'host self, args: Value<'host>) -> Result<Value<'host>, Error<'host>> {
+                let mut iterator = args.get(0)?;
+                let next = args.get(1)?.into_function()?;
+                let mut sum = 0;
+                while let Some(result) = next.clone().piped(iterator).eval()?.into_option()? {
+                    iterator = result.get(0)?;
+                    sum += result.get(1)?.into_i64()?;
+                }
+                Ok(sum.into())
+            }
+        }
+
+        let sum = Sum;
+
+        assert!(
+            Function::try_from(
+                Program::try_from(
+                    "with sum;
+                    let a = sum (0..10);
+                    let b = sum (1..=10);
+                    let c = sum (5..0);
+                    let d = sum (3..=3);
+                    a, b, c, d",
+                )
+                .unwrap()
+                .eval()
+                .unwrap(),
+            )
+            .unwrap()
+            .piped(Function::borrow(&sum).into())
+            .eval()
+            .unwrap()
+            .eq(Value::concat(
+                Value::concat(Value::concat(45.into(), 55.into()), 0.into()),
+                3.into()
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn trailing_input() {
+        assert!(matches!(
+            Program::try_from("let x = 1; x)"),
+            Err(compiler::Error::InvalidAst(_))
+        ));
+        assert!(matches!(
+            Program::try_from("1 }"),
+            Err(compiler::Error::InvalidAst(parser::Error::TrailingInput(_)))
+        ));
+    }
 }
@@ -1,5 +1,8 @@
 use espy::lexer;
-use espy::parser::{Block, BlockResult, Error, Expression, Statement};
+use espy::parser::{
+    Block, Enum, Error, Evaluation, Expression, Function, If, Interpolation, Match, Set,
+    visit::{self, Visitor},
+};
 use std::fmt::Write;
 
 // While this is comprehensive, lexigrams unused by `expect` have less thought put into them.
@@ -10,6 +13,7 @@ fn format_lexigram(mut f: impl Write, lexigram: lexer::Lexigram) {
         lexer::Lexigram::And => write!(f, "and"),
         lexer::Lexigram::BangEqual => write!(f, "!="), // symbol only
         lexer::Lexigram::Bang => write!(f, "! (bang)"),
+        lexer::Lexigram::Break => write!(f, "break"),
         lexer::Lexigram::Caret => write!(f, "^ (caret)"),
         lexer::Lexigram::CloseBrace => write!(f, "}} (closing curly brace)"),
         lexer::Lexigram::CloseParen => write!(f, ") (closing parenthesis)"),
@@ -33,6 +37,7 @@ fn format_lexigram(mut f: impl Write, lexigram: lexer::Lexigram) {
         lexer::Lexigram::If => write!(f, "if"),
         lexer::Lexigram::LesserEqual => write!(f, "lesserequal"), // symbol only
         lexer::Lexigram::Lesser => write!(f, "lesser"),           // symbol only
+        lexer::Lexigram::Label => write!(f, "label"),
         lexer::Lexigram::Let => write!(f, "let"),
         lexer::Lexigram::Match => write!(f, "match"),
         lexer::Lexigram::Minus => write!(f, "minus"), // symbol only
@@ -43,6 +48,7 @@ fn format_lexigram(mut f: impl Write, lexigram: lexer::Lexigram) {
         lexer::Lexigram::Or => write!(f, "or"),
         lexer::Lexigram::Pipe => write!(f, "|"), // symbol only
         lexer::Lexigram::Plus => write!(f, "plus"), // symbol only
+        lexer::Lexigram::Return => write!(f, "return"),
         lexer::Lexigram::Semicolon => write!(f, "; (semicolon)"),
         lexer::Lexigram::Set => write!(f, "set"),
         lexer::Lexigram::SingleArrow => write!(f, "-> (single arrow)"),
@@ -50,6 +56,10 @@ fn format_lexigram(mut f: impl Write, lexigram: lexer::Lexigram) {
         lexer::Lexigram::Slash => write!(f, "slash"), // symbol only
         lexer::Lexigram::Star => write!(f, "star"),   // symbol only
         lexer::Lexigram::String => write!(f, "string"),
+        lexer::Lexigram::StringStart => write!(f, "interpolated string"),
+        lexer::Lexigram::StringMiddle | lexer::Lexigram::StringEnd => {
+            write!(f, "}} (end of interpolated expression)")
+        }
         lexer::Lexigram::Then => write!(f, "then"),
         lexer::Lexigram::Triangle => write!(f, "triangle"), // symbol only
         lexer::Lexigram::True => write!(f, "true"),
@@ -57,6 +67,7 @@ fn format_lexigram(mut f: impl Write, lexigram: lexer::Lexigram) {
     };
 }
 
+#[derive(Clone)]
 pub struct Comment {
     pub message: String,
     pub range: Option<(usize, usize)>,
@@ -71,42 +82,92 @@ impl Diagnostic {
     fn from_error(error: &Error<'_>, source: &str) -> Self {
         match error {
             Error::Lexer(lexer::Error {
-                origin,
+                span,
                 kind: lexer::ErrorKind::UnexpectedCharacter,
             }) => Self {
                 primary: Comment {
-                    message: format!("unexpected character: {origin}"),
-                    range: Some(origin_range(origin, source)),
+                    message: format!("unexpected character: {}", span.slice(source)),
+                    range: Some(origin_range(*span)),
                 },
                 secondary: Vec::new(),
             },
             Error::Lexer(lexer::Error {
-                origin,
+                span,
                 kind: lexer::ErrorKind::ReservedSymbol,
             }) => Self {
                 primary: Comment {
-                    message: format!("reserved symbol: {origin}"),
-                    range: Some(origin_range(origin, source)),
+                    message: format!("reserved symbol: {}", span.slice(source)),
+                    range: Some(origin_range(*span)),
                 },
                 secondary: Vec::new(),
             },
             Error::Lexer(lexer::Error {
-                origin,
+                span,
                 kind: lexer::ErrorKind::UnterminatedString,
             }) => Self {
                 primary: Comment {
                     message: "unterminated string".to_string(),
-                    range: Some(origin_range(origin, source)),
+                    range: Some(origin_range(*span)),
                 },
                 secondary: Vec::new(),
             },
             Error::Lexer(lexer::Error {
-                origin,
+                span,
+                kind: lexer::ErrorKind::UnterminatedRawString,
+            }) => Self {
+                primary: Comment {
+                    message: "unterminated raw string".to_string(),
+                    range: Some(origin_range(*span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::Lexer(lexer::Error {
+                span,
                 kind: lexer::ErrorKind::UnterminatedIdentifier,
             }) => Self {
                 primary: Comment {
                     message: "unterminated identifier".to_string(),
-                    range: Some(origin_range(origin, source)),
+                    range: Some(origin_range(*span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::Lexer(lexer::Error {
+                span,
+                kind: lexer::ErrorKind::SourceTooLarge,
+            }) => Self {
+                primary: Comment {
+                    message: "source is larger than 4GiB".to_string(),
+                    range: Some(origin_range(*span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::Lexer(lexer::Error {
+                span,
+                kind: lexer::ErrorKind::InvalidDigit,
+            }) => Self {
+                primary: Comment {
+                    message: format!("invalid digit in number literal: {}", span.slice(source)),
+                    range: Some(origin_range(*span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::Lexer(lexer::Error {
+                span,
+                kind: lexer::ErrorKind::MissingDigits,
+            }) => Self {
+                primary: Comment {
+                    message: "number literal has no digits after its prefix".to_string(),
+                    range: Some(origin_range(*span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::Lexer(lexer::Error {
+                span,
+                kind: lexer::ErrorKind::InterpolationTooDeep,
+            }) => Self {
+                primary: Comment {
+                    message: "interpolated strings are nested too deeply".to_string(),
+                    range: Some(origin_range(*span)),
                 },
                 secondary: Vec::new(),
             },
@@ -133,7 +194,7 @@ impl Diagnostic {
                     primary: Comment {
                         message,
                         range: if let Some(actual) = actual {
-                            Some(origin_range(actual.origin, source))
+                            Some(origin_range(actual.span))
                         } else {
                             Some((source.len(), source.len()))
                         },
@@ -144,7 +205,25 @@ impl Diagnostic {
             Error::UnexpectedCloseParen(token) => Self {
                 primary: Comment {
                     message: "unexpected closing parenthesis".to_string(),
-                    range: Some(origin_range(token.origin, source)),
+                    range: Some(origin_range(token.span)),
+                },
+                secondary: Vec::new(),
+            },
+            Error::UnexpectedToken(token) => {
+                let mut message = "unexpected ".to_string();
+                format_lexigram(&mut message, token.lexigram);
+                Self {
+                    primary: Comment {
+                        message,
+                        range: Some(origin_range(token.span)),
+                    },
+                    secondary: Vec::new(),
+                }
+            }
+            Error::TrailingInput(token) => Self {
+                primary: Comment {
+                    message: "unexpected token after end of program".to_string(),
+                    range: Some(origin_range(token.span)),
                 },
                 secondary: Vec::new(),
             },
@@ -166,23 +245,18 @@ impl Diagnostic {
     }
 }
 
-pub fn origin_range(origin: &str, source: &str) -> (usize, usize) {
-    let start = origin.as_ptr() as isize - source.as_ptr() as isize;
-    let end = start + origin.len() as isize;
-    if start < 0 || end - start > source.len() as isize {
-        panic!("source string does not contain token origin");
-    }
-    (start as usize, end as usize)
+pub fn origin_range(span: lexer::Span) -> (usize, usize) {
+    (span.start as usize, span.end() as usize)
 }
 
-fn expression_origin(expression: &Expression<'_>, source: &str) -> Option<(usize, usize)> {
+fn expression_origin(expression: &Expression<'_>) -> Option<(usize, usize)> {
     expression.first_token.map(|first_token| {
-        let first_range = origin_range(first_token.origin, source);
+        let first_range = origin_range(first_token.span);
         (
             first_range.0,
             expression
                 .last_token
-                .map(|last_token| origin_range(last_token.origin, source).1)
+                .map(|last_token| origin_range(last_token.span).1)
                 // I think this can only ever be reached by an incomplete
                 // expression consisting of only one token.
                 .unwrap_or(first_range.1),
@@ -190,177 +264,170 @@ fn expression_origin(expression: &Expression<'_>, source: &str) -> Option<(usize
     })
 }
 
-pub fn for_each(source: &str, block: &Block, mut for_each: impl FnMut(Diagnostic)) {
-    diagnose_block(source, block, &mut for_each);
+pub fn for_each(source: &str, block: &Block, for_each: impl FnMut(Diagnostic)) {
+    Diagnoser { source, for_each }.visit_block(block);
 }
 
-fn diagnose_block(source: &str, block: &Block, for_each: &mut impl FnMut(Diagnostic)) {
-    for error in &block.diagnostics.errors {
-        for_each(Diagnostic::from_error(error, source));
-    }
-    for statement in &block.statements {
-        diagnose_statement(source, statement, &mut *for_each)
-    }
-    match &block.result {
-        BlockResult::Expression(expression) => {
-            diagnose_expression(source, expression, &mut *for_each)
-        }
-        BlockResult::Function(function) => {
-            for error in &function.diagnostics.errors {
-                for_each(Diagnostic::from_error(error, source));
+struct Diagnoser<'a, F> {
+    source: &'a str,
+    for_each: F,
+}
+
+impl<F: FnMut(Diagnostic)> Diagnoser<'_, F> {
+    fn emit(&mut self, errors: &[Error], context: Option<Comment>) {
+        for error in errors {
+            let mut diagnostic = Diagnostic::from_error(error, self.source);
+            if let Some(context) = &context {
+                diagnostic.secondary.push(context.clone());
             }
-            diagnose_block(source, &function.block, &mut *for_each);
+            (self.for_each)(diagnostic);
         }
     }
 }
 
-fn diagnose_statement(source: &str, statement: &Statement, for_each: &mut impl FnMut(Diagnostic)) {
-    match statement {
-        Statement::Evaluation(evaluation) => {
-            let let_range = evaluation
-                .binding
-                .as_ref()
-                .map(|binding| origin_range(binding.let_token.origin, source));
-            let anchored_range = evaluation
-                .expression
-                .as_ref()
-                .and_then(|x| x.first_token)
-                .map(|first_token| {
-                    let (first, last) = origin_range(first_token.origin, source);
-                    (let_range.map_or(first, |(x, _)| x), last)
-                })
-                .or(let_range);
-            for error in &evaluation.diagnostics.errors {
-                let mut diagnostic = Diagnostic::from_error(error, source);
-                if let Some(anchored_range) = anchored_range
-                    && diagnostic
-                        .primary
-                        .range
-                        .is_none_or(|range| range.0 > anchored_range.1)
-                {
-                    diagnostic.secondary.push(Comment {
-                        message: if evaluation.binding.is_some() {
-                            "for this binding"
-                        } else {
-                            "for this expression"
-                        }
-                        .to_string(),
-                        range: Some(anchored_range),
-                    })
-                }
-                for_each(diagnostic);
-            }
-            diagnose_expression(source, &evaluation.expression, &mut *for_each);
-        }
-        Statement::Set(set) => {
-            let set_range = origin_range(set.set_token.origin, source);
-            let anchored_range = set
-                .expression
-                .as_ref()
-                .and_then(|x| x.first_token)
-                .map(|first_token| {
-                    let (_, last) = origin_range(first_token.origin, source);
-                    (set_range.0, last)
-                })
-                .unwrap_or(set_range);
-            for error in &set.diagnostics.errors {
-                let mut diagnostic = Diagnostic::from_error(error, source);
-                if diagnostic
+impl<'source, F: FnMut(Diagnostic)> Visitor<'source> for Diagnoser<'_, F> {
+    fn visit_block(&mut self, block: &Block<'source>) {
+        self.emit(&block.diagnostics.errors, None);
+        visit::walk_block(self, block);
+    }
+
+    fn visit_function(&mut self, function: &Function<'source>) {
+        self.emit(&function.diagnostics.errors, None);
+        visit::walk_function(self, function);
+    }
+
+    fn visit_evaluation(&mut self, evaluation: &Evaluation<'source>) {
+        let let_range = evaluation
+            .binding
+            .as_ref()
+            .map(|binding| origin_range(binding.let_token.span));
+        let anchored_range = evaluation
+            .expression
+            .as_ref()
+            .and_then(|x| x.first_token)
+            .map(|first_token| {
+                let (first, last) = origin_range(first_token.span);
+                (let_range.map_or(first, |(x, _)| x), last)
+            })
+            .or(let_range);
+        for error in &evaluation.diagnostics.errors {
+            let mut diagnostic = Diagnostic::from_error(error, self.source);
+            if let Some(anchored_range) = anchored_range
+                && diagnostic
                     .primary
                     .range
                     .is_none_or(|range| range.0 > anchored_range.1)
-                {
-                    diagnostic.secondary.push(Comment {
-                        message: "for this assignment".to_string(),
-                        range: Some(anchored_range),
-                    })
-                }
-                for_each(diagnostic);
+            {
+                diagnostic.secondary.push(Comment {
+                    message: if evaluation.binding.is_some() {
+                        "for this binding"
+                    } else {
+                        "for this expression"
+                    }
+                    .to_string(),
+                    range: Some(anchored_range),
+                })
             }
-            diagnose_expression(source, &set.target, &mut *for_each);
-            diagnose_expression(source, &set.expression, &mut *for_each);
+            (self.for_each)(diagnostic);
         }
+        visit::walk_evaluation(self, evaluation);
     }
-}
 
-fn diagnose_expression(
-    source: &str,
-    expression: &Option<Box<Expression>>,
-    for_each: &mut impl FnMut(Diagnostic),
-) {
-    let Some(expression) = expression else {
-        return;
-    };
-    let range = expression_origin(expression, source);
-    for error in &expression.diagnostics.errors {
-        let mut diagnostic = Diagnostic::from_error(error, source);
-        if let Some(range) = range {
-            diagnostic.secondary.push(Comment {
-                message: "in this expression".to_string(),
-                range: Some(range),
+    fn visit_set(&mut self, set: &Set<'source>) {
+        let set_range = origin_range(set.set_token.span);
+        let anchored_range = set
+            .expression
+            .as_ref()
+            .and_then(|x| x.first_token)
+            .map(|first_token| {
+                let (_, last) = origin_range(first_token.span);
+                (set_range.0, last)
             })
+            .unwrap_or(set_range);
+        for error in &set.diagnostics.errors {
+            let mut diagnostic = Diagnostic::from_error(error, self.source);
+            if diagnostic
+                .primary
+                .range
+                .is_none_or(|range| range.0 > anchored_range.1)
+            {
+                diagnostic.secondary.push(Comment {
+                    message: "for this assignment".to_string(),
+                    range: Some(anchored_range),
+                })
+            }
+            (self.for_each)(diagnostic);
         }
-        for_each(diagnostic);
+        visit::walk_set(self, set);
     }
-    for node in &expression.contents {
-        match node {
-            espy::parser::Node::Block(block) => diagnose_block(source, block, for_each),
-            espy::parser::Node::If(if_node) => {
-                let mut range = origin_range(if_node.if_token.origin, source);
-                if let Some(token) = if_node
-                    .end_token
-                    .or(if_node.else_token)
-                    .or(if_node.then_token)
-                {
-                    range.1 = origin_range(token.origin, source).1;
-                }
-                for error in &if_node.diagnostics.errors {
-                    let mut diagnostic = Diagnostic::from_error(error, source);
-                    diagnostic.secondary.push(Comment {
-                        message: "in this conditional block".to_string(),
-                        range: Some(range),
-                    });
-                    for_each(diagnostic);
-                }
-                diagnose_expression(source, &if_node.condition, for_each);
-                diagnose_block(source, &if_node.first, for_each);
-                diagnose_block(source, &if_node.second, for_each);
-            }
-            espy::parser::Node::Match(match_node) => {
-                let mut range = origin_range(match_node.match_token.origin, source);
-                if let Some(token) = match_node.end_token.or(match_node.then_token) {
-                    range.1 = origin_range(token.origin, source).1;
-                }
-                for error in &match_node.diagnostics.errors {
-                    let mut diagnostic = Diagnostic::from_error(error, source);
-                    diagnostic.secondary.push(Comment {
-                        message: "in this match block".to_string(),
-                        range: Some(range),
-                    });
-                    for_each(diagnostic);
-                }
-                diagnose_expression(source, &match_node.expression, for_each);
-                for case in &match_node.cases {
-                    diagnose_expression(source, &case.case, for_each);
-                    diagnose_expression(source, &case.expression, for_each);
-                }
-            }
-            espy::parser::Node::Enum(enum_node) => {
-                let mut range = origin_range(enum_node.enum_token.origin, source);
-                if let Some(token) = enum_node.end_token {
-                    range.1 = origin_range(token.origin, source).1;
-                }
-                for error in &enum_node.diagnostics.errors {
-                    let mut diagnostic = Diagnostic::from_error(error, source);
-                    diagnostic.secondary.push(Comment {
-                        message: "in this structure definition".to_string(),
-                        range: Some(range),
-                    });
-                    for_each(diagnostic);
-                }
-                diagnose_expression(source, &enum_node.variants, for_each);
-            }
-            _ => {}
+
+    fn visit_expression(&mut self, expression: &Expression<'source>) {
+        let context = expression_origin(expression).map(|range| Comment {
+            message: "in this expression".to_string(),
+            range: Some(range),
+        });
+        self.emit(&expression.diagnostics.errors, context);
+        visit::walk_expression(self, expression);
+    }
+
+    fn visit_if(&mut self, if_node: &If<'source>) {
+        let mut range = origin_range(if_node.if_token.span);
+        if let Some(token) = if_node
+            .end_token
+            .or(if_node.else_token)
+            .or(if_node.then_token)
+        {
+            range.1 = origin_range(token.span).1;
+        }
+        let context = Comment {
+            message: "in this conditional block".to_string(),
+            range: Some(range),
+        };
+        self.emit(&if_node.diagnostics.errors, Some(context));
+        visit::walk_if(self, if_node);
+    }
+
+    fn visit_match(&mut self, match_node: &Match<'source>) {
+        let mut range = origin_range(match_node.match_token.span);
+        if let Some(token) = match_node.end_token.or(match_node.then_token) {
+            range.1 = origin_range(token.span).1;
+        }
+        let context = Comment {
+            message: "in this match block".to_string(),
+            range: Some(range),
+        };
+        self.emit(&match_node.diagnostics.errors, Some(context));
+        visit::walk_match(self, match_node);
+    }
+
+    fn visit_enum(&mut self, enum_node: &Enum<'source>) {
+        let mut range = origin_range(enum_node.enum_token.span);
+        if let Some(token) = enum_node.end_token {
+            range.1 = origin_range(token.span).1;
+        }
+        let context = Comment {
+            message: "in this structure definition".to_string(),
+            range: Some(range),
+        };
+        self.emit(&enum_node.diagnostics.errors, Some(context));
+        visit::walk_enum(self, enum_node);
+    }
+
+    fn visit_interpolation(&mut self, interpolation: &Interpolation<'source>) {
+        let mut range = origin_range(interpolation.start.span);
+        if let Some(token) = interpolation
+            .segments
+            .iter()
+            .rev()
+            .find_map(|segment| segment.string)
+        {
+            range.1 = origin_range(token.span).1;
         }
+        let context = Comment {
+            message: "in this interpolated string".to_string(),
+            range: Some(range),
+        };
+        self.emit(&interpolation.diagnostics.errors, Some(context));
+        visit::walk_interpolation(self, interpolation);
     }
 }
@@ -81,24 +81,8 @@ impl espy::ExternFn for PrintFn {
 
 /// Returns a tuple of two tuples containing the line and column number for the start and end positions, respectively.
 pub fn find_location(start: usize, end: usize, source: &str) -> ((usize, usize), (usize, usize)) {
-    // Count all newlines that occur before the starting position...
-    let start_line = 1 + source[..start].chars().filter(|c| *c == '\n').count();
-    // ...and all characters that come before the start character and the last newline.
-    let start_column = 1 + source[..start]
-        .chars()
-        .rev()
-        .take_while(|c| *c != '\n')
-        .count();
-    // This is only counting the number of newlines between the start and end,
-    // and adding it the the starting position's line number.
-    let end_line = start_line + source[start..end].chars().filter(|c| *c == '\n').count();
-    // Unlike end_line, end_column cannot be offset by start_column because the start and end lines may differ.
-    let end_column = 1 + source[..end]
-        .chars()
-        .rev()
-        .take_while(|c| *c != '\n')
-        .count();
-    ((start_line, start_column), (end_line, end_column))
+    let source = espy::lexer::SourceFile::new(source);
+    (source.location(start as u32), source.location(end as u32))
 }
 
 /// Returns the lines containing the provided range.
@@ -173,7 +157,7 @@ impl std::fmt::Display for MaybeSnippetFmt<'_> {
 
 #[wasm_bindgen]
 pub fn espy_eval(source: &str) -> String {
-    let ast = espy::parser::Block::new(&mut espy::lexer::Lexer::from(source).peekable());
+    let ast = espy::parser::Block::program(&mut espy::lexer::Lexer::from(source).peekable());
     let mut parser_diagnostics = None;
     diagnostics::for_each(source, &ast, |diagnostic| {
         let f =
@@ -197,7 +181,7 @@ pub fn espy_eval(source: &str) -> String {
         return parser_diagnostics;
     }
 
-    match espy::compiler::Program::try_from(ast) {
+    match espy::compiler::Program::new(source, ast) {
         Ok(program) => match espy::interpreter::Program::try_from(Rc::from(program.compile())).expect("textual programs may not produce invalid bytecode").eval(0, &mut Vec::new()) {
             Ok(result) => match espy::Function::try_from(result) {
                 Ok(function) => {
@@ -239,32 +223,45 @@ pub fn espy_eval(source: &str) -> String {
                     .to_string()
             }
             espy::compiler::Error::InvalidBreak(token) => {
-                let snippet = SnippetFmt::new(origin_range(token.origin, source), source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
                 format!(
                     "<p id=\"compile-error\">Attempted to break out of a scope, but no parent scope accepted unlabeled breaks.{snippet}</p>"
                 )
             }
+            espy::compiler::Error::UndefinedLabel(token) => {
+                let label = token.origin(source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
+                format!(
+                    "<p id=\"compile-error\">Undefined label: {label}.{snippet}</p>"
+                )
+            }
             espy::compiler::Error::InvalidInteger(token, e) => {
-                let snippet = SnippetFmt::new(origin_range(token.origin, source), source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
                 format!(
                     "<p id=\"compile-error\">Invalid integer literal: {e}.{snippet}</p>"
                 )
             }
+            espy::compiler::Error::InvalidFloat(token, e) => {
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
+                format!(
+                    "<p id=\"compile-error\">Invalid floating point literal: {e}.{snippet}</p>"
+                )
+            }
             espy::compiler::Error::InvalidString(token, e) => {
-                let snippet = SnippetFmt::new(origin_range(token.origin, source), source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
                 format!(
                     "<p id=\"compile-error\">Invalid string literal: {e:?}.{snippet}</p>"
                 )
             }
             espy::compiler::Error::InvalidIdentifier(token, e) => {
-                let snippet = SnippetFmt::new(origin_range(token.origin, source), source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
                 format!(
                     "<p id=\"compile-error\">Invalid raw identifier: {e:?}.{snippet}</p>"
                 )
             }
             espy::compiler::Error::UndefinedSymbol(token) => {
-                let symbol = token.origin;
-                let snippet = SnippetFmt::new(origin_range(token.origin, source), source);
+                let symbol = token.origin(source);
+                let snippet = SnippetFmt::new(origin_range(token.span), source);
                 format!(
                     "<p id=\"compile-error\">Undefined symbol: {symbol}.{snippet}</p>"
                 )
@@ -1,9 +1,21 @@
-use clap::{Args, Parser};
-use std::{fs, path::Path};
+use clap::{Args, Parser, Subcommand};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 #[derive(Parser)]
-#[clap(version, about)]
+#[clap(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[clap(subcommand)]
+    subcommand: Option<Command>,
+
     #[clap(flatten)]
     input: Input,
 }
@@ -17,8 +29,57 @@ struct Input {
     program: Option<Box<Path>>,
 }
 
-fn main() {
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite programs in the canonical style.
+    Fmt {
+        /// Report programs which are not formatted instead of rewriting them.
+        #[clap(long)]
+        check: bool,
+
+        #[clap(required = true)]
+        programs: Vec<PathBuf>,
+    },
+}
+
+fn fmt(check: bool, programs: &[PathBuf]) -> ExitCode {
+    let mut status = ExitCode::SUCCESS;
+    for program in programs {
+        let source = match fs::read_to_string(program) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {e}", program.display());
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let formatted = match espy::parser::format(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}: failed to parse: {e:?}", program.display());
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("{}: not formatted", program.display());
+            status = ExitCode::FAILURE;
+        } else if let Err(e) = fs::write(program, formatted) {
+            eprintln!("{}: {e}", program.display());
+            status = ExitCode::FAILURE;
+        }
+    }
+    status
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    if let Some(Command::Fmt { check, programs }) = cli.subcommand {
+        return fmt(check, &programs);
+    }
     let source = if let Some(program) = cli.input.program {
         fs::read_to_string(program).unwrap().into_boxed_str()
     } else {
@@ -28,4 +89,5 @@ fn main() {
     };
     let result = espy::Program::try_from(&*source).unwrap().eval().unwrap();
     println!("{result:?}");
+    ExitCode::SUCCESS
 }